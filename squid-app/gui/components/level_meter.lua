@@ -22,9 +22,17 @@ function LevelMeter:new(prop)
     obj.yellow_threshold = prop.yellow_threshold or 0.6
     obj.red_threshold = prop.red_threshold or 0.9
 
-    obj.value = prop.value or math.random()
+    obj.value = prop.value or 0
     obj.radius = prop.radius or 6
-    obj.head_value = math.random()
+    obj.head_value = 0
+
+    -- name passed to engine.get_meter, without it the level is set by hand
+    obj.meter = prop.meter
+    obj.channel = prop.channel or "l"
+    -- "peak", "true_peak" or "rms"
+    obj.mode = prop.mode or "peak"
+    obj.min_db = prop.min_db or -60
+    obj.max_db = prop.max_db or 0
 
     obj.peak_hold_delay = prop.peak_hold_delay or 1.0
     obj.peak_hold_timer = 0
@@ -51,7 +59,18 @@ function LevelMeter:set_level(value)
     end
 end
 
+function LevelMeter:db_to_level(db)
+    return (db - self.min_db) / (self.max_db - self.min_db)
+end
+
 function LevelMeter:update(dt)
+    if self.meter then
+        local readings = engine.get_meter(self.meter)
+        if readings then
+            self:set_level(self:db_to_level(readings[self.mode .. "_" .. self.channel]))
+        end
+    end
+
     if self.peak_hold_timer > 0 then
         self.peak_hold_timer = self.peak_hold_timer - dt
     else
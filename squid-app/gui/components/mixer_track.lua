@@ -16,6 +16,7 @@ function MixerTrack:new(prop)
     local obj = BaseComponent.new(self, prop)
 
     obj.name = prop.name or ""
+    obj.meter = prop.meter
 
     obj.child = Container:new({
         x = obj.x,
@@ -37,9 +38,9 @@ function MixerTrack:new(prop)
 
                         }, Row:new({
                             children = {
-                                LMeter:new({ width = Size.relative(0.35), height = Size.relative(1) }),
+                                LMeter:new({ width = Size.relative(0.35), height = Size.relative(1), meter = obj.meter, channel = "l" }),
                                 Container:new({ width = Size.relative(0.3), height = Size.relative(1) }),
-                                LMeter:new({ width = Size.relative(0.35), height = Size.relative(1) })
+                                LMeter:new({ width = Size.relative(0.35), height = Size.relative(1), meter = obj.meter, channel = "r" })
                             }
                         }))
 
@@ -1,5 +1,6 @@
 require("gui/components/container")
 require("gui/components/oscilloscope")
+require("gui/components/spectrum")
 require("gui/components/vectorscope")
 
 require("gui/components/row")
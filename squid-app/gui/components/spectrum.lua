@@ -0,0 +1,106 @@
+require("gui/globals")
+local draw = require("gui/helpers/drawing")
+local BaseComponent = require("gui/core/base_component");
+
+Spectrum = BaseComponent:extend()
+
+
+function Spectrum:new(prop)
+    prop = prop or {}
+    local obj = BaseComponent.new(self, prop)
+    obj.bg = prop.bg or { r = 0, g = 0, b = 0, a = 255 }
+    obj.color = prop.color or { r = 255, g = 255, b = 255, a = 255 }
+    obj.peak_color = prop.peak_color or { r = 255, g = 190, b = 0, a = 200 }
+    obj.grid_color = prop.grid_color or { r = 255, g = 255, b = 255, a = 25 }
+    obj.border_width = prop.border_width or 0
+    obj.border_color = prop.border_color or { r = 255, g = 255, b = 255, a = 0 }
+    obj.border_radius = prop.border_radius or 0
+    obj.thickness = prop.thickness or 2
+
+    obj.bins = prop.bins or 64
+    obj.min_freq = prop.min_freq or 20
+    obj.max_freq = prop.max_freq or 20000
+    obj.min_db = prop.min_db or -90
+    obj.max_db = prop.max_db or 0
+    obj.grid_step_db = prop.grid_step_db or 12
+    obj.show_peaks = prop.show_peaks ~= false
+
+    -- seconds to reach a new level, rising and falling
+    obj.attack = prop.attack or 0.02
+    obj.release = prop.release or 0.25
+
+    obj.levels = {}
+    obj.peaks = {}
+    return obj
+end
+
+function Spectrum:register_permanent() end
+
+function Spectrum:register_interactive() end
+
+function Spectrum:calculate_layout(parent_abs_x, parent_abs_y, parent_width, parent_height)
+    BaseComponent.calculate_layout(self, parent_abs_x, parent_abs_y, parent_width, parent_height)
+end
+
+local function smooth(current, target, time, dt)
+    if time <= 0 then return target end
+    return current + (target - current) * math.min(1, dt / time)
+end
+
+function Spectrum:update(dt)
+    local data = engine.get_spectrum({
+        bins = self.bins,
+        min_freq = self.min_freq,
+        max_freq = self.max_freq,
+    })
+
+    for i, level in ipairs(data.levels) do
+        local current = self.levels[i] or self.min_db
+        local time = level > current and self.attack or self.release
+        self.levels[i] = smooth(current, level, time, dt)
+        self.peaks[i] = data.peaks[i]
+    end
+end
+
+function Spectrum:db_to_y(db)
+    local range = self.max_db - self.min_db
+    local t = (db - self.min_db) / range
+    t = math.max(0, math.min(1, t))
+    return self.computed_y + self.computed_height * (1 - t)
+end
+
+function Spectrum:draw()
+    self:update(engine.get_delta_time())
+
+    draw.bordered_rounded_rect(self.computed_x, self.computed_y, self.computed_width, self.computed_height,
+        self.border_radius, self.border_width, self.bg,
+        self.border_color);
+
+    local x = self.computed_x
+    local w = self.computed_width
+
+    local db = self.max_db - self.grid_step_db
+    while db > self.min_db do
+        local y = self:db_to_y(db)
+        draw.line(x, y, x + w, y, 1, self.grid_color)
+        db = db - self.grid_step_db
+    end
+
+    local count = #self.levels
+    if count < 2 then return end
+
+    local step_x = w / (count - 1)
+    for i = 1, count - 1 do
+        local x1 = x + (i - 1) * step_x
+        local x2 = x + i * step_x
+        draw.line(x1, self:db_to_y(self.levels[i]), x2, self:db_to_y(self.levels[i + 1]), self.thickness, self.color)
+    end
+
+    if self.show_peaks then
+        for i = 1, count do
+            local px = x + (i - 1) * step_x
+            local py = self:db_to_y(self.peaks[i] or self.min_db)
+            draw.line(px - step_x * 0.4, py, px + step_x * 0.4, py, 1, self.peak_color)
+        end
+    end
+end
@@ -37,12 +37,45 @@ if not engine then
     ---@type fun(prop, color)
     function engine.draw_waveform(p, color) end
 
-    ---@type fun(note)
-    function engine.send_note_on_event(note) end
+    ---@type fun(note, velocity?)
+    function engine.send_note_on_event(note, velocity) end
 
     ---@type fun(note)
     function engine.send_note_off_event(note) end
 
+    ---@type fun(control, value)
+    function engine.send_control_change(control, value) end
+
+    ---@type fun(amount)
+    function engine.send_pitch_bend(amount) end
+
+    ---@type fun(prop?): table
+    function engine.get_spectrum(prop) return { levels = {}, peaks = {}, freqs = {} } end
+
+    ---@type fun(prop)
+    function engine.set_spectrum_options(prop) end
+
+    ---@type fun(name: string): table?
+    function engine.get_meter(name) return nil end
+
+    ---@type fun(name: string)
+    function engine.reset_meter(name) end
+
+    ---@type fun()
+    function engine.transport_play() end
+
+    ---@type fun()
+    function engine.transport_stop() end
+
+    ---@type fun(bpm: number)
+    function engine.set_bpm(bpm) end
+
+    ---@type fun(beat: number)
+    function engine.seek(beat) end
+
+    ---@type fun(): table
+    function engine.get_transport() return {} end
+
     ---@type fun(path)
     function engine.load_texture(path) end
 
@@ -28,7 +28,8 @@ for i = 0, 20 do
                     width = Size.relative(1),
                     height = Size.relative(0.98),
                     name = "C" ..
-                        i
+                        i,
+                    meter = i == 0 and "master" or nil
                 })
             }))
     )
@@ -5,6 +5,7 @@ require("gui/components/prelude")
 require("gui/layouts/workspace")
 
 TopBarScope = Oscilloscope:new({ width = Size.absolute(120), height = Size.relative(1), thickness = 2, color = { r = 255, g = 255, b = 255, a = 255 }, bg = { r = 0, g = 0, b = 0, a = 180 }, border_radius = 8, })
+TopBarSpectrum = Spectrum:new({ width = Size.absolute(160), height = Size.relative(1), bins = 48, thickness = 1.5, color = { r = 255, g = 255, b = 255, a = 255 }, bg = { r = 0, g = 0, b = 0, a = 180 }, border_radius = 8, })
 
 return Layout:new({ x = 0, y = 0, bg = { r = 30, g = 30, b = 30 } }, {
     Container:new(
@@ -68,7 +69,8 @@ return Layout:new({ x = 0, y = 0, bg = { r = 30, g = 30, b = 30 } }, {
                                 })),
 
 
-                            TopBarScope
+                            TopBarScope,
+                            TopBarSpectrum
 
                         }
                     })
@@ -9,8 +9,8 @@ use std::{
 use futures::executor::block_on;
 use macroquad::{prelude::*, texture};
 use mlua::{FromLua, Lua, Table};
-use squid_core::{Event, EventData};
-use squid_engine::StreamContext;
+use squid_core::{Event, EventData, MeterReadings, gain_to_db};
+use squid_engine::{SpectrumAnalyzer, StreamContext};
 
 pub enum ShapeStyle {
     RoundedRect { radius: f32 },
@@ -35,15 +35,28 @@ macro_rules! lua_fn_async {
 pub struct RuntimeApi {
     texture_cache: Arc<Mutex<HashMap<String, Texture2D>>>,
     font_cache: Arc<Mutex<HashMap<String, Font>>>,
+    spectrum: Arc<Mutex<SpectrumAnalyzer>>,
+    meters: Arc<Mutex<HashMap<String, Arc<MeterReadings>>>>,
 }
 
 impl RuntimeApi {
-    pub fn new() -> Self {
+    // `sample_rate` is the rate of the stream feeding the spectrum tap.
+    pub fn new(sample_rate: f32) -> Self {
         Self {
             texture_cache: Arc::new(Mutex::new(HashMap::new())),
             font_cache: Arc::new(Mutex::new(HashMap::new())),
+            spectrum: Arc::new(Mutex::new(SpectrumAnalyzer::new(sample_rate))),
+            meters: Arc::new(Mutex::new(HashMap::new())),
         }
     }
+
+    // Makes a track or bus meter readable from Lua under `name`.
+    pub fn register_meter(&self, name: &str, readings: Arc<MeterReadings>) {
+        self.meters
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), readings);
+    }
     pub fn get_or<T>(tbl: &mlua::Table, val: &str, default: T) -> T
     where
         T: FromLua,
@@ -647,32 +660,210 @@ impl RuntimeApi {
 
         let shared_st = app_state.clone();
         // --- send_note_on_event ---
+        lua_fn!(lua, engine, "send_note_on_event", move |_,
+                                                         (
+            note,
+            velocity,
+        ): (
+            f32,
+            Option<f32>
+        )| {
+            let velocity = velocity.unwrap_or(100.0).clamp(1.0, 127.0);
+            let _ = shared_st.events.push(Event::new(
+                0,
+                EventData::NoteOn {
+                    note: note as u8,
+                    velocity: velocity as u8,
+                },
+            ));
+            Ok(())
+        });
+
+        let shared_st = app_state.clone();
+        // --- send_note_off_event ---
+        lua_fn!(lua, engine, "send_note_off_event", move |_, note: f32| {
+            let _ = shared_st
+                .events
+                .push(Event::new(0, EventData::NoteOff { note: note as u8 }));
+            Ok(())
+        });
+
+        let shared_st = app_state.clone();
+        // --- send_control_change ---
+        lua_fn!(lua, engine, "send_control_change", move |_,
+                                                          (
+            control,
+            value,
+        ): (
+            f32,
+            f32
+        )| {
+            let _ = shared_st.events.push(Event::new(
+                0,
+                EventData::ControlChange {
+                    control: control.clamp(0.0, 127.0) as u8,
+                    value: value.clamp(0.0, 127.0) as u8,
+                },
+            ));
+            Ok(())
+        });
+
+        let shared_st = app_state.clone();
+        // --- send_pitch_bend ---
+        // -1 to 1, 0 is centered.
+        lua_fn!(lua, engine, "send_pitch_bend", move |_, amount: f32| {
+            let value = 8192.0 + amount.clamp(-1.0, 1.0) * 8191.0;
+            let _ = shared_st.events.push(Event::new(
+                0,
+                EventData::PitchBend {
+                    value: value as u16,
+                },
+            ));
+            Ok(())
+        });
+
+        let shared_st = app_state.clone();
+        let spectrum = self.spectrum.clone();
+        // --- get_spectrum ---
         lua_fn!(
             lua,
             engine,
-            "send_note_on_event",
-            move |_, (note): (f32)| {
-                let _ = shared_st.events.push(Event {
-                    timing: 0,
-                    data: EventData::NoteOn {
-                        note: note as u8,
-                        velocity: 0,
-                    },
-                });
+            "get_spectrum",
+            move |lua, prop: Option<Table>| {
+                let (bins, min_freq, max_freq) = match &prop {
+                    Some(prop) => (
+                        Self::get_or(prop, "bins", 64usize),
+                        Self::get_or(prop, "min_freq", 20.0),
+                        Self::get_or(prop, "max_freq", 20000.0),
+                    ),
+                    None => (64, 20.0, 20000.0),
+                };
+
+                let mut spectrum = spectrum.lock().unwrap();
+                spectrum.drain(&shared_st.spectrum);
+
+                let levels = lua.create_table()?;
+                let peaks = lua.create_table()?;
+                let freqs = lua.create_table()?;
+                for (i, bin) in spectrum
+                    .log_bins(bins.max(1), min_freq, max_freq)
+                    .iter()
+                    .enumerate()
+                {
+                    levels.set(i + 1, bin.level_db)?;
+                    peaks.set(i + 1, bin.peak_db)?;
+                    freqs.set(i + 1, bin.freq)?;
+                }
+
+                let result = lua.create_table()?;
+                result.set("levels", levels)?;
+                result.set("peaks", peaks)?;
+                result.set("freqs", freqs)?;
+                Ok(result)
+            }
+        );
+
+        let spectrum = self.spectrum.clone();
+        // --- set_spectrum_options ---
+        lua_fn!(
+            lua,
+            engine,
+            "set_spectrum_options",
+            move |_, prop: Table| {
+                let mut spectrum = spectrum.lock().unwrap();
+                if let Ok(averaging) = prop.get::<f32>("averaging") {
+                    spectrum.set_averaging(averaging);
+                }
+                if let Ok(hold) = prop.get::<f32>("peak_hold") {
+                    spectrum.set_peak_hold(hold);
+                }
+                if let Ok(decay) = prop.get::<f32>("peak_decay") {
+                    spectrum.set_peak_decay(decay);
+                }
+                if let Ok(sample_rate) = prop.get::<f32>("sample_rate") {
+                    spectrum.set_sample_rate(sample_rate);
+                }
                 Ok(())
             }
         );
 
+        let meters = self.meters.clone();
+        // --- get_meter ---
+        lua_fn!(lua, engine, "get_meter", move |lua, name: String| {
+            let meters = meters.lock().unwrap();
+            let Some(readings) = meters.get(&name) else {
+                return Ok(None);
+            };
+
+            let result = lua.create_table()?;
+            result.set("peak_l", gain_to_db(readings.sample_peak(0)))?;
+            result.set("peak_r", gain_to_db(readings.sample_peak(1)))?;
+            result.set("true_peak_l", gain_to_db(readings.true_peak(0)))?;
+            result.set("true_peak_r", gain_to_db(readings.true_peak(1)))?;
+            result.set("max_true_peak", gain_to_db(readings.max_true_peak()))?;
+            result.set("rms_l", gain_to_db(readings.rms(0)))?;
+            result.set("rms_r", gain_to_db(readings.rms(1)))?;
+            result.set("momentary", readings.momentary())?;
+            result.set("short_term", readings.short_term())?;
+            result.set("integrated", readings.integrated())?;
+            result.set("range", readings.loudness_range())?;
+            Ok(Some(result))
+        });
+
+        let meters = self.meters.clone();
+        // --- reset_meter ---
+        lua_fn!(lua, engine, "reset_meter", move |_, name: String| {
+            if let Some(readings) = meters.lock().unwrap().get(&name) {
+                readings.request_reset();
+            }
+            Ok(())
+        });
+
         let shared_st = app_state.clone();
-        // --- send_note_off_event ---
-        lua_fn!(lua, engine, "send_note_off_event", move |_, note: f32| {
-            let _ = shared_st.events.push(Event {
-                timing: 0,
-                data: EventData::NoteOff { note: note as u8 },
-            });
+        // --- transport_play ---
+        lua_fn!(lua, engine, "transport_play", move |_, ()| {
+            shared_st.transport.play();
             Ok(())
         });
 
+        let shared_st = app_state.clone();
+        // --- transport_stop ---
+        lua_fn!(lua, engine, "transport_stop", move |_, ()| {
+            shared_st.transport.stop();
+            Ok(())
+        });
+
+        let shared_st = app_state.clone();
+        // --- set_bpm ---
+        lua_fn!(lua, engine, "set_bpm", move |_, bpm: f32| {
+            shared_st.transport.set_bpm(bpm);
+            Ok(())
+        });
+
+        let shared_st = app_state.clone();
+        // --- seek ---
+        lua_fn!(lua, engine, "seek", move |_, beat: f64| {
+            shared_st.transport.seek_to_beat(beat);
+            Ok(())
+        });
+
+        let shared_st = app_state.clone();
+        // --- get_transport ---
+        lua_fn!(lua, engine, "get_transport", move |lua, ()| {
+            let transport = &shared_st.transport;
+            let position = transport.song_position();
+
+            let result = lua.create_table()?;
+            result.set("playing", transport.is_playing())?;
+            result.set("bpm", transport.bpm())?;
+            result.set("beat", transport.beat())?;
+            result.set("bar", position.bar)?;
+            result.set("bar_beat", position.beat)?;
+            result.set("tick", position.tick)?;
+            result.set("position", position.to_string())?;
+            Ok(result)
+        });
+
         let texture_cache = self.texture_cache.clone();
         // --- load_texture ---
         lua_fn!(lua, engine, "load_texture", move |_, path: String| {
@@ -12,7 +12,8 @@ use macroquad::prelude::*;
 use mlua::{Function, Lua, Result};
 use squid_app::api::RuntimeApi;
 use squid_core::{
-    Event, EventData, FixedSpscQueue, FloatVector, MAX_BLOCK_SIZE, Note, PitchClass, Plugin,
+    Event, EventData, FixedSpscQueue, FloatVector, MAX_BLOCK_SIZE, Meter, Note, PitchClass, Plugin,
+    Transport,
     dsp::{
         filters::sv_filter::ScalarSvf,
         mod_core::adsr_mod_source::{AdsrModSource, calculate_coefficient},
@@ -76,6 +77,8 @@ async fn main() -> Result<()> {
     let mut t = 0;
 
     let mut pd = LivePlayback::init();
+    let mut meter = Meter::new(pd.sample_rate as f32);
+    let mut transport = Transport::new(pd.sample_rate as f32, shared_ctx.transport.bpm());
     pd.start(move |out| {
         f1.update_coeffs(c, 0.7, 44100.);
         f2.update_coeffs(c, 0.7, 44100.);
@@ -86,7 +89,10 @@ async fn main() -> Result<()> {
         let ctx = &ProcessContext {
             inputs: &[],
             events: &e,
-            sample_rate: 44100.,
+            sample_rate,
+            time: shared_ctx
+                .transport
+                .next_block(&mut transport, MAX_BLOCK_SIZE),
         };
 
         synth.process(&ctx, out);
@@ -99,8 +105,11 @@ async fn main() -> Result<()> {
         }
         t += 1;
 
+        meter.process(out[0], out[1], &shared_ctx.master_meter);
+
         for (i, j) in out[0].iter().zip(out[1].iter()) {
             let _ = shared.push((i + j) / 3.);
+            let _ = shared_ctx.spectrum.push((i + j) * 0.5);
         }
 
         if shared.len() >= 500 {
@@ -115,7 +124,8 @@ async fn main() -> Result<()> {
     });
 
     let lua = Lua::new();
-    let mut runtime = RuntimeApi::new();
+    let mut runtime = RuntimeApi::new(pd.sample_rate as f32);
+    runtime.register_meter("master", ctx.master_meter.clone());
     runtime.add_api_to_lua(&lua, ctx.clone());
 
     let code = fs::read_to_string("gui/main.lua")?;
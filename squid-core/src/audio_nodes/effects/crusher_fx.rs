@@ -0,0 +1,159 @@
+use core::simd::Simd;
+
+use libm::powf;
+use sleef::f32x::roundf;
+
+use crate::{
+    AudioNode, FloatVector, Presets,
+    effects::Effect,
+    process_context::{FixedBuf, ProcessContext},
+    rand::{Rand, SimdRand},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrusherPreset {
+    pub bit_depth: f32,
+    pub rate: f32,
+    pub dither: f32,
+    pub jitter: f32,
+    pub mix: f32,
+}
+
+#[derive(Clone)]
+pub struct CrusherFx {
+    bits: f32,
+    rate: f32,
+    dither: f32,
+    jitter: f32,
+    mix: f32,
+    sample_rate: f32,
+
+    phase: f32,
+    threshold: f32,
+    held: [f32; 2],
+
+    rng: Rand,
+    dither_rng: SimdRand<{ FloatVector::LANES }>,
+}
+
+impl CrusherFx {
+    pub fn new() -> Self {
+        Self {
+            bits: 24.0,
+            rate: 44100.0,
+            dither: 0.0,
+            jitter: 0.0,
+            mix: 1.0,
+            sample_rate: 44100.0,
+
+            phase: 1.0,
+            threshold: 1.0,
+            held: [0.0; 2],
+
+            rng: Rand::new(0x5EED),
+            dither_rng: SimdRand::new(0xD17E),
+        }
+    }
+
+    // Fractional depths are allowed, the step size is interpolated continuously.
+    pub fn set_bit_depth(&mut self, bits: f32) {
+        self.bits = bits.clamp(1.0, 24.0);
+    }
+
+    // Hold rate in Hz, does not have to divide the engine rate.
+    pub fn set_rate(&mut self, rate: f32) {
+        self.rate = rate.clamp(20.0, self.sample_rate);
+    }
+
+    pub fn set_dither(&mut self, amount: f32) {
+        self.dither = amount.clamp(0.0, 1.0);
+    }
+
+    // Random deviation of each hold period, as a fraction of the period.
+    pub fn set_jitter(&mut self, amount: f32) {
+        self.jitter = amount.clamp(0.0, 1.0);
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    fn sample_and_hold(&mut self, inputs: [&FixedBuf; 2], outputs: &mut [&mut FixedBuf]) {
+        let inc = self.rate / self.sample_rate;
+
+        for i in 0..inputs[0].len() {
+            self.phase += inc;
+            if self.phase >= self.threshold {
+                self.phase -= self.threshold;
+                self.threshold = 1.0 + self.jitter * self.rng.next_f32_bipolar() * 0.5;
+
+                self.held = [inputs[0][i], inputs[1][i]];
+            }
+
+            outputs[0][i] = self.held[0];
+            outputs[1][i] = self.held[1];
+        }
+    }
+}
+
+impl Default for CrusherFx {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioNode for CrusherFx {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        // A mono input feeds both channels, a missing one is silence.
+        let silence = FixedBuf::default();
+        let left = ctx.inputs.first().copied().unwrap_or(&silence);
+        let inputs = [left, ctx.inputs.get(1).copied().unwrap_or(left)];
+        self.sample_and_hold(inputs, outputs);
+
+        let steps = Simd::splat(powf(2.0, self.bits - 1.0));
+        let dither = Simd::splat(self.dither * 0.5) / steps;
+        let wet = Simd::splat(self.mix);
+        let dry = Simd::splat(1.0 - self.mix);
+
+        for (output, input) in outputs.iter_mut().zip(inputs) {
+            output.map_in_place(|x| {
+                // Triangular (TPDF) dither spanning one quantization step.
+                let noise = self.dither_rng.next_f32_bipolar() + self.dither_rng.next_f32_bipolar();
+                roundf((x + noise * dither) * steps) / steps
+            });
+            output.zip_map_in_place(input, |w, d| w * wet + d * dry);
+        }
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.rate = self.rate.min(sample_rate);
+        self.phase = 1.0;
+        self.threshold = 1.0;
+        self.held = [0.0; 2];
+    }
+}
+
+impl Presets for CrusherFx {
+    type Preset = CrusherPreset;
+
+    fn preset(&self) -> CrusherPreset {
+        CrusherPreset {
+            bit_depth: self.bits,
+            rate: self.rate,
+            dither: self.dither,
+            jitter: self.jitter,
+            mix: self.mix,
+        }
+    }
+
+    fn load_preset(&mut self, preset: &CrusherPreset) {
+        self.set_bit_depth(preset.bit_depth);
+        self.set_rate(preset.rate);
+        self.set_dither(preset.dither);
+        self.set_jitter(preset.jitter);
+        self.set_mix(preset.mix);
+    }
+}
+
+impl Effect for CrusherFx {}
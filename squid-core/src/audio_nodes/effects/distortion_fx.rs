@@ -0,0 +1,179 @@
+use libm::{log10f, powf, sqrtf};
+
+use crate::{
+    AudioNode, Presets,
+    dsp::{
+        filters::sv_filter::ScalarSvf,
+        microprocessors::saturation::{Saturation, SaturationCurve},
+        oversampler::{OversampleFactor, Oversampler},
+    },
+    effects::Effect,
+    process_context::{FixedBuf, ProcessContext},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistortionPreset {
+    pub curve: SaturationCurve,
+    pub drive_db: f32,
+    pub bias: f32,
+    pub bit_depth: f32,
+    pub tone: f32,
+    pub mix: f32,
+    pub compensation: bool,
+    pub oversampling: OversampleFactor,
+}
+
+#[derive(Clone, Copy)]
+pub struct DistortionFx {
+    curve: SaturationCurve,
+    drive: f32,
+    bias: f32,
+    bits: f32,
+    tone: f32,
+    mix: f32,
+    compensate: bool,
+    sample_rate: f32,
+
+    oversamplers: [Oversampler; 2],
+    tone_filters: [ScalarSvf; 2],
+}
+
+impl DistortionFx {
+    pub fn new(curve: SaturationCurve) -> Self {
+        let mut fx = Self {
+            curve,
+            drive: 1.0,
+            bias: 0.0,
+            bits: 8.0,
+            tone: 12_000.0,
+            mix: 1.0,
+            compensate: true,
+            sample_rate: 44100.0,
+
+            oversamplers: [Oversampler::new(OversampleFactor::X4); 2],
+            tone_filters: [ScalarSvf::new(); 2],
+        };
+        fx.update_tone();
+        fx
+    }
+
+    pub fn set_curve(&mut self, curve: SaturationCurve) {
+        self.curve = curve;
+    }
+
+    pub fn set_drive_db(&mut self, db: f32) {
+        self.drive = powf(10.0, db.clamp(0.0, 48.0) / 20.0);
+    }
+
+    pub fn set_bias(&mut self, bias: f32) {
+        self.bias = bias.clamp(-1.0, 1.0);
+    }
+
+    // Only used by `SaturationCurve::Bit`.
+    pub fn set_bit_depth(&mut self, bits: f32) {
+        self.bits = bits.clamp(1.0, 24.0);
+    }
+
+    pub fn set_tone(&mut self, cutoff: f32) {
+        self.tone = cutoff;
+        self.update_tone();
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    pub fn set_compensation(&mut self, enabled: bool) {
+        self.compensate = enabled;
+    }
+
+    pub fn set_oversampling(&mut self, factor: OversampleFactor) {
+        for os in &mut self.oversamplers {
+            os.set_factor(factor);
+        }
+        self.update_tone();
+    }
+
+    fn update_tone(&mut self) {
+        let os_rate = self.sample_rate * self.oversamplers[0].factor().ratio() as f32;
+        for filter in &mut self.tone_filters {
+            filter.update_coeffs(self.tone, 0.707, os_rate);
+        }
+    }
+}
+
+impl AudioNode for DistortionFx {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let curve = self.curve;
+        let drive = self.drive;
+        let bias = self.bias;
+        let bits = self.bits;
+        let mix = self.mix;
+
+        // Removes the static offset the bias introduces so silence stays silent.
+        let dc = Saturation::apply(curve, bias, bits);
+        let makeup = if self.compensate {
+            1.0 / sqrtf(drive)
+        } else {
+            1.0
+        };
+
+        let channels = outputs.iter_mut().zip(ctx.inputs.iter()).zip(
+            self.oversamplers
+                .iter_mut()
+                .zip(self.tone_filters.iter_mut()),
+        );
+
+        for ((output, input), (os, tone)) in channels {
+            for (out, &x) in output.iter_mut().zip(input.iter()) {
+                // Dry/wet mixing happens at the oversampled rate as well, so both
+                // paths share the same filter latency.
+                *out = os.process(x, |s| {
+                    let shaped = Saturation::apply(curve, s * drive + bias, bits) - dc;
+                    let (lp, _, _) = tone.process(shaped);
+
+                    s * (1.0 - mix) + lp * makeup * mix
+                });
+            }
+        }
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        for os in &mut self.oversamplers {
+            os.reset();
+        }
+        self.tone_filters = [ScalarSvf::new(); 2];
+        self.update_tone();
+    }
+}
+
+impl Presets for DistortionFx {
+    type Preset = DistortionPreset;
+
+    fn preset(&self) -> DistortionPreset {
+        DistortionPreset {
+            curve: self.curve,
+            drive_db: 20.0 * log10f(self.drive),
+            bias: self.bias,
+            bit_depth: self.bits,
+            tone: self.tone,
+            mix: self.mix,
+            compensation: self.compensate,
+            oversampling: self.oversamplers[0].factor(),
+        }
+    }
+
+    fn load_preset(&mut self, preset: &DistortionPreset) {
+        self.set_curve(preset.curve);
+        self.set_drive_db(preset.drive_db);
+        self.set_bias(preset.bias);
+        self.set_bit_depth(preset.bit_depth);
+        self.set_mix(preset.mix);
+        self.set_compensation(preset.compensation);
+        self.set_oversampling(preset.oversampling);
+        self.set_tone(preset.tone);
+    }
+}
+
+impl Effect for DistortionFx {}
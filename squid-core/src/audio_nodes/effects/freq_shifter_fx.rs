@@ -0,0 +1,76 @@
+use core::{f32::consts::TAU, simd::Simd};
+
+use sleef::f32x::sincos_u35;
+
+use crate::{
+    AudioNode, SIMD_LANES,
+    dsp::filters::hilbert::Hilbert,
+    effects::Effect,
+    phase_accumulator::PhaseAccumulator,
+    process_context::{FixedBuf, ProcessContext},
+};
+
+// Bode style single sideband shifter. Takes a mono input and writes the
+// up-shifted signal to the first output and the down-shifted one to the second.
+#[derive(Clone)]
+pub struct FreqShifterFx {
+    shift: f32,
+    sample_rate: f32,
+    hilbert: Hilbert,
+    phasor: PhaseAccumulator<{ SIMD_LANES }>,
+    sin_buf: FixedBuf,
+    cos_buf: FixedBuf,
+}
+
+impl FreqShifterFx {
+    pub fn new(shift: f32) -> Self {
+        Self {
+            shift: shift.abs(),
+            sample_rate: 44100.0,
+            hilbert: Hilbert::new(),
+            phasor: PhaseAccumulator::new(0.0),
+            sin_buf: FixedBuf::default(),
+            cos_buf: FixedBuf::default(),
+        }
+    }
+
+    pub fn set_shift(&mut self, shift: f32) {
+        self.shift = shift.abs();
+    }
+}
+
+impl AudioNode for FreqShifterFx {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        self.phasor
+            .process_const(self.shift, self.sample_rate, &mut self.sin_buf);
+
+        let v_tau = Simd::splat(TAU);
+        let chunks = self
+            .sin_buf
+            .simd_chunks_mut()
+            .iter_mut()
+            .zip(self.cos_buf.simd_chunks_mut());
+        for (s, c) in chunks {
+            (*s, *c) = sincos_u35(*s * v_tau);
+        }
+
+        let (up, down) = outputs.split_at_mut(1);
+        let input = ctx.inputs[0];
+
+        for i in 0..input.len() {
+            let (re, im) = self.hilbert.process(input[i]);
+            let (sin, cos) = (self.sin_buf[i], self.cos_buf[i]);
+
+            up[0][i] = re * cos + im * sin;
+            down[0][i] = re * cos - im * sin;
+        }
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.hilbert.reset();
+        self.phasor = PhaseAccumulator::new(0.0);
+    }
+}
+
+impl Effect for FreqShifterFx {}
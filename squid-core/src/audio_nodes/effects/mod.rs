@@ -1,3 +1,10 @@
 pub trait Effect {}
 
+pub mod crusher_fx;
+pub mod distortion_fx;
+pub mod freq_shifter_fx;
 pub mod gain_fx;
+pub mod pitch_shifter_fx;
+pub mod ring_mod_fx;
+pub mod spectral_fx;
+pub mod vocoder_fx;
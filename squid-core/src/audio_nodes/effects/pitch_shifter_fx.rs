@@ -0,0 +1,257 @@
+use core::{
+    array,
+    f32::consts::PI,
+    simd::{Simd, cmp::SimdPartialOrd, num::SimdFloat},
+};
+
+use libm::{expf, floorf, powf, sinf};
+
+use crate::{
+    AudioNode, SIMD_LANES,
+    dsp::filters::simd_svf::SimdSvf,
+    effects::Effect,
+    process_context::{FixedBuf, ProcessContext},
+};
+
+const DELAY_SIZE: usize = 8192;
+const DELAY_MASK: usize = DELAY_SIZE - 1;
+const MIN_DELAY: f32 = 2.0;
+
+const FORMANT_GROUPS: usize = 2;
+const FORMANT_BANDS: usize = FORMANT_GROUPS * SIMD_LANES;
+const FORMANT_LOWEST: f32 = 120.0;
+const FORMANT_HIGHEST: f32 = 10_000.0;
+const FORMANT_MAX_GAIN: f32 = 4.0;
+const FORMANT_FOLLOW_MS: f32 = 8.0;
+// The overlapping Q = 2 bands sum to about +7.3 dB, measured flat within 0.5 dB.
+const FORMANT_BANK_NORM: f32 = 1.0 / 2.3;
+
+type Lanes = Simd<f32, SIMD_LANES>;
+
+// Re-imposes the spectral envelope of the dry signal on the shifted one by
+// comparing band levels of both signals.
+#[derive(Clone, Copy)]
+struct FormantKeeper {
+    dry_bank: [SimdSvf<SIMD_LANES>; FORMANT_GROUPS],
+    wet_bank: [SimdSvf<SIMD_LANES>; FORMANT_GROUPS],
+    dry_env: [Lanes; FORMANT_GROUPS],
+    wet_env: [Lanes; FORMANT_GROUPS],
+    coeff: Lanes,
+}
+
+impl FormantKeeper {
+    fn new() -> Self {
+        Self {
+            dry_bank: [SimdSvf::new(); FORMANT_GROUPS],
+            wet_bank: [SimdSvf::new(); FORMANT_GROUPS],
+            dry_env: [Simd::splat(0.0); FORMANT_GROUPS],
+            wet_env: [Simd::splat(0.0); FORMANT_GROUPS],
+            coeff: Simd::splat(0.0),
+        }
+    }
+
+    fn update(&mut self, sample_rate: f32) {
+        let ratio = FORMANT_HIGHEST / FORMANT_LOWEST;
+        let q = Simd::splat(2.0);
+
+        for g in 0..FORMANT_GROUPS {
+            let freqs = Simd::from_array(array::from_fn(|lane| {
+                let t = (g * SIMD_LANES + lane) as f32 / (FORMANT_BANDS - 1) as f32;
+                FORMANT_LOWEST * powf(ratio, t)
+            }));
+            self.dry_bank[g].update_coeffs(freqs, q, sample_rate);
+            self.wet_bank[g].update_coeffs(freqs, q, sample_rate);
+        }
+
+        self.coeff = Simd::splat(1.0 - expf(-1.0 / (FORMANT_FOLLOW_MS * 0.001 * sample_rate)));
+    }
+
+    #[inline(always)]
+    fn process(&mut self, dry: f32, wet: f32) -> f32 {
+        let dry = Simd::splat(dry);
+        let wet = Simd::splat(wet);
+        let eps = Simd::splat(1e-5);
+        let mut sum = Simd::splat(0.0);
+
+        for g in 0..FORMANT_GROUPS {
+            let dry_band = self.dry_bank[g].process_bp_normalized(dry);
+            let wet_band = self.wet_bank[g].process_bp_normalized(wet);
+
+            self.dry_env[g] += (dry_band.abs() - self.dry_env[g]) * self.coeff;
+            self.wet_env[g] += (wet_band.abs() - self.wet_env[g]) * self.coeff;
+
+            let gain =
+                (self.dry_env[g] / (self.wet_env[g] + eps)).simd_min(Simd::splat(FORMANT_MAX_GAIN));
+            let gain = self.wet_env[g].simd_gt(eps).select(gain, Simd::splat(0.0));
+
+            sum += wet_band * gain;
+        }
+
+        sum.reduce_sum() * FORMANT_BANK_NORM
+    }
+
+    fn reset(&mut self) {
+        self.dry_bank.iter_mut().for_each(|f| f.reset());
+        self.wet_bank.iter_mut().for_each(|f| f.reset());
+        self.dry_env = [Simd::splat(0.0); FORMANT_GROUPS];
+        self.wet_env = [Simd::splat(0.0); FORMANT_GROUPS];
+    }
+}
+
+#[derive(Clone, Copy)]
+struct GrainDelay {
+    buffer: [f32; DELAY_SIZE],
+    write: usize,
+}
+
+impl GrainDelay {
+    fn new() -> Self {
+        Self {
+            buffer: [0.0; DELAY_SIZE],
+            write: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn push(&mut self, x: f32) {
+        self.buffer[self.write] = x;
+        self.write = (self.write + 1) & DELAY_MASK;
+    }
+
+    // Linear interpolated read, `delay` samples behind the newest sample.
+    #[inline(always)]
+    fn read(&self, delay: f32) -> f32 {
+        let pos = self.write as f32 - 1.0 - delay + DELAY_SIZE as f32;
+        let base = floorf(pos);
+        let frac = pos - base;
+
+        let i0 = base as usize & DELAY_MASK;
+        let i1 = (i0 + 1) & DELAY_MASK;
+        self.buffer[i0] + (self.buffer[i1] - self.buffer[i0]) * frac
+    }
+}
+
+// Real-time pitch shifter. Two overlapping grains read from a delay line at a
+// different speed than it is written, crossfaded with complementary windows.
+#[derive(Clone, Copy)]
+pub struct PitchShifterFx {
+    ratio: f32,
+    grain_ms: f32,
+    grain_len: f32,
+    mix: f32,
+    preserve_formants: bool,
+    sample_rate: f32,
+
+    phase: f32,
+    delays: [GrainDelay; 2],
+    formants: [FormantKeeper; 2],
+}
+
+impl PitchShifterFx {
+    pub fn new(semitones: f32) -> Self {
+        let mut fx = Self {
+            ratio: 1.0,
+            grain_ms: 40.0,
+            grain_len: 0.0,
+            mix: 1.0,
+            preserve_formants: false,
+            sample_rate: 44100.0,
+
+            phase: 0.0,
+            delays: [GrainDelay::new(); 2],
+            formants: [FormantKeeper::new(); 2],
+        };
+        fx.set_semitones(semitones);
+        fx.update();
+        fx
+    }
+
+    pub fn set_semitones(&mut self, semitones: f32) {
+        self.ratio = powf(2.0, semitones.clamp(-24.0, 24.0) / 12.0);
+    }
+
+    pub fn set_ratio(&mut self, ratio: f32) {
+        self.ratio = ratio.clamp(0.25, 4.0);
+    }
+
+    // Longer grains smear transients, shorter grains add roughness.
+    pub fn set_grain_ms(&mut self, grain_ms: f32) {
+        self.grain_ms = grain_ms.clamp(10.0, 100.0);
+        self.update();
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    pub fn set_preserve_formants(&mut self, enabled: bool) {
+        self.preserve_formants = enabled;
+    }
+
+    fn update(&mut self) {
+        let max_len = (DELAY_SIZE - 4) as f32 - MIN_DELAY;
+        self.grain_len = (self.grain_ms * 0.001 * self.sample_rate).min(max_len);
+
+        for keeper in &mut self.formants {
+            keeper.update(self.sample_rate);
+        }
+    }
+}
+
+impl AudioNode for PitchShifterFx {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let step = (1.0 - self.ratio) / self.grain_len;
+        let grain_len = self.grain_len;
+        let preserve = self.preserve_formants;
+        let wet = self.mix;
+        let dry = 1.0 - self.mix;
+        let start = self.phase;
+
+        let channels = outputs
+            .iter_mut()
+            .zip(ctx.inputs.iter())
+            .zip(self.delays.iter_mut().zip(self.formants.iter_mut()));
+
+        // Both channels run the same grain clock so the stereo image stays intact.
+        let mut phase = start;
+        for ((output, input), (delay, keeper)) in channels {
+            phase = start;
+            for (out, &x) in output.iter_mut().zip(input.iter()) {
+                let p0 = phase;
+                let p1 = if p0 >= 0.5 { p0 - 0.5 } else { p0 + 0.5 };
+
+                let d0 = MIN_DELAY + p0 * grain_len;
+                let d1 = MIN_DELAY + p1 * grain_len;
+
+                // sin^2 and cos^2 windows, their sum is always one.
+                let w0 = sinf(PI * p0);
+                let w0 = w0 * w0;
+                let w1 = 1.0 - w0;
+
+                delay.push(x);
+                let mut y = delay.read(d0) * w0 + delay.read(d1) * w1;
+                if preserve {
+                    y = keeper.process(x, y);
+                }
+
+                *out = x * dry + y * wet;
+
+                phase += step;
+                phase -= floorf(phase);
+            }
+        }
+        self.phase = phase;
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.phase = 0.0;
+        self.delays = [GrainDelay::new(); 2];
+        for keeper in &mut self.formants {
+            keeper.reset();
+        }
+        self.update();
+    }
+}
+
+impl Effect for PitchShifterFx {}
@@ -0,0 +1,84 @@
+use core::{f32::consts::TAU, simd::Simd};
+
+use sleef::f32x::sin_fast;
+
+use crate::{
+    AudioNode, SIMD_LANES,
+    effects::Effect,
+    phase_accumulator::PhaseAccumulator,
+    process_context::{FixedBuf, ProcessContext},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RingModCarrier {
+    // Sine carrier generated by the node.
+    Internal,
+    // Carrier taken from the third input.
+    External,
+}
+
+#[derive(Clone)]
+pub struct RingModFx {
+    carrier: RingModCarrier,
+    freq: f32,
+    mix: f32,
+    sample_rate: f32,
+    phasor: PhaseAccumulator<{ SIMD_LANES }>,
+    carrier_buf: FixedBuf,
+}
+
+impl RingModFx {
+    pub fn new(carrier: RingModCarrier) -> Self {
+        Self {
+            carrier,
+            freq: 440.0,
+            mix: 1.0,
+            sample_rate: 44100.0,
+            phasor: PhaseAccumulator::new(0.0),
+            carrier_buf: FixedBuf::default(),
+        }
+    }
+
+    pub fn set_carrier(&mut self, carrier: RingModCarrier) {
+        self.carrier = carrier;
+    }
+
+    pub fn set_frequency(&mut self, freq: f32) {
+        self.freq = freq.max(0.0);
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+}
+
+impl AudioNode for RingModFx {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let carrier = match self.carrier {
+            RingModCarrier::Internal => {
+                self.phasor
+                    .process_const(self.freq, self.sample_rate, &mut self.carrier_buf);
+
+                let v_tau = Simd::splat(TAU);
+                self.carrier_buf
+                    .map_in_place(|phase| sin_fast(phase * v_tau));
+                &self.carrier_buf
+            }
+            RingModCarrier::External => ctx.inputs[2],
+        };
+
+        let dry = Simd::splat(1.0 - self.mix);
+        let wet = Simd::splat(self.mix);
+
+        for (output, input) in outputs.iter_mut().zip(ctx.inputs.iter()).take(2) {
+            output.zip_map_from(input, carrier, |x, c| x * (dry + wet * c));
+        }
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.phasor = PhaseAccumulator::new(0.0);
+    }
+}
+
+impl Effect for RingModFx {}
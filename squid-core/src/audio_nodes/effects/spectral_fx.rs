@@ -0,0 +1,64 @@
+use crate::{
+    AudioNode,
+    dsp::{
+        stft::{SpectralProcessor, Stft},
+        window::Window,
+    },
+    effects::Effect,
+    process_context::{FixedBuf, ProcessContext},
+};
+
+// Runs a `SpectralProcessor` on both channels through an STFT of `SIZE`
+// samples. Output is delayed by `latency()` samples.
+#[derive(Clone)]
+pub struct SpectralFx<P: SpectralProcessor, const SIZE: usize> {
+    processor: P,
+    stfts: [Stft<SIZE>; 2],
+}
+
+impl<P: SpectralProcessor, const SIZE: usize> SpectralFx<P, SIZE> {
+    pub fn new(processor: P, overlap: usize) -> Self {
+        Self {
+            processor,
+            stfts: [Stft::new(Window::Hann, overlap); 2],
+        }
+    }
+
+    pub fn processor(&self) -> &P {
+        &self.processor
+    }
+
+    pub fn processor_mut(&mut self) -> &mut P {
+        &mut self.processor
+    }
+
+    pub fn latency(&self) -> usize {
+        self.stfts[0].latency()
+    }
+}
+
+impl<P: SpectralProcessor, const SIZE: usize> AudioNode for SpectralFx<P, SIZE> {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let processor = &mut self.processor;
+        let channels = outputs
+            .iter_mut()
+            .zip(ctx.inputs.iter())
+            .zip(self.stfts.iter_mut())
+            .enumerate();
+
+        for (channel, ((output, input), stft)) in channels {
+            for (out, &x) in output.iter_mut().zip(input.iter()) {
+                *out = stft.process(x, |re, im| processor.process_spectrum(channel, re, im));
+            }
+        }
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        for stft in &mut self.stfts {
+            stft.reset();
+        }
+        self.processor.reset(sample_rate);
+    }
+}
+
+impl<P: SpectralProcessor, const SIZE: usize> Effect for SpectralFx<P, SIZE> {}
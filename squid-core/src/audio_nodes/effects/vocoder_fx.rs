@@ -0,0 +1,202 @@
+use core::{
+    array,
+    simd::{Simd, cmp::SimdPartialOrd, num::SimdFloat},
+};
+
+use libm::{expf, log2f, powf, sqrtf};
+
+use crate::{
+    AudioNode, SIMD_LANES,
+    dsp::filters::simd_svf::SimdSvf,
+    effects::Effect,
+    process_context::{FixedBuf, ProcessContext},
+    rand::SimdRand,
+};
+
+pub const VOCODER_MIN_BANDS: usize = 8;
+pub const VOCODER_MAX_BANDS: usize = 32;
+
+const GROUPS: usize = VOCODER_MAX_BANDS / SIMD_LANES;
+const LOWEST_BAND: f32 = 80.0;
+const HIGHEST_BAND: f32 = 12_000.0;
+// Bands above this frequency get the unvoiced noise added to their carrier.
+const UNVOICED_FROM: f32 = 3_000.0;
+
+type Lanes = Simd<f32, SIMD_LANES>;
+
+// Channel vocoder. The first input is the modulator (voice), the second one the
+// carrier (synth). Bands are laid out across SIMD lanes so a whole group of
+// band-pass filters and envelope followers runs in a single pass.
+#[derive(Clone)]
+pub struct VocoderFx {
+    band_count: usize,
+    attack: f32,
+    release: f32,
+    formant_shift: f32,
+    unvoiced: f32,
+    sample_rate: f32,
+
+    modulator_bank: [SimdSvf<SIMD_LANES>; GROUPS],
+    carrier_bank: [SimdSvf<SIMD_LANES>; GROUPS],
+    envelopes: [Lanes; GROUPS],
+    band_gains: [Lanes; GROUPS],
+    noise_gains: [Lanes; GROUPS],
+
+    attack_coeff: Lanes,
+    release_coeff: Lanes,
+    output_gain: f32,
+
+    noise: SimdRand<SIMD_LANES>,
+}
+
+impl VocoderFx {
+    pub fn new(band_count: usize) -> Self {
+        let mut fx = Self {
+            band_count: band_count.clamp(VOCODER_MIN_BANDS, VOCODER_MAX_BANDS),
+            attack: 5.0,
+            release: 50.0,
+            formant_shift: 1.0,
+            unvoiced: 0.0,
+            sample_rate: 44100.0,
+
+            modulator_bank: [SimdSvf::new(); GROUPS],
+            carrier_bank: [SimdSvf::new(); GROUPS],
+            envelopes: [Simd::splat(0.0); GROUPS],
+            band_gains: [Simd::splat(0.0); GROUPS],
+            noise_gains: [Simd::splat(0.0); GROUPS],
+
+            attack_coeff: Simd::splat(0.0),
+            release_coeff: Simd::splat(0.0),
+            output_gain: 1.0,
+
+            noise: SimdRand::new(0xF0C0),
+        };
+        fx.update_bands();
+        fx.update_followers();
+        fx
+    }
+
+    pub fn set_band_count(&mut self, band_count: usize) {
+        self.band_count = band_count.clamp(VOCODER_MIN_BANDS, VOCODER_MAX_BANDS);
+        self.update_bands();
+    }
+
+    pub fn set_attack_ms(&mut self, attack: f32) {
+        self.attack = attack.max(0.1);
+        self.update_followers();
+    }
+
+    pub fn set_release_ms(&mut self, release: f32) {
+        self.release = release.max(0.1);
+        self.update_followers();
+    }
+
+    // Scales the analysis bands against the synthesis bands, > 1 moves the
+    // formants down, < 1 moves them up.
+    pub fn set_formant_shift(&mut self, ratio: f32) {
+        self.formant_shift = ratio.clamp(0.5, 2.0);
+        self.update_bands();
+    }
+
+    pub fn set_unvoiced(&mut self, amount: f32) {
+        self.unvoiced = amount.clamp(0.0, 1.0);
+        self.update_bands();
+    }
+
+    fn active_groups(&self) -> usize {
+        self.band_count.div_ceil(SIMD_LANES)
+    }
+
+    fn update_bands(&mut self) {
+        let count = self.band_count;
+        let octaves = log2f(HIGHEST_BAND / LOWEST_BAND);
+
+        // Neighbouring bands cross at roughly -3 dB.
+        let bw = octaves / count as f32;
+        let q = sqrtf(powf(2.0, bw)) / (powf(2.0, bw) - 1.0);
+        let v_q = Simd::splat(q);
+
+        for g in 0..GROUPS {
+            let band = |lane: usize| g * SIMD_LANES + lane;
+            let freq = |lane: usize| {
+                let t = band(lane).min(count - 1) as f32 / (count - 1) as f32;
+                LOWEST_BAND * powf(2.0, octaves * t)
+            };
+
+            let carrier_freqs = Simd::from_array(array::from_fn(freq));
+            let modulator_freqs = carrier_freqs * Simd::splat(self.formant_shift);
+
+            self.carrier_bank[g].update_coeffs(carrier_freqs, v_q, self.sample_rate);
+            self.modulator_bank[g].update_coeffs(modulator_freqs, v_q, self.sample_rate);
+
+            self.band_gains[g] =
+                Simd::from_array(array::from_fn(
+                    |lane| if band(lane) < count { 1.0 } else { 0.0 },
+                ));
+            self.noise_gains[g] = Simd::from_array(array::from_fn(|lane| {
+                if band(lane) < count && freq(lane) >= UNVOICED_FROM {
+                    self.unvoiced
+                } else {
+                    0.0
+                }
+            }));
+        }
+
+        // Keeps the summed bands roughly at the level of the carrier.
+        self.output_gain = 4.0 / sqrtf(count as f32);
+    }
+
+    fn update_followers(&mut self) {
+        let coeff = |ms: f32| 1.0 - expf(-1.0 / (ms * 0.001 * self.sample_rate));
+        self.attack_coeff = Simd::splat(coeff(self.attack));
+        self.release_coeff = Simd::splat(coeff(self.release));
+    }
+}
+
+impl AudioNode for VocoderFx {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let modulator = ctx.inputs[0];
+        let carrier = ctx.inputs[1];
+        let groups = self.active_groups();
+
+        for i in 0..modulator.len() {
+            let m = Simd::splat(modulator[i]);
+            let c = Simd::splat(carrier[i]);
+            let mut sum = Simd::splat(0.0);
+
+            for g in 0..groups {
+                let level = self.modulator_bank[g].process_bp_normalized(m).abs();
+                let env = &mut self.envelopes[g];
+                let coeff = level
+                    .simd_gt(*env)
+                    .select(self.attack_coeff, self.release_coeff);
+                *env += (level - *env) * coeff;
+
+                let excitation = c + self.noise.next_f32_bipolar() * self.noise_gains[g];
+                let band = self.carrier_bank[g].process_bp_normalized(excitation);
+
+                sum += band * *env * self.band_gains[g];
+            }
+
+            let y = sum.reduce_sum() * self.output_gain;
+            outputs[0][i] = y;
+            outputs[1][i] = y;
+        }
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.envelopes = [Simd::splat(0.0); GROUPS];
+        for filter in self
+            .modulator_bank
+            .iter_mut()
+            .chain(self.carrier_bank.iter_mut())
+        {
+            filter.reset();
+        }
+        self.update_bands();
+        self.update_followers();
+    }
+}
+
+impl Effect for VocoderFx {}
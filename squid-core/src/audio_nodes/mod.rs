@@ -7,10 +7,22 @@ pub trait AudioNode {
     fn reset(&mut self, sample_rate: f32);
 }
 
+// Lets containers hold `&mut dyn AudioNode` to mix node types.
+impl<T: AudioNode + ?Sized> AudioNode for &mut T {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        (**self).process(ctx, outputs);
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        (**self).reset(sample_rate);
+    }
+}
+
 pub mod effects;
 pub mod modulators;
 pub mod oscillators;
 pub mod phase_tracker;
+pub mod preset_switcher;
 pub mod processors;
 pub mod shapers;
 pub mod synths;
@@ -0,0 +1,162 @@
+use core::ops::Deref;
+
+use crate::{
+    AudioNode, PresetBank, PresetQueue, Presets, ProgramId, ProgramSelect,
+    process_context::{FixedBuf, ProcessContext},
+};
+
+// Loads stored presets into a node on program changes, after bank select if
+// any. The output fades out, the preset loads while it is silent and the
+// output fades back in, so parameters never jump under a sounding signal.
+// Program changes with nothing stored are ignored.
+//
+// Presets are prepared elsewhere and pushed on `updates`, e.g. an
+// `Arc<PresetQueue<_>>` shared with a control thread, and are taken into the
+// bank at the start of each block. Storing over the current program loads
+// the new version the same way.
+pub struct PresetSwitcher<N: Presets, Q, const PROGRAMS: usize> {
+    node: N,
+    updates: Q,
+    bank: PresetBank<N::Preset, PROGRAMS>,
+    select: ProgramSelect,
+    program: Option<ProgramId>,
+    pending: Option<N::Preset>,
+
+    gain: f32,
+    fade_ms: f32,
+    fade_step: f32,
+    sample_rate: f32,
+}
+
+impl<N, Q, const PROGRAMS: usize> PresetSwitcher<N, Q, PROGRAMS>
+where
+    N: AudioNode + Presets,
+    Q: Deref<Target = PresetQueue<N::Preset>>,
+{
+    pub fn new(node: N, updates: Q) -> Self {
+        let mut switcher = Self {
+            node,
+            updates,
+            bank: PresetBank::new(),
+            select: ProgramSelect::new(),
+            program: None,
+            pending: None,
+
+            gain: 1.0,
+            fade_ms: 5.0,
+            fade_step: 1.0,
+            sample_rate: 44100.0,
+        };
+        switcher.update_fade();
+        switcher
+    }
+
+    pub fn node(&self) -> &N {
+        &self.node
+    }
+
+    pub fn node_mut(&mut self) -> &mut N {
+        &mut self.node
+    }
+
+    pub fn bank(&self) -> &PresetBank<N::Preset, PROGRAMS> {
+        &self.bank
+    }
+
+    // For filling the bank before the node goes to the audio thread, later
+    // changes should go through the queue.
+    pub fn bank_mut(&mut self) -> &mut PresetBank<N::Preset, PROGRAMS> {
+        &mut self.bank
+    }
+
+    // The last program switched to.
+    pub fn program(&self) -> Option<ProgramId> {
+        self.program
+    }
+
+    // Each way, a switch takes twice this.
+    pub fn set_fade_time(&mut self, ms: f32) {
+        self.fade_ms = ms.clamp(0.0, 100.0);
+        self.update_fade();
+    }
+
+    // Switches as if a program change came in. Returns false if nothing is
+    // stored as `id`.
+    pub fn select(&mut self, id: ProgramId) -> bool {
+        match self.bank.get(id) {
+            Some(&preset) => {
+                self.program = Some(id);
+                self.pending = Some(preset);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn update_fade(&mut self) {
+        let samples = self.fade_ms * 0.001 * self.sample_rate;
+        self.fade_step = if samples > 1.0 { 1.0 / samples } else { 1.0 };
+    }
+
+    fn fade(&mut self, outputs: &mut [&mut FixedBuf]) {
+        let target = if self.pending.is_some() { 0.0 } else { 1.0 };
+        if self.gain == 1.0 && target == 1.0 {
+            return;
+        }
+
+        let len = outputs.first().map_or(0, |output| output.len());
+        for i in 0..len {
+            if target > self.gain {
+                self.gain = (self.gain + self.fade_step).min(1.0);
+            } else if target < self.gain {
+                self.gain = (self.gain - self.fade_step).max(0.0);
+            }
+            for output in outputs.iter_mut() {
+                output[i] *= self.gain;
+            }
+        }
+    }
+}
+
+impl<N, Q, const PROGRAMS: usize> AudioNode for PresetSwitcher<N, Q, PROGRAMS>
+where
+    N: AudioNode + Presets,
+    Q: Deref<Target = PresetQueue<N::Preset>>,
+{
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        if ctx.sample_rate != self.sample_rate {
+            self.sample_rate = ctx.sample_rate;
+            self.update_fade();
+        }
+        while let Some((id, preset)) = self.updates.pop() {
+            self.bank.store(id, preset);
+            if self.program == Some(id) {
+                self.pending = Some(preset);
+            }
+        }
+        for event in ctx.events {
+            if let Some(id) = self.select.handle(event.data) {
+                self.select(id);
+            }
+        }
+
+        self.node.process(ctx, outputs);
+        self.fade(outputs);
+
+        if self.gain == 0.0
+            && let Some(preset) = self.pending.take()
+        {
+            self.node.load_preset(&preset);
+        }
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update_fade();
+        self.node.reset(sample_rate);
+        if let Some(preset) = self.pending.take() {
+            self.node.load_preset(&preset);
+        }
+        self.gain = 1.0;
+    }
+}
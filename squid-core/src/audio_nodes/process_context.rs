@@ -1,6 +1,6 @@
 use core::ops::{Deref, DerefMut};
 
-use crate::{Event, FloatVector};
+use crate::{Event, FloatVector, TimeInfo};
 #[derive(Clone)]
 pub struct FixedBuf {
     pub data: FloatVector,
@@ -38,6 +38,7 @@ pub struct ProcessContext<'a> {
     pub sample_rate: f32,
     pub events: &'a [Event],
     pub inputs: &'a [&'a FixedBuf],
+    pub time: TimeInfo,
 }
 
 impl<'a> ProcessContext<'a> {
@@ -46,8 +47,14 @@ impl<'a> ProcessContext<'a> {
             sample_rate,
             events,
             inputs,
+            time: TimeInfo::stopped(sample_rate, 120.0),
         }
     }
+
+    pub fn with_time(mut self, time: TimeInfo) -> Self {
+        self.time = time;
+        self
+    }
 }
 impl<'a> Default for ProcessContext<'a> {
     fn default() -> Self {
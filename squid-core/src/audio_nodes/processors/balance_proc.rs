@@ -0,0 +1,59 @@
+use core::simd::Simd;
+
+use crate::{
+    AudioNode, FloatVector,
+    dsp::stereo::{PanLaw, StereoImaging},
+    process_context::{FixedBuf, ProcessContext},
+    processors::Processor,
+};
+
+// Stereo balance. An optional third input is added to the balance position
+// at audio rate.
+#[derive(Clone, Copy)]
+pub struct BalanceProc {
+    balance: f32,
+    law: PanLaw,
+}
+
+impl BalanceProc {
+    pub fn new(law: PanLaw) -> Self {
+        Self { balance: 0.0, law }
+    }
+
+    pub fn set_balance(&mut self, balance: f32) {
+        self.balance = balance.clamp(-1.0, 1.0);
+    }
+
+    pub fn set_law(&mut self, law: PanLaw) {
+        self.law = law;
+    }
+}
+
+impl AudioNode for BalanceProc {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let base = Simd::splat(self.balance);
+        let zero = FloatVector::splat(0.0);
+        let modulation = ctx.inputs.get(2).map_or(&zero, |m| &m.data);
+
+        let (left, right) = outputs.split_at_mut(1);
+        let inputs = ctx.inputs[0]
+            .simd_chunks()
+            .iter()
+            .zip(ctx.inputs[1].simd_chunks())
+            .zip(modulation.simd_chunks());
+        let outputs = left[0]
+            .simd_chunks_mut()
+            .iter_mut()
+            .zip(right[0].simd_chunks_mut());
+
+        for ((out_l, out_r), ((l, r), m)) in outputs.zip(inputs) {
+            let (gl, gr) = StereoImaging::balance_gains(base + *m, self.law);
+            *out_l = *l * gl;
+            *out_r = *r * gr;
+        }
+    }
+
+    fn reset(&mut self, _: f32) {}
+}
+
+impl Processor for BalanceProc {}
@@ -0,0 +1,48 @@
+use core::ops::Deref;
+
+use crate::{
+    AudioNode,
+    metering::{Meter, MeterReadings},
+    process_context::{FixedBuf, ProcessContext},
+    processors::Processor,
+};
+
+// Passes stereo audio through unchanged and publishes its levels. `R` is
+// whatever the readings are shared through, e.g. an `Arc` or a `&'static`.
+pub struct MeterProc<R: Deref<Target = MeterReadings>> {
+    meter: Meter,
+    readings: R,
+}
+
+impl<R: Deref<Target = MeterReadings>> MeterProc<R> {
+    pub fn new(readings: R) -> Self {
+        Self {
+            meter: Meter::new(44100.0),
+            readings,
+        }
+    }
+
+    pub fn readings(&self) -> &MeterReadings {
+        &self.readings
+    }
+
+    pub fn set_rms_window_ms(&mut self, ms: f32) {
+        self.meter.set_rms_window_ms(ms);
+    }
+}
+
+impl<R: Deref<Target = MeterReadings>> AudioNode for MeterProc<R> {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let (left, right) = (ctx.inputs[0], ctx.inputs[1]);
+        self.meter.process(left, right, &self.readings);
+
+        outputs[0].replace(left);
+        outputs[1].replace(right);
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        self.meter.set_sample_rate(sample_rate);
+    }
+}
+
+impl<R: Deref<Target = MeterReadings>> Processor for MeterProc<R> {}
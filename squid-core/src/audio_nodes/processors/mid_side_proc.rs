@@ -0,0 +1,58 @@
+use crate::{
+    AudioNode,
+    dsp::stereo::StereoImaging,
+    process_context::{FixedBuf, ProcessContext},
+    processors::Processor,
+};
+
+// L/R in, M/S out.
+#[derive(Clone, Copy)]
+pub struct MidSideEncoderProc;
+
+impl AudioNode for MidSideEncoderProc {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let (mid, side) = outputs.split_at_mut(1);
+        let inputs = ctx.inputs[0]
+            .simd_chunks()
+            .iter()
+            .zip(ctx.inputs[1].simd_chunks());
+        let outputs = mid[0]
+            .simd_chunks_mut()
+            .iter_mut()
+            .zip(side[0].simd_chunks_mut());
+
+        for ((m, s), (l, r)) in outputs.zip(inputs) {
+            (*m, *s) = StereoImaging::ms_encode(*l, *r);
+        }
+    }
+
+    fn reset(&mut self, _: f32) {}
+}
+
+impl Processor for MidSideEncoderProc {}
+
+// M/S in, L/R out.
+#[derive(Clone, Copy)]
+pub struct MidSideDecoderProc;
+
+impl AudioNode for MidSideDecoderProc {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let (left, right) = outputs.split_at_mut(1);
+        let inputs = ctx.inputs[0]
+            .simd_chunks()
+            .iter()
+            .zip(ctx.inputs[1].simd_chunks());
+        let outputs = left[0]
+            .simd_chunks_mut()
+            .iter_mut()
+            .zip(right[0].simd_chunks_mut());
+
+        for ((l, r), (m, s)) in outputs.zip(inputs) {
+            (*l, *r) = StereoImaging::ms_decode(*m, *s);
+        }
+    }
+
+    fn reset(&mut self, _: f32) {}
+}
+
+impl Processor for MidSideDecoderProc {}
@@ -2,4 +2,9 @@ use crate::AudioNode;
 
 pub trait Processor: AudioNode {}
 
+pub mod balance_proc;
 pub mod gain_proc;
+pub mod meter_proc;
+pub mod mid_side_proc;
+pub mod panner_proc;
+pub mod stereo_width_proc;
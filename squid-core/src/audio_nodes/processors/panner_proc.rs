@@ -0,0 +1,58 @@
+use core::simd::Simd;
+
+use crate::{
+    AudioNode, FloatVector,
+    dsp::stereo::{PanLaw, StereoImaging},
+    process_context::{FixedBuf, ProcessContext},
+    processors::Processor,
+};
+
+// Mono in, stereo out. An optional second input is added to the pan position
+// at audio rate.
+#[derive(Clone, Copy)]
+pub struct PannerProc {
+    pan: f32,
+    law: PanLaw,
+}
+
+impl PannerProc {
+    pub fn new(law: PanLaw) -> Self {
+        Self { pan: 0.0, law }
+    }
+
+    pub fn set_pan(&mut self, pan: f32) {
+        self.pan = pan.clamp(-1.0, 1.0);
+    }
+
+    pub fn set_law(&mut self, law: PanLaw) {
+        self.law = law;
+    }
+}
+
+impl AudioNode for PannerProc {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let base = Simd::splat(self.pan);
+        let zero = FloatVector::splat(0.0);
+        let modulation = ctx.inputs.get(1).map_or(&zero, |m| &m.data);
+
+        let (left, right) = outputs.split_at_mut(1);
+        let inputs = ctx.inputs[0]
+            .simd_chunks()
+            .iter()
+            .zip(modulation.simd_chunks());
+        let outputs = left[0]
+            .simd_chunks_mut()
+            .iter_mut()
+            .zip(right[0].simd_chunks_mut());
+
+        for ((out_l, out_r), (s, m)) in outputs.zip(inputs) {
+            let (gl, gr) = StereoImaging::pan_gains(base + *m, self.law);
+            *out_l = *s * gl;
+            *out_r = *s * gr;
+        }
+    }
+
+    fn reset(&mut self, _: f32) {}
+}
+
+impl Processor for PannerProc {}
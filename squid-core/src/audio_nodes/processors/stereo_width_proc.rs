@@ -0,0 +1,49 @@
+use core::simd::Simd;
+
+use crate::{
+    AudioNode,
+    dsp::stereo::StereoImaging,
+    process_context::{FixedBuf, ProcessContext},
+    processors::Processor,
+};
+
+#[derive(Clone, Copy)]
+pub struct StereoWidthProc {
+    width: f32,
+}
+
+impl StereoWidthProc {
+    pub fn new(width: f32) -> Self {
+        Self {
+            width: width.clamp(0.0, 2.0),
+        }
+    }
+
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width.clamp(0.0, 2.0);
+    }
+}
+
+impl AudioNode for StereoWidthProc {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let width = Simd::splat(self.width);
+
+        let (left, right) = outputs.split_at_mut(1);
+        let inputs = ctx.inputs[0]
+            .simd_chunks()
+            .iter()
+            .zip(ctx.inputs[1].simd_chunks());
+        let outputs = left[0]
+            .simd_chunks_mut()
+            .iter_mut()
+            .zip(right[0].simd_chunks_mut());
+
+        for ((out_l, out_r), (l, r)) in outputs.zip(inputs) {
+            (*out_l, *out_r) = StereoImaging::width(*l, *r, width);
+        }
+    }
+
+    fn reset(&mut self, _: f32) {}
+}
+
+impl Processor for StereoWidthProc {}
@@ -2,4 +2,6 @@ use crate::AudioNode;
 
 pub trait Synth: AudioNode {}
 
+pub mod multitimbral;
 pub mod poly_synth;
+pub mod voice_allocator;
@@ -0,0 +1,241 @@
+use core::{array, simd::Simd};
+
+use crate::{
+    AudioNode, ChannelledEventSequence, EventData, EventProcessor,
+    event_processors::{BlockEvents, MAX_BLOCK_EVENTS, key_range::KeyRange, transpose::Transpose},
+    process_context::{FixedBuf, ProcessContext},
+};
+
+pub const MIDI_CHANNELS: usize = 16;
+
+// Lanes of the channel sequence: one per channel, then one for SysEx, which
+// isn't tied to a channel and goes to every slot.
+const SYSTEM_LANE: usize = MIDI_CHANNELS;
+const LANES: usize = MIDI_CHANNELS + 1;
+
+// One instrument in a `MultiTimbral` and the part of the input it plays.
+pub struct InstrumentSlot<I> {
+    instrument: I,
+    channels: u16,
+    keys: KeyRange,
+    transpose: Transpose,
+    gain: f32,
+    muted: bool,
+}
+
+impl<I: AudioNode> InstrumentSlot<I> {
+    // Listens to one channel over the whole keyboard.
+    pub fn new(instrument: I, channel: u8) -> Self {
+        let mut slot = Self {
+            instrument,
+            channels: 0,
+            keys: KeyRange::default(),
+            transpose: Transpose::default(),
+            gain: 1.0,
+            muted: false,
+        };
+        slot.set_listening(channel, true);
+        slot
+    }
+
+    pub fn instrument(&self) -> &I {
+        &self.instrument
+    }
+
+    pub fn instrument_mut(&mut self) -> &mut I {
+        &mut self.instrument
+    }
+
+    pub fn set_listening(&mut self, channel: u8, listening: bool) {
+        if (channel as usize) < MIDI_CHANNELS {
+            if listening {
+                self.channels |= 1 << channel;
+            } else {
+                self.channels &= !(1 << channel);
+            }
+        }
+    }
+
+    pub fn is_listening(&self, channel: u8) -> bool {
+        (channel as usize) < MIDI_CHANNELS && self.channels & (1 << channel) != 0
+    }
+
+    // Bit n listens to channel n.
+    pub fn set_channels(&mut self, mask: u16) {
+        self.channels = mask;
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    // Notes outside the range are ignored, for splits.
+    pub fn set_key_range(&mut self, low: u8, high: u8) {
+        self.keys = KeyRange::new(low, high);
+    }
+
+    pub fn key_range(&self) -> (u8, u8) {
+        (self.keys.low, self.keys.high)
+    }
+
+    // Applies after the key range, so splits stay where the keys are.
+    pub fn set_transpose(&mut self, semitones: i8) {
+        self.transpose.set_semitones(semitones);
+    }
+
+    pub fn transpose(&self) -> i8 {
+        self.transpose.semitones()
+    }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.gain = gain.max(0.0);
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    // A muted slot still receives its events so notes don't hang.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+}
+
+// Plays up to `SLOTS` instruments from one event stream. Each slot picks
+// the channels it listens to, a key range and a transpose, and slots that
+// share a channel layer. Use `&mut dyn AudioNode` as `I` to mix instrument
+// types.
+pub struct MultiTimbral<I, const SLOTS: usize> {
+    slots: [Option<InstrumentSlot<I>>; SLOTS],
+    channels: ChannelledEventSequence<LANES, MAX_BLOCK_EVENTS>,
+    filtered: [BlockEvents; SLOTS],
+    events: BlockEvents,
+}
+
+impl<I: AudioNode, const SLOTS: usize> MultiTimbral<I, SLOTS> {
+    pub fn new() -> Self {
+        Self {
+            slots: array::from_fn(|_| None),
+            channels: ChannelledEventSequence::new(),
+            filtered: [BlockEvents::new(); SLOTS],
+            events: BlockEvents::new(),
+        }
+    }
+
+    // Puts `instrument` in `index` listening to the channel of the same
+    // number, and returns the slot for further routing. Returns None past
+    // the last slot.
+    pub fn set_instrument(
+        &mut self,
+        index: usize,
+        instrument: I,
+    ) -> Option<&mut InstrumentSlot<I>> {
+        let slot = self.slots.get_mut(index)?;
+        let channel = (index % MIDI_CHANNELS) as u8;
+        Some(slot.insert(InstrumentSlot::new(instrument, channel)))
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<InstrumentSlot<I>> {
+        self.slots.get_mut(index)?.take()
+    }
+
+    pub fn slot(&self, index: usize) -> Option<&InstrumentSlot<I>> {
+        self.slots.get(index)?.as_ref()
+    }
+
+    pub fn slot_mut(&mut self, index: usize) -> Option<&mut InstrumentSlot<I>> {
+        self.slots.get_mut(index)?.as_mut()
+    }
+
+    pub fn slots(&self) -> impl Iterator<Item = (usize, &InstrumentSlot<I>)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| Some((i, slot.as_ref()?)))
+    }
+}
+
+impl<I: AudioNode, const SLOTS: usize> AudioNode for MultiTimbral<I, SLOTS> {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        for output in outputs.iter_mut() {
+            output.data.fill(0.0);
+        }
+        let slot_out = &mut [&mut FixedBuf::default(), &mut FixedBuf::default()];
+
+        // Events on channels past the 16th have no lane and are dropped.
+        for &event in ctx.events {
+            let lane = match event.data {
+                EventData::SysEx { .. } => SYSTEM_LANE,
+                _ => event.channel as usize,
+            };
+            self.channels.push_event(lane, event);
+        }
+
+        // Each channel goes to the slots listening to it. A slot on several
+        // channels gets them one after another, so it is put back in time
+        // order after.
+        for filtered in &mut self.filtered {
+            filtered.clear();
+        }
+        for lane in 0..LANES {
+            while let Some(event) = self.channels.pop_event(lane) {
+                for (slot, filtered) in self.slots.iter().zip(&mut self.filtered) {
+                    if let Some(slot) = slot
+                        && (lane == SYSTEM_LANE || slot.is_listening(lane as u8))
+                        && event.note().is_none_or(|note| slot.keys.contains(note))
+                    {
+                        filtered.push(event);
+                    }
+                }
+            }
+        }
+
+        for (slot, filtered) in self.slots.iter_mut().zip(&mut self.filtered) {
+            let Some(slot) = slot else {
+                continue;
+            };
+            filtered.sort_by_timing();
+            self.events.clear();
+            slot.transpose.process(ctx, filtered, &mut self.events);
+
+            slot_out[0].data.fill(0.0);
+            slot_out[1].data.fill(0.0);
+            let slot_ctx = ProcessContext {
+                events: &self.events,
+                ..*ctx
+            };
+            slot.instrument.process(&slot_ctx, slot_out);
+
+            if slot.muted {
+                continue;
+            }
+            let gain = Simd::splat(slot.gain);
+            for (output, slot_out) in outputs.iter_mut().zip(slot_out.iter()) {
+                output
+                    .data
+                    .zip_map_in_place(&slot_out.data, |o, s| o + s * gain);
+            }
+        }
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        for slot in self.slots.iter_mut().flatten() {
+            slot.instrument.reset(sample_rate);
+            slot.transpose.reset(sample_rate);
+        }
+        for filtered in &mut self.filtered {
+            filtered.clear();
+        }
+        self.events.clear();
+    }
+}
+
+impl<I: AudioNode, const SLOTS: usize> Default for MultiTimbral<I, SLOTS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,48 +1,419 @@
-use core::array;
+use core::{array, f32::consts::TAU};
+
+use libm::{exp2f, floorf, powf, sinf};
 
 use crate::{
-    AudioNode, Event, EventData, SIMD_LANES,
+    AudioNode, Event, EventData, MAX_BLOCK_SIZE, MAX_SYSEX_BYTES, MtsMessage, NOTE_BEND_RANGE,
+    Presets, SIMD_LANES, SysExAssembler, Tuning,
     dsp::mod_core::adsr_mod_source::AdsrModSource,
     oscillators::Oscillator,
     process_context::{FixedBuf, ProcessContext},
-    voice::Voice,
+    synths::voice_allocator::{MAX_VOICES, VoiceAllocator, VoiceMode},
+    voice::{NoteExpression, Voice},
 };
 
-const VOICE_COUNT: usize = 64;
+const CC_MOD_WHEEL: u8 = 1;
+const CC_SUSTAIN: u8 = 64;
+const CC_SOSTENUTO: u8 = 66;
+const CC_TIMBRE: u8 = 74;
+const CC_ALL_NOTES_OFF: u8 = 123;
+
+// Where a controller goes. Depths are for the controller at full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModTarget {
+    #[default]
+    Off,
+    // Depth in cents.
+    Vibrato,
+    // Depth in octaves.
+    FilterCutoff,
+    // Depth as the fraction of gain removed at the LFO trough.
+    Tremolo,
+    // Depth as the fraction of gain removed with the controller at zero.
+    Volume,
+}
+
+// The sound settings of a `PolySynth`, see the setters of the same names.
+// Voice mode and tuning aren't included, they belong to the player rather
+// than the sound.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolySynthPreset {
+    pub velocity_amount: f32,
+    pub velocity_curve: f32,
+    pub filter: Option<f32>,
+    pub resonance: f32,
+    pub velocity_to_cutoff: f32,
+    pub bend_range: f32,
+    pub mod_wheel_target: (ModTarget, f32),
+    pub pressure_target: (ModTarget, f32),
+    pub timbre_target: (ModTarget, f32),
+    pub lfo_rate: f32,
+}
+
+impl Default for PolySynthPreset {
+    fn default() -> Self {
+        Self {
+            velocity_amount: 1.0,
+            velocity_curve: 0.0,
+            filter: None,
+            resonance: 0.707,
+            velocity_to_cutoff: 0.0,
+            bend_range: 2.0,
+            mod_wheel_target: (ModTarget::Off, 0.0),
+            pressure_target: (ModTarget::Off, 0.0),
+            timbre_target: (ModTarget::Off, 0.0),
+            lfo_rate: 5.5,
+        }
+    }
+}
 
 pub struct PolySynth<T: Oscillator> {
-    voices: [Voice<T>; VOICE_COUNT],
+    voices: [Voice<T>; MAX_VOICES],
+    tuning: Tuning,
+    tuning_channels: u16,
+    sysex: SysExAssembler<MAX_SYSEX_BYTES>,
+    allocator: VoiceAllocator,
+
+    // Velocity response, see `set_velocity_response`.
+    velocity_amount: f32,
+    velocity_curve: f32,
+    velocity_exponent: f32,
+
+    filter: Option<(f32, f32)>,
+    velocity_to_cutoff: f32,
+
+    bend_range: f32,
+    bend: f32,
+
+    mod_wheel: f32,
+    mod_wheel_route: (ModTarget, f32),
+    pressure_route: (ModTarget, f32),
+    timbre_route: (ModTarget, f32),
+    lfo_rate: f32,
+    lfo_phase: f32,
 }
 
 impl<T: Oscillator> PolySynth<T> {
     pub fn new(osc: T, env: AdsrModSource<{ 8 }>) -> Self {
         Self {
             voices: array::from_fn(|_| Voice::new(osc.clone(), env)),
+            tuning: Tuning::default(),
+            tuning_channels: u16::MAX,
+            sysex: SysExAssembler::new(),
+            allocator: VoiceAllocator::new(),
+
+            velocity_amount: 1.0,
+            velocity_curve: 0.0,
+            velocity_exponent: 1.0,
+
+            filter: None,
+            velocity_to_cutoff: 0.0,
+
+            bend_range: 2.0,
+            bend: 0.0,
+
+            mod_wheel: 0.0,
+            mod_wheel_route: (ModTarget::Off, 0.0),
+            pressure_route: (ModTarget::Off, 0.0),
+            timbre_route: (ModTarget::Off, 0.0),
+            lfo_rate: 5.5,
+            lfo_phase: 0.0,
         }
     }
 
-    fn process_events(&mut self, events: &[Event]) {
+    // `amount` is how much of the gain follows velocity, from 0 (none) to 1.
+    // `curve` bends the response like `VelocityCurve`: positive values make
+    // soft notes louder, negative ones quieter.
+    pub fn set_velocity_response(&mut self, amount: f32, curve: f32) {
+        self.velocity_amount = amount.clamp(0.0, 1.0);
+        self.velocity_curve = curve.clamp(-1.0, 1.0);
+        self.velocity_exponent = powf(4.0, -self.velocity_curve);
+    }
+
+    // Per-voice low-pass, None turns it off.
+    pub fn set_filter(&mut self, cutoff: Option<f32>, resonance: f32) {
+        self.filter = cutoff.map(|cutoff| (cutoff.clamp(20.0, 20000.0), resonance.max(0.1)));
+    }
+
+    // Octaves the cutoff drops from full velocity to silence.
+    pub fn set_velocity_to_cutoff(&mut self, octaves: f32) {
+        self.velocity_to_cutoff = octaves.clamp(0.0, 10.0);
+    }
+
+    // Semitones either way at full bend.
+    pub fn set_bend_range(&mut self, semitones: f32) {
+        self.bend_range = semitones.clamp(0.0, 48.0);
+    }
+
+    pub fn bend_range(&self) -> f32 {
+        self.bend_range
+    }
+
+    // Current bend in semitones.
+    pub fn bend(&self) -> f32 {
+        self.bend
+    }
+
+    pub fn set_mod_wheel_target(&mut self, target: ModTarget, depth: f32) {
+        self.mod_wheel_route = (target, depth);
+    }
+
+    // Per-note pressure, e.g. from `MpeInput` or polyphonic aftertouch.
+    pub fn set_pressure_target(&mut self, target: ModTarget, depth: f32) {
+        self.pressure_route = (target, depth);
+    }
+
+    // Per-note CC74.
+    pub fn set_timbre_target(&mut self, target: ModTarget, depth: f32) {
+        self.timbre_route = (target, depth);
+    }
+
+    pub fn mod_wheel(&self) -> f32 {
+        self.mod_wheel
+    }
+
+    // Rate of the LFO used by vibrato and tremolo.
+    pub fn set_lfo_rate(&mut self, hz: f32) {
+        self.lfo_rate = hz.clamp(0.01, 50.0);
+    }
+
+    pub fn voices(&self) -> &[Voice<T>] {
+        &self.voices
+    }
+
+    pub fn allocator(&self) -> &VoiceAllocator {
+        &self.allocator
+    }
+
+    // Polyphony, stealing, priority, glide and unison settings.
+    pub fn allocator_mut(&mut self) -> &mut VoiceAllocator {
+        &mut self.allocator
+    }
+
+    // Releases whatever is sounding before switching.
+    pub fn set_voice_mode(&mut self, mode: VoiceMode) {
+        self.allocator.all_notes_off(&mut self.voices, 0);
+        self.allocator.set_mode(mode);
+    }
+
+    pub fn all_notes_off(&mut self) {
+        self.allocator.all_notes_off(&mut self.voices, 0);
+    }
+
+    // Applies from the next note on, sounding notes keep their pitch.
+    pub fn set_tuning(&mut self, tuning: Tuning) {
+        self.tuning = tuning;
+    }
+
+    pub fn tuning(&self) -> &Tuning {
+        &self.tuning
+    }
+
+    pub fn tuning_mut(&mut self) -> &mut Tuning {
+        &mut self.tuning
+    }
+
+    // Channels whose MTS octave tunings apply here, bit n for channel n.
+    // Other MTS messages aren't channel specific.
+    pub fn set_tuning_channels(&mut self, mask: u16) {
+        self.tuning_channels = mask;
+    }
+
+    // Voices apply each event at its own sample within the block.
+    fn process_events(&mut self, events: &[Event], sample_rate: f32) {
+        self.allocator.set_sample_rate(sample_rate);
+        self.allocator.start_waiting(&mut self.voices, &self.tuning);
         for event in events {
-            match event.data {
-                EventData::NoteOn { note, velocity: _ } => {
-                    if let Some(voice) = self.voices.iter_mut().find(|v| v.is_idle()) {
-                        voice.note_on(note, 44100.);
-                    }
-                }
+            let offset = event.timing as usize;
+            // MIDI 1.0 input is scaled up, so one path handles both.
+            match event.data.to_high_res() {
                 EventData::NoteOff { note } => {
-                    if let Some(voice) = self.voices.iter_mut().find(|v| v.is_playing(note)) {
-                        voice.note_off();
+                    self.allocator.note_off(
+                        &mut self.voices,
+                        offset,
+                        event.channel,
+                        note,
+                        &self.tuning,
+                    );
+                }
+                EventData::NoteOn16 { note, velocity } => {
+                    self.allocator.note_on(
+                        &mut self.voices,
+                        offset,
+                        event.channel,
+                        note,
+                        velocity as f32 / u16::MAX as f32,
+                        &self.tuning,
+                    );
+                }
+                EventData::ControlChange32 { control, value } => {
+                    self.control_change(offset, control, unipolar(value));
+                }
+                EventData::PitchBend32 { value } => {
+                    self.bend = bipolar(value) * self.bend_range;
+                }
+                EventData::NotePitchBend32 { note, value } => {
+                    self.note_expression(event.channel, note, |e| {
+                        e.bend = bipolar(value) * NOTE_BEND_RANGE
+                    });
+                }
+                EventData::NotePressure32 { note, pressure } => {
+                    self.note_expression(event.channel, note, |e| e.pressure = unipolar(pressure));
+                }
+                EventData::NoteControlChange32 {
+                    note,
+                    control: CC_TIMBRE,
+                    value,
+                } => {
+                    self.note_expression(event.channel, note, |e| e.timbre = unipolar(value));
+                }
+                EventData::SysEx { chunk } => {
+                    // MIDI Tuning Standard changes also move sounding notes.
+                    if let Some(payload) = self.sysex.push(&chunk)
+                        && let Some(message) = MtsMessage::parse(payload)
+                        && message.apply(&mut self.tuning, self.tuning_channels)
+                    {
+                        for voice in self.voices.iter_mut().filter(|v| !v.is_idle()) {
+                            voice.retune(&self.tuning);
+                        }
                     }
                 }
                 _ => {}
             }
         }
     }
+
+    // `value` from 0 to 1.
+    fn control_change(&mut self, offset: usize, control: u8, value: f32) {
+        let down = value >= 0.5;
+        match control {
+            CC_MOD_WHEEL => self.mod_wheel = value,
+            CC_SUSTAIN => self.allocator.set_sustain(&mut self.voices, offset, down),
+            CC_SOSTENUTO => self.allocator.set_sostenuto(&mut self.voices, offset, down),
+            CC_ALL_NOTES_OFF => self.allocator.all_notes_off(&mut self.voices, offset),
+            _ => {}
+        }
+    }
+
+    // Sounding and releasing voices of the note. As in MPE, a note is its
+    // channel and key.
+    fn note_expression(&mut self, channel: u8, note: u8, f: impl Fn(&mut NoteExpression)) {
+        for voice in self.voices.iter_mut() {
+            if !voice.is_idle()
+                && !voice.is_fading()
+                && voice.note() == note
+                && voice.channel() == channel
+            {
+                f(voice.expression_mut());
+            }
+        }
+    }
+
+    // Bend, velocity and controllers are applied once per block.
+    fn update_voices(&mut self, sample_rate: f32) {
+        let lfo = sinf(TAU * self.lfo_phase);
+        self.lfo_phase += self.lfo_rate * MAX_BLOCK_SIZE as f32 / sample_rate;
+        self.lfo_phase -= floorf(self.lfo_phase);
+
+        let mut global = Mods::new();
+        global.add(self.mod_wheel_route, self.mod_wheel, lfo);
+
+        for voice in self.voices.iter_mut().filter(|v| !v.is_idle()) {
+            let expression = voice.expression();
+            let mut mods = global;
+            mods.add(self.pressure_route, expression.pressure, lfo);
+            mods.add(self.timbre_route, expression.timbre, lfo);
+
+            let velocity = powf(voice.velocity(), self.velocity_exponent);
+            let semitones = self.bend + expression.bend + mods.cents / 100.0;
+            voice.set_pitch_mod(exp2f(semitones / 12.0));
+            voice.set_amp(
+                (1.0 - self.velocity_amount + self.velocity_amount * velocity) * mods.gain,
+            );
+            voice.set_filter(self.filter.map(|(cutoff, q)| {
+                let octaves = mods.octaves - self.velocity_to_cutoff * (1.0 - velocity);
+                ((cutoff * exp2f(octaves)).clamp(20.0, 20000.0), q)
+            }));
+        }
+    }
+}
+
+fn unipolar(value: u32) -> f32 {
+    (value as f64 / u32::MAX as f64) as f32
+}
+
+// -1 to 1 around the center of a 32 bit bend.
+fn bipolar(value: u32) -> f32 {
+    let center = (1u64 << 31) as f64;
+    ((value as f64 - center) / center) as f32
+}
+
+// Modulation summed from the controllers of one voice.
+#[derive(Clone, Copy)]
+struct Mods {
+    cents: f32,
+    octaves: f32,
+    gain: f32,
+}
+
+impl Mods {
+    fn new() -> Self {
+        Self {
+            cents: 0.0,
+            octaves: 0.0,
+            gain: 1.0,
+        }
+    }
+
+    // `amount` is the controller from 0 to 1, `lfo` from -1 to 1.
+    fn add(&mut self, (target, depth): (ModTarget, f32), amount: f32, lfo: f32) {
+        match target {
+            ModTarget::Off => {}
+            ModTarget::Vibrato => self.cents += amount * depth * lfo,
+            ModTarget::FilterCutoff => self.octaves += amount * depth,
+            ModTarget::Tremolo => {
+                self.gain *= 1.0 - (amount * depth).clamp(0.0, 1.0) * (0.5 - 0.5 * lfo);
+            }
+            ModTarget::Volume => self.gain *= 1.0 - depth.clamp(0.0, 1.0) * (1.0 - amount),
+        }
+    }
+}
+
+impl<T: Oscillator> Presets for PolySynth<T> {
+    type Preset = PolySynthPreset;
+
+    fn preset(&self) -> PolySynthPreset {
+        PolySynthPreset {
+            velocity_amount: self.velocity_amount,
+            velocity_curve: self.velocity_curve,
+            filter: self.filter.map(|(cutoff, _)| cutoff),
+            resonance: self.filter.map_or(0.707, |(_, q)| q),
+            velocity_to_cutoff: self.velocity_to_cutoff,
+            bend_range: self.bend_range,
+            mod_wheel_target: self.mod_wheel_route,
+            pressure_target: self.pressure_route,
+            timbre_target: self.timbre_route,
+            lfo_rate: self.lfo_rate,
+        }
+    }
+
+    // Sounding notes carry on with the new settings from the next block.
+    fn load_preset(&mut self, preset: &PolySynthPreset) {
+        self.set_velocity_response(preset.velocity_amount, preset.velocity_curve);
+        self.set_filter(preset.filter, preset.resonance);
+        self.set_velocity_to_cutoff(preset.velocity_to_cutoff);
+        self.set_bend_range(preset.bend_range);
+        self.set_mod_wheel_target(preset.mod_wheel_target.0, preset.mod_wheel_target.1);
+        self.set_pressure_target(preset.pressure_target.0, preset.pressure_target.1);
+        self.set_timbre_target(preset.timbre_target.0, preset.timbre_target.1);
+        self.set_lfo_rate(preset.lfo_rate);
+    }
 }
 
 impl<T: Oscillator> AudioNode for PolySynth<T> {
     fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
-        self.process_events(ctx.events);
+        self.process_events(ctx.events, ctx.sample_rate);
+        self.update_voices(ctx.sample_rate);
 
         let dummy_out = &mut [&mut FixedBuf::default(), &mut FixedBuf::default()];
         let sum_out = &mut [&mut FixedBuf::default(), &mut FixedBuf::default()];
@@ -0,0 +1,620 @@
+use libm::{fabsf, log2f, sqrtf};
+
+use crate::{Tuning, oscillators::Oscillator, voice::Voice};
+
+pub const MAX_VOICES: usize = 64;
+pub const MAX_UNISON: usize = 16;
+
+const MAX_HELD: usize = 16;
+const MAX_WAITING: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VoiceMode {
+    #[default]
+    Poly,
+    // One note at a time, every new note restarts the envelope.
+    Mono,
+    // One note at a time, notes played while another is held only change
+    // pitch.
+    Legato,
+}
+
+// Which held key sounds in the mono modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotePriority {
+    #[default]
+    Last,
+    Low,
+    High,
+}
+
+// Which note makes room when the polyphony limit is reached. Released notes
+// always go before held ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StealMode {
+    Oldest,
+    Quietest,
+    // A repeated key retriggers the voice already playing it, otherwise the
+    // oldest note is stolen.
+    #[default]
+    SameNote,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GlideMode {
+    // Every glide takes the glide time.
+    #[default]
+    Time,
+    // The glide time is per octave, so wider jumps take longer.
+    Rate,
+}
+
+// A poly note waiting for stolen voices to fade out.
+#[derive(Clone, Copy)]
+struct Waiting {
+    channel: u8,
+    note: u8,
+    velocity: f32,
+    key_down: bool,
+}
+
+// Decides which voices play a note. Each note takes `unison` voices, detuned
+// around its pitch, and they are stolen together.
+pub struct VoiceAllocator {
+    mode: VoiceMode,
+    priority: NotePriority,
+    steal: StealMode,
+    polyphony: usize,
+    unison: usize,
+    unison_detune: f32,
+    glide_mode: GlideMode,
+    glide_ms: f32,
+
+    // Start order of the note each voice belongs to, shared by its unison
+    // voices.
+    ages: [u64; MAX_VOICES],
+    clock: u64,
+    held: [u8; MAX_HELD],
+    held_len: usize,
+    mono_note: Option<u8>,
+    last_freq: Option<f32>,
+    waiting: [Option<Waiting>; MAX_WAITING],
+
+    // Voices whose key is down, and the ones latched by the sostenuto pedal.
+    // A voice is released once neither its key nor a pedal holds it.
+    key_down: [bool; MAX_VOICES],
+    latched: [bool; MAX_VOICES],
+    sustain: bool,
+    sostenuto: bool,
+
+    sample_rate: f32,
+    // Of the note being handled.
+    velocity: f32,
+    channel: u8,
+}
+
+impl VoiceAllocator {
+    pub fn new() -> Self {
+        Self {
+            mode: VoiceMode::Poly,
+            priority: NotePriority::Last,
+            steal: StealMode::SameNote,
+            polyphony: MAX_VOICES,
+            unison: 1,
+            unison_detune: 0.0,
+            glide_mode: GlideMode::Time,
+            glide_ms: 0.0,
+
+            ages: [0; MAX_VOICES],
+            clock: 0,
+            held: [0; MAX_HELD],
+            held_len: 0,
+            mono_note: None,
+            last_freq: None,
+            waiting: [None; MAX_WAITING],
+
+            key_down: [false; MAX_VOICES],
+            latched: [false; MAX_VOICES],
+            sustain: false,
+            sostenuto: false,
+
+            sample_rate: 44100.0,
+            velocity: 1.0,
+            channel: 0,
+        }
+    }
+
+    pub fn mode(&self) -> VoiceMode {
+        self.mode
+    }
+
+    // Notes still sounding from the previous mode are left to the caller,
+    // see `all_notes_off`.
+    pub fn set_mode(&mut self, mode: VoiceMode) {
+        self.mode = mode;
+        self.held_len = 0;
+        self.mono_note = None;
+        self.waiting = [None; MAX_WAITING];
+    }
+
+    pub fn set_priority(&mut self, priority: NotePriority) {
+        self.priority = priority;
+    }
+
+    pub fn set_steal_mode(&mut self, steal: StealMode) {
+        self.steal = steal;
+    }
+
+    // Notes, not voices, that may sound at once in poly mode.
+    pub fn set_polyphony(&mut self, polyphony: usize) {
+        self.polyphony = polyphony.clamp(1, MAX_VOICES);
+    }
+
+    pub fn polyphony(&self) -> usize {
+        self.polyphony
+    }
+
+    // Applies from the next note on.
+    pub fn set_unison(&mut self, voices: usize, detune_cents: f32) {
+        self.unison = voices.clamp(1, MAX_UNISON);
+        self.unison_detune = detune_cents.clamp(0.0, 100.0);
+    }
+
+    // 0 turns glide off.
+    pub fn set_glide(&mut self, mode: GlideMode, ms: f32) {
+        self.glide_mode = mode;
+        self.glide_ms = ms.clamp(0.0, 10000.0);
+    }
+
+    // For note pitch and glide times, set before handling notes.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    // Starts poly notes that were waiting for stolen voices, at the start of
+    // the block. Call before handling the block's notes.
+    pub fn start_waiting<T: Oscillator>(&mut self, voices: &mut [Voice<T>], tuning: &Tuning) {
+        let len = voices.len().min(MAX_VOICES);
+        let voices = &mut voices[..len];
+
+        while let Some(waiting) = self.waiting[0] {
+            let mut chosen = [0; MAX_UNISON];
+            let count = self.idle_voices(voices, &mut chosen);
+            if count < self.unison.min(len) {
+                return;
+            }
+            self.waiting.copy_within(1.., 0);
+            self.waiting[MAX_WAITING - 1] = None;
+
+            self.velocity = waiting.velocity;
+            self.channel = waiting.channel;
+            self.start(voices, &chosen[..count], 0, waiting.note, true, tuning);
+            // Released while waiting but held by a pedal.
+            if !waiting.key_down {
+                for &i in &chosen[..count] {
+                    self.key_down[i] = false;
+                }
+            }
+        }
+    }
+
+    pub fn note_on<T: Oscillator>(
+        &mut self,
+        voices: &mut [Voice<T>],
+        offset: usize,
+        channel: u8,
+        note: u8,
+        // 0 to 1.
+        velocity: f32,
+        tuning: &Tuning,
+    ) {
+        if tuning.frequency(note).is_none() {
+            return;
+        }
+        self.velocity = velocity;
+        self.channel = channel;
+        match self.mode {
+            VoiceMode::Poly => self.poly_note_on(voices, offset, note, tuning),
+            VoiceMode::Mono | VoiceMode::Legato => {
+                self.push_held(note);
+                let target = self.target();
+                if target != self.mono_note
+                    && let Some(target) = target
+                {
+                    let retrigger = self.mode == VoiceMode::Mono || self.mono_note.is_none();
+                    self.play_mono(voices, offset, target, retrigger, tuning);
+                }
+            }
+        }
+    }
+
+    // Poly notes are told apart by channel as well as key, the mono modes
+    // only go by key.
+    pub fn note_off<T: Oscillator>(
+        &mut self,
+        voices: &mut [Voice<T>],
+        offset: usize,
+        channel: u8,
+        note: u8,
+        tuning: &Tuning,
+    ) {
+        if self.mode == VoiceMode::Poly {
+            if let Some(w) = self.waiting_note(channel, note) {
+                let waiting = self.waiting[w].as_mut().unwrap();
+                waiting.key_down = false;
+                if !self.sustain {
+                    self.remove_waiting(w);
+                }
+            }
+            for (i, voice) in voices.iter_mut().enumerate().take(MAX_VOICES) {
+                if voice.is_playing(note)
+                    && voice.channel() == channel
+                    && !voice.is_fading()
+                    && self.key_down[i]
+                {
+                    self.key_down[i] = false;
+                    if !self.is_pedalled(i) {
+                        voice.note_off_at(offset);
+                    }
+                }
+            }
+            return;
+        }
+
+        self.remove_held(note);
+        if self.mono_note != Some(note) {
+            return;
+        }
+        match self.target() {
+            Some(target) => {
+                let retrigger = self.mode == VoiceMode::Mono;
+                self.play_mono(voices, offset, target, retrigger, tuning);
+            }
+            None => {
+                for (i, voice) in voices.iter_mut().enumerate().take(MAX_VOICES) {
+                    self.key_down[i] = false;
+                    if voice.is_active() && !self.is_pedalled(i) {
+                        voice.note_off_at(offset);
+                    }
+                }
+                self.mono_note = None;
+            }
+        }
+    }
+
+    // Releases everything, pedals included.
+    pub fn all_notes_off<T: Oscillator>(&mut self, voices: &mut [Voice<T>], offset: usize) {
+        for voice in voices.iter_mut().filter(|v| v.is_active()) {
+            voice.note_off_at(offset);
+        }
+        self.held_len = 0;
+        self.mono_note = None;
+        self.waiting = [None; MAX_WAITING];
+        self.key_down = [false; MAX_VOICES];
+        self.latched = [false; MAX_VOICES];
+    }
+
+    pub fn sustain(&self) -> bool {
+        self.sustain
+    }
+
+    pub fn sostenuto(&self) -> bool {
+        self.sostenuto
+    }
+
+    // While down, released keys keep sounding until the pedal comes up.
+    pub fn set_sustain<T: Oscillator>(
+        &mut self,
+        voices: &mut [Voice<T>],
+        offset: usize,
+        down: bool,
+    ) {
+        self.sustain = down;
+        if !down {
+            self.release_pedalled(voices, offset);
+        }
+    }
+
+    // Like sustain, but only for the keys held when the pedal goes down.
+    pub fn set_sostenuto<T: Oscillator>(
+        &mut self,
+        voices: &mut [Voice<T>],
+        offset: usize,
+        down: bool,
+    ) {
+        if down && !self.sostenuto {
+            for (i, voice) in voices.iter().enumerate().take(MAX_VOICES) {
+                self.latched[i] = voice.is_active() && self.key_down[i];
+            }
+        }
+        self.sostenuto = down;
+        if !down {
+            self.latched = [false; MAX_VOICES];
+            self.release_pedalled(voices, offset);
+        }
+    }
+
+    fn is_pedalled(&self, i: usize) -> bool {
+        self.sustain || self.latched[i]
+    }
+
+    fn release_pedalled<T: Oscillator>(&mut self, voices: &mut [Voice<T>], offset: usize) {
+        if !self.sustain {
+            while let Some(w) = self
+                .waiting
+                .iter()
+                .position(|w| w.is_some_and(|w| !w.key_down))
+            {
+                self.remove_waiting(w);
+            }
+        }
+        for (i, voice) in voices.iter_mut().enumerate().take(MAX_VOICES) {
+            if voice.is_active() && !self.key_down[i] && !self.is_pedalled(i) {
+                voice.note_off_at(offset);
+            }
+        }
+    }
+
+    fn poly_note_on<T: Oscillator>(
+        &mut self,
+        voices: &mut [Voice<T>],
+        offset: usize,
+        note: u8,
+        tuning: &Tuning,
+    ) {
+        let len = voices.len().min(MAX_VOICES);
+        let voices = &mut voices[..len];
+
+        if let Some(w) = self.waiting_note(self.channel, note) {
+            let waiting = self.waiting[w].as_mut().unwrap();
+            waiting.velocity = self.velocity;
+            waiting.key_down = true;
+            return;
+        }
+
+        if self.steal == StealMode::SameNote
+            && let Some(i) = voices.iter().position(|v| {
+                !v.is_idle() && !v.is_fading() && v.note() == note && v.channel() == self.channel
+            })
+        {
+            let group = self.ages[i];
+            self.clock += 1;
+            for (voice, age) in voices.iter_mut().zip(&mut self.ages) {
+                if *age == group && !voice.is_fading() {
+                    voice.set_velocity(self.velocity);
+                    voice.set_channel(self.channel);
+                    voice.note_on_at(offset, note, tuning, self.sample_rate);
+                    *age = self.clock;
+                }
+            }
+            for (i, age) in self.ages.iter().enumerate().take(len) {
+                if *age == self.clock {
+                    self.key_down[i] = true;
+                    self.latched[i] = false;
+                }
+            }
+            self.last_freq = tuning.frequency(note).map(Into::into);
+            return;
+        }
+
+        // Each note needs `unison` voices, so fewer notes may fit than the
+        // polyphony allows.
+        let limit = self.polyphony.min(len / self.unison).max(1);
+        while self.sounding_notes(voices) + self.waiting_len() >= limit {
+            let Some(victim) = self.victim(voices) else {
+                break;
+            };
+            for (voice, &age) in voices.iter_mut().zip(&self.ages) {
+                if age == victim && !voice.is_fading() {
+                    voice.kill_at(offset);
+                }
+            }
+        }
+
+        // Stolen voices keep fading out, the note waits for them unless
+        // there are enough idle ones. Only with too many notes waiting are
+        // fading voices cut short.
+        let mut chosen = [0; MAX_UNISON];
+        let mut count = 0;
+        if self.waiting_len() == 0 {
+            count = self.idle_voices(voices, &mut chosen);
+        }
+        if count < self.unison.min(len) {
+            let waiting = Waiting {
+                channel: self.channel,
+                note,
+                velocity: self.velocity,
+                key_down: true,
+            };
+            if let Some(slot) = self.waiting.iter_mut().find(|w| w.is_none()) {
+                *slot = Some(waiting);
+                return;
+            }
+            for (i, voice) in voices.iter().enumerate() {
+                if count < self.unison && voice.is_fading() && !chosen[..count].contains(&i) {
+                    chosen[count] = i;
+                    count += 1;
+                }
+            }
+        }
+
+        self.start(voices, &chosen[..count], offset, note, true, tuning);
+    }
+
+    // Up to `unison` idle voices, returns how many were found.
+    fn idle_voices<T: Oscillator>(
+        &self,
+        voices: &[Voice<T>],
+        chosen: &mut [usize; MAX_UNISON],
+    ) -> usize {
+        let mut count = 0;
+        for (i, voice) in voices.iter().enumerate() {
+            if count < self.unison && voice.is_idle() {
+                chosen[count] = i;
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn waiting_len(&self) -> usize {
+        self.waiting.iter().flatten().count()
+    }
+
+    fn waiting_note(&self, channel: u8, note: u8) -> Option<usize> {
+        self.waiting
+            .iter()
+            .position(|w| w.is_some_and(|w| w.channel == channel && w.note == note))
+    }
+
+    fn remove_waiting(&mut self, i: usize) {
+        self.waiting.copy_within(i + 1.., i);
+        self.waiting[MAX_WAITING - 1] = None;
+    }
+
+    fn play_mono<T: Oscillator>(
+        &mut self,
+        voices: &mut [Voice<T>],
+        offset: usize,
+        note: u8,
+        retrigger: bool,
+        tuning: &Tuning,
+    ) {
+        let count = self.unison.min(voices.len());
+        let mut chosen = [0; MAX_UNISON];
+        for (i, c) in chosen[..count].iter_mut().enumerate() {
+            *c = i;
+        }
+        // Voices left over from a wider unison setting.
+        for voice in voices[count..].iter_mut().filter(|v| v.is_active()) {
+            voice.note_off_at(offset);
+        }
+
+        self.start(voices, &chosen[..count], offset, note, retrigger, tuning);
+        self.mono_note = Some(note);
+    }
+
+    // Glides from the pitch the first voice is sounding, or from the last
+    // note played if it is idle.
+    fn start<T: Oscillator>(
+        &mut self,
+        voices: &mut [Voice<T>],
+        chosen: &[usize],
+        offset: usize,
+        note: u8,
+        retrigger: bool,
+        tuning: &Tuning,
+    ) {
+        let Some(freq) = tuning.frequency(note).map(f32::from) else {
+            return;
+        };
+        let from = match chosen.first().map(|&i| &voices[i]) {
+            Some(voice) if !voice.is_idle() => Some(voice.frequency()),
+            _ => self.last_freq,
+        };
+        let sample_rate = self.sample_rate;
+        let glide = from.map_or(0, |from| self.glide_samples(from, freq, sample_rate));
+
+        self.clock += 1;
+        let count = chosen.len();
+        for (n, &i) in chosen.iter().enumerate() {
+            let voice = &mut voices[i];
+            let spread = if count > 1 {
+                2.0 * n as f32 / (count - 1) as f32 - 1.0
+            } else {
+                0.0
+            };
+            voice.set_detune(spread * self.unison_detune);
+            voice.set_gain(1.0 / sqrtf(count as f32));
+            voice.set_channel(self.channel);
+
+            if retrigger || !voice.is_active() {
+                voice.set_velocity(self.velocity);
+                voice.note_on_at(offset, note, tuning, sample_rate);
+            } else {
+                voice.legato_to(note, tuning, sample_rate);
+            }
+            if let Some(from) = from {
+                voice.glide_from(from, glide);
+            }
+            self.ages[i] = self.clock;
+            self.key_down[i] = true;
+            self.latched[i] = false;
+        }
+        self.last_freq = Some(freq);
+    }
+
+    fn glide_samples(&self, from: f32, to: f32, sample_rate: f32) -> u32 {
+        if self.glide_ms <= 0.0 || from <= 0.0 {
+            return 0;
+        }
+        let ms = match self.glide_mode {
+            GlideMode::Time => self.glide_ms,
+            GlideMode::Rate => self.glide_ms * fabsf(log2f(to / from)),
+        };
+        (ms * 0.001 * sample_rate) as u32
+    }
+
+    // Notes holding voices that aren't being stolen.
+    fn sounding_notes<T: Oscillator>(&self, voices: &[Voice<T>]) -> usize {
+        let sounding = |v: &Voice<T>| !v.is_idle() && !v.is_fading();
+        voices
+            .iter()
+            .enumerate()
+            .filter(|&(i, v)| {
+                sounding(v)
+                    && !voices[..i]
+                        .iter()
+                        .zip(&self.ages)
+                        .any(|(w, &age)| sounding(w) && age == self.ages[i])
+            })
+            .count()
+    }
+
+    fn victim<T: Oscillator>(&self, voices: &[Voice<T>]) -> Option<u64> {
+        voices
+            .iter()
+            .zip(&self.ages)
+            .filter(|(v, _)| !v.is_idle() && !v.is_fading())
+            .min_by(|(a, age_a), (b, age_b)| {
+                let order = match self.steal {
+                    StealMode::Quietest => a.level().total_cmp(&b.level()),
+                    StealMode::Oldest | StealMode::SameNote => age_a.cmp(age_b),
+                };
+                a.is_active().cmp(&b.is_active()).then(order)
+            })
+            .map(|(_, &age)| age)
+    }
+
+    fn target(&self) -> Option<u8> {
+        let held = self.held[..self.held_len].iter().copied();
+        match self.priority {
+            NotePriority::Last => self.held[..self.held_len].last().copied(),
+            NotePriority::Low => held.min(),
+            NotePriority::High => held.max(),
+        }
+    }
+
+    fn push_held(&mut self, note: u8) {
+        self.remove_held(note);
+        if self.held_len == MAX_HELD {
+            self.held.copy_within(1.., 0);
+            self.held_len -= 1;
+        }
+        self.held[self.held_len] = note;
+        self.held_len += 1;
+    }
+
+    fn remove_held(&mut self, note: u8) {
+        if let Some(i) = self.held[..self.held_len].iter().position(|&n| n == note) {
+            self.held.copy_within(i + 1..self.held_len, i);
+            self.held_len -= 1;
+        }
+    }
+}
+
+impl Default for VoiceAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,13 +1,49 @@
 use core::simd::{Mask, Simd};
 
+use libm::{exp2f, powf};
+
 use crate::{
-    AudioNode, FloatVector, Note, SIMD_LANES, VOICE_GAIN,
-    dsp::mod_core::adsr_mod_source::AdsrModSource,
+    AudioNode, FloatVector, MAX_BLOCK_SIZE, SIMD_LANES, Tuning, VOICE_GAIN,
+    dsp::{filters::sv_filter::ScalarSvf, mod_core::adsr_mod_source::AdsrModSource},
     modulators::envlopes::{Envelope, ar_env::ArEnv},
     oscillators::Oscillator,
     process_context::{FixedBuf, ProcessContext},
 };
 
+// Enough for a retrigger and release of one voice inside a block.
+const MAX_PENDING: usize = 4;
+
+// Fade applied to a stolen voice before it goes idle.
+const KILL_FADE_SECONDS: f32 = 0.003;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Gate {
+    On,
+    Off,
+    Kill,
+}
+
+// Per-note expression, e.g. from MPE. Reset when the note starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteExpression {
+    // Semitones.
+    pub bend: f32,
+    // 0 to 1.
+    pub pressure: f32,
+    // 0 to 1, centered by default like MPE's CC74.
+    pub timbre: f32,
+}
+
+impl Default for NoteExpression {
+    fn default() -> Self {
+        Self {
+            bend: 0.0,
+            pressure: 0.0,
+            timbre: 0.5,
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Voice<T: Oscillator> {
     osc: T,
@@ -16,6 +52,28 @@ pub struct Voice<T: Oscillator> {
     sample_rate: f32,
     freq: f32,
     note: u8,
+    channel: u8,
+
+    // Envelope changes waiting for their sample in the next block.
+    pending: [(usize, Gate); MAX_PENDING],
+    pending_len: usize,
+    level: f32,
+    fade: Option<f32>,
+
+    // Sounding pitch, gliding towards `freq` for `glide_left` samples.
+    current: f32,
+    glide_ratio: f32,
+    glide_left: u32,
+    detune: f32,
+    gain: f32,
+
+    // Set per block by the instrument from velocity, bend and modulation.
+    velocity: f32,
+    expression: NoteExpression,
+    amp: f32,
+    pitch_mod: f32,
+    filter: Option<(f32, f32)>,
+    filters: [ScalarSvf; 2],
 }
 
 impl<T> Voice<T>
@@ -30,31 +88,251 @@ where
             sample_rate: 0.,
             freq: 0.,
             note: 0,
+            channel: 0,
+
+            pending: [(0, Gate::Off); MAX_PENDING],
+            pending_len: 0,
+            level: 0.,
+            fade: None,
+
+            current: 0.,
+            glide_ratio: 1.,
+            glide_left: 0,
+            detune: 1.,
+            gain: 1.,
+
+            velocity: 1.,
+            expression: NoteExpression::default(),
+            amp: 1.,
+            pitch_mod: 1.,
+            filter: None,
+            filters: [ScalarSvf::new(); 2],
         }
     }
 
     pub fn is_idle(&self) -> bool {
-        !self.active && self.env.is_idle()
+        !self.active && self.env.is_idle() && self.pending_len == 0
     }
 
     pub fn is_playing(&self, note: u8) -> bool {
         self.active && self.note == note
     }
 
-    pub fn note_on(&mut self, note: u8, sample_rate: f32) {
+    // Key still held, as opposed to releasing or idle.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // Stolen and on its way to idle.
+    pub fn is_fading(&self) -> bool {
+        match self.pending[..self.pending_len]
+            .iter()
+            .rposition(|p| p.1 != Gate::Off)
+        {
+            Some(i) => self.pending[i].1 == Gate::Kill,
+            None => self.fade.is_some(),
+        }
+    }
+
+    pub fn note(&self) -> u8 {
+        self.note
+    }
+
+    // Envelope level at the end of the last block.
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    // Channel of the note, so notes on the same key from different MPE
+    // member channels stay apart.
+    pub fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    pub fn set_channel(&mut self, channel: u8) {
+        self.channel = channel;
+    }
+
+    // Sounding pitch without detune.
+    pub fn frequency(&self) -> f32 {
+        self.current
+    }
+
+    // Keys the tuning leaves unmapped don't sound.
+    pub fn note_on(&mut self, note: u8, tuning: &Tuning, sample_rate: f32) {
+        self.note_on_at(0, note, tuning, sample_rate);
+    }
+
+    pub fn note_off(&mut self) {
+        self.note_off_at(0);
+    }
+
+    // The envelope starts `offset` samples into the next processed block.
+    // Pitch follows from the start of that block.
+    pub fn note_on_at(&mut self, offset: usize, note: u8, tuning: &Tuning, sample_rate: f32) {
+        if self.set_note(note, tuning, sample_rate) {
+            self.expression = NoteExpression::default();
+            self.push_pending(offset, Gate::On);
+        }
+    }
+
+    pub fn note_off_at(&mut self, offset: usize) {
+        self.active = false;
+        self.push_pending(offset, Gate::Off);
+    }
+
+    // Follows a change to the tuning while sounding, dropping any glide.
+    pub fn retune(&mut self, tuning: &Tuning) {
+        if let Some(freq) = tuning.frequency(self.note) {
+            self.freq = freq.into();
+            self.current = self.freq;
+            self.glide_left = 0;
+            self.configure_osc();
+        }
+    }
+
+    // Changes pitch without restarting the envelope.
+    pub fn legato_to(&mut self, note: u8, tuning: &Tuning, sample_rate: f32) {
+        self.set_note(note, tuning, sample_rate);
+    }
+
+    // Fades out quickly from `offset` on, for voice stealing.
+    pub fn kill_at(&mut self, offset: usize) {
+        self.active = false;
+        self.push_pending(offset, Gate::Kill);
+    }
+
+    // Slides from `freq` to the current note over `samples`, evenly in
+    // pitch.
+    pub fn glide_from(&mut self, freq: f32, samples: u32) {
+        if samples == 0 || freq <= 0. || self.freq <= 0. {
+            return;
+        }
+        self.current = freq;
+        self.glide_left = samples;
+        self.glide_ratio = powf(self.freq / freq, 1. / samples as f32);
+        self.configure_osc();
+    }
+
+    pub fn set_detune(&mut self, cents: f32) {
+        self.detune = exp2f(cents / 1200.);
+        self.configure_osc();
+    }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.gain = gain;
+    }
+
+    // 0 to 1.
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    pub fn set_velocity(&mut self, velocity: f32) {
+        self.velocity = velocity.clamp(0., 1.);
+    }
+
+    pub fn expression(&self) -> NoteExpression {
+        self.expression
+    }
+
+    pub fn expression_mut(&mut self) -> &mut NoteExpression {
+        &mut self.expression
+    }
+
+    // Gain on top of the envelope, e.g. from velocity.
+    pub fn set_amp(&mut self, amp: f32) {
+        self.amp = amp;
+    }
+
+    // Frequency ratio for bends and vibrato, on top of glide and detune.
+    pub fn set_pitch_mod(&mut self, ratio: f32) {
+        if ratio != self.pitch_mod {
+            self.pitch_mod = ratio;
+            self.configure_osc();
+        }
+    }
+
+    // Low-pass cutoff and Q, None bypasses the filter.
+    pub fn set_filter(&mut self, filter: Option<(f32, f32)>) {
+        self.filter = filter;
+    }
+
+    fn set_note(&mut self, note: u8, tuning: &Tuning, sample_rate: f32) -> bool {
+        let Some(freq) = tuning.frequency(note) else {
+            return false;
+        };
+
         self.note = note;
-        self.freq = Note::from_midi(note).to_frequency().into();
+        self.freq = freq.into();
+        self.current = self.freq;
+        self.glide_left = 0;
         self.sample_rate = sample_rate;
         self.active = true;
 
-        self.osc.configure(self.freq, self.sample_rate, None);
+        self.configure_osc();
+        true
+    }
 
-        self.env.note_on(Mask::splat(true));
+    fn configure_osc(&mut self) {
+        let freq = self.current * self.detune * self.pitch_mod;
+        self.osc.configure(freq, self.sample_rate, None);
     }
 
-    pub fn note_off(&mut self) {
-        self.active = false;
-        self.env.note_off(Mask::splat(true));
+    fn push_pending(&mut self, offset: usize, gate: Gate) {
+        let offset = offset.min(MAX_BLOCK_SIZE - 1);
+        if self.pending_len == MAX_PENDING {
+            // Keep the newest state, it is what the voice ends up in.
+            self.pending.copy_within(1.., 0);
+            self.pending_len -= 1;
+        }
+        self.pending[self.pending_len] = (offset, gate);
+        self.pending_len += 1;
+    }
+
+    // Envelope levels for the block. Between pending changes the envelope
+    // steps once per SIMD chunk, and a change lands on its exact sample.
+    fn envelope(&mut self) -> FloatVector {
+        let fade_step = 1. / (KILL_FADE_SECONDS * self.sample_rate).max(1.);
+        let mut v_mod = FloatVector::splat(0.);
+        let mut next = 0;
+
+        for (i, out) in v_mod.iter_mut().enumerate() {
+            let mut changed = false;
+            while next < self.pending_len && self.pending[next].0 <= i {
+                match self.pending[next].1 {
+                    Gate::On => {
+                        self.env.note_on(Mask::splat(true));
+                        self.fade = None;
+                    }
+                    Gate::Off => self.env.note_off(Mask::splat(true)),
+                    Gate::Kill => self.fade = Some(1.),
+                }
+                changed = true;
+                next += 1;
+            }
+            if changed || i % SIMD_LANES == 0 {
+                self.level = self.env.process()[0];
+            }
+
+            let mut gain = 1.;
+            if let Some(fade) = self.fade {
+                let fade = fade - fade_step;
+                if fade <= 0. {
+                    self.env.reset();
+                    self.level = 0.;
+                    self.fade = None;
+                    gain = 0.;
+                } else {
+                    self.fade = Some(fade);
+                    gain = fade;
+                }
+            }
+            *out = self.level * gain;
+        }
+
+        self.pending_len = 0;
+        v_mod
     }
 }
 
@@ -67,18 +345,45 @@ where
             return;
         }
 
+        if ctx.sample_rate != self.sample_rate {
+            self.sample_rate = ctx.sample_rate;
+            self.configure_osc();
+        }
+        if self.glide_left > 0 {
+            let step = self.glide_left.min(MAX_BLOCK_SIZE as u32);
+            self.glide_left -= step;
+            self.current = if self.glide_left == 0 {
+                self.freq
+            } else {
+                self.current * powf(self.glide_ratio, step as f32)
+            };
+            self.configure_osc();
+        }
         self.osc.process(ctx, outputs);
 
-        let mut v_mod = FloatVector::splat(0.);
-        v_mod.map_in_place(|_| self.env.process());
+        if let Some((cutoff, q)) = self.filter {
+            for (filter, out) in self.filters.iter_mut().zip(outputs.iter_mut()) {
+                filter.update_coeffs(cutoff, q, self.sample_rate);
+                filter.process_block_lp(out);
+            }
+        }
 
-        let g = Simd::splat(VOICE_GAIN);
+        let v_mod = self.envelope();
+
+        let g = Simd::splat(VOICE_GAIN * self.gain * self.amp);
         outputs[0].data.zip_map_in_place(&v_mod, |c, m| c * g * m);
         outputs[1].data.zip_map_in_place(&v_mod, |c, m| c * g * m);
     }
 
     fn reset(&mut self, sample_rate: f32) {
         self.osc.reset(sample_rate);
+        self.env.reset();
+        self.sample_rate = sample_rate;
         self.active = false;
+        self.pending_len = 0;
+        self.level = 0.;
+        self.fade = None;
+        self.glide_left = 0;
+        self.filters = [ScalarSvf::new(); 2];
     }
 }
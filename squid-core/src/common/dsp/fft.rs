@@ -0,0 +1,389 @@
+// Allocation free FFTs. Data is kept split into real and imaginary slices so
+// the butterflies can work on whole SIMD vectors; a `VecBlock` (or `FixedBuf`)
+// can be passed in directly through its slice deref.
+
+use core::{
+    f64::consts::PI,
+    ops::{Add, Mul, Sub},
+    simd::Simd,
+};
+
+use libm::{atan2f, cos, sin, sqrtf};
+
+use crate::SIMD_LANES;
+
+type Lanes = Simd<f32, SIMD_LANES>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub const fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    pub fn from_polar(norm: f32, arg: f32) -> Self {
+        Self::new(norm * libm::cosf(arg), norm * libm::sinf(arg))
+    }
+
+    #[inline(always)]
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    #[inline(always)]
+    pub fn norm_sqr(self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    pub fn norm(self) -> f32 {
+        sqrtf(self.norm_sqr())
+    }
+
+    pub fn arg(self) -> f32 {
+        atan2f(self.im, self.re)
+    }
+
+    #[inline(always)]
+    pub fn scale(self, s: f32) -> Self {
+        Self::new(self.re * s, self.im * s)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+// In-place complex FFT of a power of two `SIZE` (at least 4). The first two
+// radix-2 stages are merged into a single radix-4 pass, stages wider than a
+// SIMD vector run vectorised.
+#[derive(Clone, Copy)]
+pub struct Fft<const SIZE: usize> {
+    // Twiddles of the stage with half width `h` are stored contiguously at
+    // `h..2h`, so the vectorised stages can load them directly.
+    tw_re: [f32; SIZE],
+    tw_im: [f32; SIZE],
+}
+
+impl<const SIZE: usize> Fft<SIZE> {
+    pub fn new() -> Self {
+        assert!(
+            SIZE >= 4 && SIZE.is_power_of_two(),
+            "FFT size must be a power of two"
+        );
+
+        let mut tw_re = [0.0; SIZE];
+        let mut tw_im = [0.0; SIZE];
+
+        let mut h = 1;
+        while h < SIZE {
+            for k in 0..h {
+                let angle = -PI * k as f64 / h as f64;
+                tw_re[h + k] = cos(angle) as f32;
+                tw_im[h + k] = sin(angle) as f32;
+            }
+            h *= 2;
+        }
+
+        Self { tw_re, tw_im }
+    }
+
+    pub const fn size(&self) -> usize {
+        SIZE
+    }
+
+    pub fn forward(&self, re: &mut [f32], im: &mut [f32]) {
+        self.transform(re, im, SIZE);
+    }
+
+    // Unnormalised forward transform conjugated on both sides, scaled by 1 / SIZE.
+    pub fn inverse(&self, re: &mut [f32], im: &mut [f32]) {
+        self.inverse_transform(re, im, SIZE);
+    }
+
+    // Stage twiddles do not depend on the total size, so any smaller power of
+    // two can be transformed with the same tables.
+    fn transform(&self, re: &mut [f32], im: &mut [f32], len: usize) {
+        let re = &mut re[..len];
+        let im = &mut im[..len];
+
+        Self::bit_reverse(re, im);
+        Self::radix4_pass(re, im);
+
+        let mut h = 4;
+        while h < len {
+            if h >= SIMD_LANES {
+                self.radix2_simd(re, im, h);
+            } else {
+                self.radix2_scalar(re, im, h);
+            }
+            h *= 2;
+        }
+    }
+
+    fn inverse_transform(&self, re: &mut [f32], im: &mut [f32], len: usize) {
+        im[..len].iter_mut().for_each(|x| *x = -*x);
+        self.transform(re, im, len);
+
+        let scale = 1.0 / len as f32;
+        re[..len].iter_mut().for_each(|x| *x *= scale);
+        im[..len].iter_mut().for_each(|x| *x *= -scale);
+    }
+
+    fn bit_reverse(re: &mut [f32], im: &mut [f32]) {
+        let len = re.len();
+        let shift = usize::BITS - len.trailing_zeros();
+        for i in 0..len {
+            let j = i.reverse_bits() >> shift;
+            if j > i {
+                re.swap(i, j);
+                im.swap(i, j);
+            }
+        }
+    }
+
+    // Stage widths 2 and 4 at once, the only twiddle involved is -i.
+    #[inline(always)]
+    fn radix4_pass(re: &mut [f32], im: &mut [f32]) {
+        for (r, i) in re.chunks_exact_mut(4).zip(im.chunks_exact_mut(4)) {
+            let (r0, i0) = (r[0] + r[1], i[0] + i[1]);
+            let (r1, i1) = (r[0] - r[1], i[0] - i[1]);
+            let (r2, i2) = (r[2] + r[3], i[2] + i[3]);
+            let (r3, i3) = (r[2] - r[3], i[2] - i[3]);
+
+            r[0] = r0 + r2;
+            i[0] = i0 + i2;
+            r[2] = r0 - r2;
+            i[2] = i0 - i2;
+
+            // (r3 + i*i3) * -i = i3 - i*r3
+            r[1] = r1 + i3;
+            i[1] = i1 - r3;
+            r[3] = r1 - i3;
+            i[3] = i1 + r3;
+        }
+    }
+
+    fn radix2_scalar(&self, re: &mut [f32], im: &mut [f32], h: usize) {
+        for start in (0..re.len()).step_by(2 * h) {
+            for k in 0..h {
+                let a = start + k;
+                let b = a + h;
+                let (wr, wi) = (self.tw_re[h + k], self.tw_im[h + k]);
+
+                let tr = re[b] * wr - im[b] * wi;
+                let ti = re[b] * wi + im[b] * wr;
+
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+    }
+
+    fn radix2_simd(&self, re: &mut [f32], im: &mut [f32], h: usize) {
+        for start in (0..re.len()).step_by(2 * h) {
+            let (re_lo, re_hi) = re[start..start + 2 * h].split_at_mut(h);
+            let (im_lo, im_hi) = im[start..start + 2 * h].split_at_mut(h);
+
+            let lo = re_lo
+                .as_chunks_mut::<SIMD_LANES>()
+                .0
+                .iter_mut()
+                .zip(im_lo.as_chunks_mut::<SIMD_LANES>().0.iter_mut());
+            let hi = re_hi
+                .as_chunks_mut::<SIMD_LANES>()
+                .0
+                .iter_mut()
+                .zip(im_hi.as_chunks_mut::<SIMD_LANES>().0.iter_mut());
+            let tw = self.tw_re[h..2 * h]
+                .as_chunks::<SIMD_LANES>()
+                .0
+                .iter()
+                .zip(self.tw_im[h..2 * h].as_chunks::<SIMD_LANES>().0.iter());
+
+            for (((ar, ai), (br, bi)), (wr, wi)) in lo.zip(hi).zip(tw) {
+                let (a_re, a_im) = (Lanes::from_array(*ar), Lanes::from_array(*ai));
+                let (b_re, b_im) = (Lanes::from_array(*br), Lanes::from_array(*bi));
+                let (w_re, w_im) = (Lanes::from_array(*wr), Lanes::from_array(*wi));
+
+                let t_re = b_re * w_re - b_im * w_im;
+                let t_im = b_re * w_im + b_im * w_re;
+
+                *ar = (a_re + t_re).to_array();
+                *ai = (a_im + t_im).to_array();
+                *br = (a_re - t_re).to_array();
+                *bi = (a_im - t_im).to_array();
+            }
+        }
+    }
+}
+
+impl<const SIZE: usize> Default for Fft<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// FFT of `SIZE` real samples computed through a complex FFT of half the size.
+// Spectra have `SIZE / 2 + 1` bins, DC at index 0 and Nyquist at `SIZE / 2`.
+#[derive(Clone, Copy)]
+pub struct RealFft<const SIZE: usize> {
+    // The last stage of a full size FFT holds exactly the e^(-2 pi i k / SIZE)
+    // twiddles needed to split the half size result.
+    fft: Fft<SIZE>,
+}
+
+impl<const SIZE: usize> RealFft<SIZE> {
+    pub const BINS: usize = SIZE / 2 + 1;
+
+    pub fn new() -> Self {
+        assert!(SIZE >= 8, "real FFT size must be at least 8");
+        Self { fft: Fft::new() }
+    }
+
+    pub const fn size(&self) -> usize {
+        SIZE
+    }
+
+    // `re` and `im` need room for at least `BINS` values.
+    pub fn forward(&self, input: &[f32], re: &mut [f32], im: &mut [f32]) {
+        let half = SIZE / 2;
+
+        // Even samples become the real part, odd samples the imaginary part.
+        for (n, pair) in input[..SIZE].chunks_exact(2).enumerate() {
+            re[n] = pair[0];
+            im[n] = pair[1];
+        }
+        self.fft.transform(re, im, half);
+
+        let z0 = Complex::new(re[0], im[0]);
+        re[0] = z0.re + z0.im;
+        im[0] = 0.0;
+        re[half] = z0.re - z0.im;
+        im[half] = 0.0;
+
+        // Bins k and half - k depend on each other, so they are split together.
+        for k in 1..=half / 2 {
+            let m = half - k;
+            let zk = Complex::new(re[k], im[k]);
+            let zm = Complex::new(re[m], im[m]);
+
+            let (xk, xm) = self.split(zk, zm, k);
+            re[k] = xk.re;
+            im[k] = xk.im;
+            re[m] = xm.re;
+            im[m] = xm.im;
+        }
+    }
+
+    // Consumes the spectrum in `re` and `im`, which are used as scratch space.
+    pub fn inverse(&self, re: &mut [f32], im: &mut [f32], output: &mut [f32]) {
+        let half = SIZE / 2;
+
+        let dc = re[0];
+        let nyquist = re[half];
+        re[0] = 0.5 * (dc + nyquist);
+        im[0] = 0.5 * (dc - nyquist);
+
+        for k in 1..=half / 2 {
+            let m = half - k;
+            let xk = Complex::new(re[k], im[k]);
+            let xm = Complex::new(re[m], im[m]);
+
+            let (zk, zm) = self.merge(xk, xm, k);
+            re[k] = zk.re;
+            im[k] = zk.im;
+            re[m] = zm.re;
+            im[m] = zm.im;
+        }
+
+        self.fft.inverse_transform(re, im, half);
+
+        for (n, pair) in output[..SIZE].chunks_exact_mut(2).enumerate() {
+            pair[0] = re[n];
+            pair[1] = im[n];
+        }
+    }
+
+    #[inline(always)]
+    fn twiddle(&self, k: usize) -> Complex {
+        let i = SIZE / 2 + k;
+        Complex::new(self.fft.tw_re[i], self.fft.tw_im[i])
+    }
+
+    // X[k] = E[k] + W^k O[k], with E and O the spectra of the even and odd samples.
+    #[inline(always)]
+    fn split(&self, zk: Complex, zm: Complex, k: usize) -> (Complex, Complex) {
+        let m = SIZE / 2 - k;
+
+        let even_k = (zk + zm.conj()).scale(0.5);
+        let odd_k = mul_neg_i(zk - zm.conj()).scale(0.5);
+        let even_m = (zm + zk.conj()).scale(0.5);
+        let odd_m = mul_neg_i(zm - zk.conj()).scale(0.5);
+
+        (
+            even_k + self.twiddle(k) * odd_k,
+            even_m + self.twiddle(m) * odd_m,
+        )
+    }
+
+    // Inverse of `split`, Z[k] = E[k] + i O[k].
+    #[inline(always)]
+    fn merge(&self, xk: Complex, xm: Complex, k: usize) -> (Complex, Complex) {
+        let m = SIZE / 2 - k;
+
+        let even_k = (xk + xm.conj()).scale(0.5);
+        let odd_k = ((xk - xm.conj()) * self.twiddle(k).conj()).scale(0.5);
+        let even_m = (xm + xk.conj()).scale(0.5);
+        let odd_m = ((xm - xk.conj()) * self.twiddle(m).conj()).scale(0.5);
+
+        (even_k + mul_i(odd_k), even_m + mul_i(odd_m))
+    }
+}
+
+impl<const SIZE: usize> Default for RealFft<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[inline(always)]
+fn mul_i(c: Complex) -> Complex {
+    Complex::new(-c.im, c.re)
+}
+
+#[inline(always)]
+fn mul_neg_i(c: Complex) -> Complex {
+    Complex::new(c.im, -c.re)
+}
@@ -0,0 +1,79 @@
+// IIR Hilbert transformer built from two chains of second order all-pass
+// sections (Olli Niemitalo's design). The outputs of the two chains stay
+// ~90 degrees apart from roughly 20 Hz to 20 kHz at 44.1 kHz.
+
+const CHAIN_A: [f32; 4] = [0.692_387_8, 0.936_065_43, 0.988_229_5, 0.998_748_8];
+const CHAIN_B: [f32; 4] = [0.402_192_12, 0.856_171_1, 0.972_290_95, 0.995_288_5];
+
+#[derive(Clone, Copy)]
+struct AllpassChain {
+    coeffs: [f32; 4],
+    x1: [f32; 4],
+    x2: [f32; 4],
+    y1: [f32; 4],
+    y2: [f32; 4],
+}
+
+impl AllpassChain {
+    fn new(a: [f32; 4]) -> Self {
+        Self {
+            coeffs: a.map(|a| a * a),
+            x1: [0.0; 4],
+            x2: [0.0; 4],
+            y1: [0.0; 4],
+            y2: [0.0; 4],
+        }
+    }
+
+    #[inline(always)]
+    fn process(&mut self, mut x: f32) -> f32 {
+        for i in 0..4 {
+            let y = self.coeffs[i] * (x + self.y2[i]) - self.x2[i];
+
+            self.x2[i] = self.x1[i];
+            self.x1[i] = x;
+            self.y2[i] = self.y1[i];
+            self.y1[i] = y;
+
+            x = y;
+        }
+        x
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Hilbert {
+    a: AllpassChain,
+    b: AllpassChain,
+    a_delay: f32,
+}
+
+impl Hilbert {
+    pub fn new() -> Self {
+        Self {
+            a: AllpassChain::new(CHAIN_A),
+            b: AllpassChain::new(CHAIN_B),
+            a_delay: 0.0,
+        }
+    }
+
+    // Returns the (in-phase, quadrature) pair of the analytic signal.
+    #[inline(always)]
+    pub fn process(&mut self, x: f32) -> (f32, f32) {
+        let i = self.a_delay;
+        self.a_delay = self.a.process(x);
+        let q = self.b.process(x);
+
+        (i, q)
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl Default for Hilbert {
+    fn default() -> Self {
+        Self::new()
+    }
+}
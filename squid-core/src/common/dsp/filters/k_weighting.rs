@@ -0,0 +1,87 @@
+// K-weighting pre-filter from ITU-R BS.1770: a high shelf modelling the head
+// followed by the "RLB" high-pass. Coefficients are derived for any rate, at
+// 48 kHz they match the values printed in the standard.
+
+use core::f64::consts::PI;
+
+use libm::{pow, tan};
+
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    const fn identity() -> Self {
+        Self {
+            b: [1.0, 0.0, 0.0],
+            a: [0.0, 0.0],
+            z: [0.0; 2],
+        }
+    }
+
+    // Transposed direct form II, kept in f64 since the high-pass sits very low.
+    #[inline(always)]
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct KWeighting {
+    shelf: Biquad,
+    highpass: Biquad,
+}
+
+impl KWeighting {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut filter = Self {
+            shelf: Biquad::identity(),
+            highpass: Biquad::identity(),
+        };
+        filter.update_coeffs(sample_rate);
+        filter
+    }
+
+    pub fn update_coeffs(&mut self, sample_rate: f32) {
+        let rate = sample_rate as f64;
+
+        let f0 = 1681.974450955533;
+        let gain_db = 3.999843853973347;
+        let q = 0.7071752369554196;
+        let k = tan(PI * f0 / rate);
+        let vh = pow(10.0, gain_db / 20.0);
+        let vb = pow(vh, 0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+
+        self.shelf.b = [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ];
+        self.shelf.a = [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0];
+
+        let f0 = 38.13547087602444;
+        let q = 0.5003270373238773;
+        let k = tan(PI * f0 / rate);
+        let a0 = 1.0 + k / q + k * k;
+
+        self.highpass.b = [1.0, -2.0, 1.0];
+        self.highpass.a = [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0];
+    }
+
+    #[inline(always)]
+    pub fn process(&mut self, x: f32) -> f32 {
+        self.highpass.process(self.shelf.process(x as f64)) as f32
+    }
+
+    pub fn reset(&mut self) {
+        self.shelf.z = [0.0; 2];
+        self.highpass.z = [0.0; 2];
+    }
+}
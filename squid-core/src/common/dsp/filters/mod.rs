@@ -1 +1,4 @@
+pub mod hilbert;
+pub mod k_weighting;
+pub mod simd_svf;
 pub mod sv_filter;
@@ -0,0 +1,88 @@
+// State-Variable Filter where every lane is an independent filter with its own
+// coefficients, handy for filter banks and per-voice filtering.
+
+use core::f32::consts::PI;
+use core::simd::{LaneCount, Simd, SupportedLaneCount, num::SimdFloat};
+
+use sleef::f32x::tan_u35;
+
+#[derive(Clone, Copy)]
+pub struct SimdSvf<const N: usize>
+where
+    LaneCount<N>: SupportedLaneCount,
+{
+    ic1eq: Simd<f32, N>,
+    ic2eq: Simd<f32, N>,
+
+    g: Simd<f32, N>,
+    k: Simd<f32, N>,
+    a1: Simd<f32, N>,
+    a2: Simd<f32, N>,
+}
+
+impl<const N: usize> SimdSvf<N>
+where
+    LaneCount<N>: SupportedLaneCount,
+{
+    pub fn new() -> Self {
+        Self {
+            ic1eq: Simd::splat(0.0),
+            ic2eq: Simd::splat(0.0),
+            g: Simd::splat(0.0),
+            k: Simd::splat(0.0),
+            a1: Simd::splat(0.0),
+            a2: Simd::splat(0.0),
+        }
+    }
+
+    pub fn update_coeffs(&mut self, cutoff: Simd<f32, N>, q: Simd<f32, N>, sample_rate: f32) {
+        let cutoff_clamped =
+            cutoff.simd_clamp(Simd::splat(10.0), Simd::splat(sample_rate / 2.0 - 100.0));
+        self.g = tan_u35(Simd::splat(PI / sample_rate) * cutoff_clamped);
+
+        let one = Simd::splat(1.0);
+        self.k = one / q.simd_max(Simd::splat(0.5));
+
+        self.a1 = one / (one + self.g * (self.g + self.k));
+        self.a2 = self.g * self.a1;
+    }
+
+    // Returns (lp, hp, bp) for every lane.
+    #[inline(always)]
+    pub fn process(&mut self, v0: Simd<f32, N>) -> (Simd<f32, N>, Simd<f32, N>, Simd<f32, N>) {
+        let two = Simd::splat(2.0);
+
+        let v1 = self.a1 * self.ic1eq + self.a2 * (v0 - self.ic2eq);
+        let v2 = self.ic2eq + self.g * v1;
+
+        let lp = v2;
+        let bp = v1;
+        let hp = v0 - self.k * v1 - v2;
+
+        self.ic1eq = two * v1 - self.ic1eq;
+        self.ic2eq = two * v2 - self.ic2eq;
+
+        (lp, hp, bp)
+    }
+
+    // Band-pass scaled to unity gain at the center frequency.
+    #[inline(always)]
+    pub fn process_bp_normalized(&mut self, v0: Simd<f32, N>) -> Simd<f32, N> {
+        let (_, _, bp) = self.process(v0);
+        bp * self.k
+    }
+
+    pub fn reset(&mut self) {
+        self.ic1eq = Simd::splat(0.0);
+        self.ic2eq = Simd::splat(0.0);
+    }
+}
+
+impl<const N: usize> Default for SimdSvf<N>
+where
+    LaneCount<N>: SupportedLaneCount,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1 +1,2 @@
 pub mod gain;
+pub mod saturation;
@@ -0,0 +1,67 @@
+use libm::{expf, floorf, powf, roundf};
+
+use crate::dsp::mixing::Mixing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaturationCurve {
+    Tanh,
+    HardClip,
+    Tube,
+    Diode,
+    Foldback,
+    Bit,
+}
+
+pub struct Saturation;
+
+impl Saturation {
+    #[inline(always)]
+    pub fn tanh(x: f32) -> f32 {
+        Mixing::saturate(x, 1.0)
+    }
+
+    #[inline(always)]
+    pub fn hard_clip(x: f32) -> f32 {
+        x.clamp(-1.0, 1.0)
+    }
+
+    // Asymmetric transfer: the negative half compresses earlier, which adds even harmonics.
+    #[inline(always)]
+    pub fn tube(x: f32) -> f32 {
+        if x >= 0.0 {
+            1.0 - expf(-x)
+        } else {
+            -0.7 * (1.0 - expf(x / 0.7))
+        }
+    }
+
+    // Anti-parallel diode pair, a sharper knee than tanh.
+    #[inline(always)]
+    pub fn diode(x: f32) -> f32 {
+        x / powf(1.0 + powf(x.abs(), 2.5), 0.4)
+    }
+
+    #[inline(always)]
+    pub fn foldback(x: f32) -> f32 {
+        let t = (x + 1.0) * 0.25;
+        4.0 * (t - floorf(t + 0.5)).abs() - 1.0
+    }
+
+    #[inline(always)]
+    pub fn bit(x: f32, bits: f32) -> f32 {
+        let steps = powf(2.0, bits.clamp(1.0, 24.0) - 1.0);
+        roundf(Self::hard_clip(x) * steps) / steps
+    }
+
+    #[inline(always)]
+    pub fn apply(curve: SaturationCurve, x: f32, bits: f32) -> f32 {
+        match curve {
+            SaturationCurve::Tanh => Self::tanh(x),
+            SaturationCurve::HardClip => Self::hard_clip(x),
+            SaturationCurve::Tube => Self::tube(x),
+            SaturationCurve::Diode => Self::diode(x),
+            SaturationCurve::Foldback => Self::foldback(x),
+            SaturationCurve::Bit => Self::bit(x, bits),
+        }
+    }
+}
@@ -1,4 +1,3 @@
-use core::simd::f32x8;
 use core::simd::{LaneCount, Simd, SupportedLaneCount};
 
 pub struct MixingSimd;
@@ -11,24 +10,34 @@ impl MixingSimd {
         (a + b) / Simd::splat(2.)
     }
 
-    pub fn stereo_pan(l: f32x8, r: f32x8, pan: f32) -> (f32x8, f32x8) {
+    pub fn stereo_pan<const N: usize>(
+        l: Simd<f32, N>,
+        r: Simd<f32, N>,
+        pan: f32,
+    ) -> (Simd<f32, N>, Simd<f32, N>)
+    where
+        LaneCount<N>: SupportedLaneCount,
+    {
         let pan = pan.clamp(-1.0, 1.0);
         let left_gain = (1.0 - pan) * 0.5;
         let right_gain = (1.0 + pan) * 0.5;
 
-        let left = l * f32x8::splat(left_gain);
-        let right = r * f32x8::splat(right_gain);
+        let left = l * Simd::splat(left_gain);
+        let right = r * Simd::splat(right_gain);
 
         (left, right)
     }
 
-    pub fn mono_pan_both(s: f32x8, pan: f32) -> (f32x8, f32x8) {
+    pub fn mono_pan_both<const N: usize>(s: Simd<f32, N>, pan: f32) -> (Simd<f32, N>, Simd<f32, N>)
+    where
+        LaneCount<N>: SupportedLaneCount,
+    {
         let pan = pan.clamp(-1.0, 1.0);
         let left_gain = (1.0 - pan) * 0.5;
         let right_gain = (1.0 + pan) * 0.5;
 
-        let left = s * f32x8::splat(left_gain);
-        let right = s * f32x8::splat(right_gain);
+        let left = s * Simd::splat(left_gain);
+        let right = s * Simd::splat(right_gain);
 
         (left, right)
     }
@@ -1,4 +1,5 @@
 pub mod approx;
+pub mod fft;
 pub mod filters;
 pub mod gain;
 pub mod microprocessors;
@@ -6,8 +7,12 @@ pub mod mixing;
 pub mod mixing_simd;
 pub mod mod_core;
 pub mod osc_core;
+pub mod oversampler;
 pub mod polyblep;
+pub mod stereo;
+pub mod stft;
 pub mod vecblock;
+pub mod window;
 
 pub trait Waveform {
     fn process(&self, phase: f32) -> f32;
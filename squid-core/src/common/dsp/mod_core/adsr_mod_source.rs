@@ -57,6 +57,12 @@ where
         self.state = mask.select(Simd::splat(STATE_RELEASE), self.state);
     }
 
+    // Silences every lane at once, skipping the release.
+    pub fn reset(&mut self) {
+        self.current_voltage = Simd::splat(0.0);
+        self.state = Simd::splat(STATE_IDLE);
+    }
+
     pub fn is_idle(&self) -> bool {
         self.state.as_array()[0] == STATE_IDLE
     }
@@ -0,0 +1,159 @@
+// Halfband FIR based oversampling used by nonlinear stages (saturation, folding, ...)
+// to keep the harmonics they generate from aliasing back into the audio band.
+
+use core::f32::consts::PI;
+
+use libm::{cosf, sinf};
+
+const HALFBAND_TAPS: usize = 31;
+
+#[derive(Clone, Copy)]
+pub struct HalfbandFir {
+    coeffs: [f32; HALFBAND_TAPS],
+    history: [f32; HALFBAND_TAPS * 2],
+    pos: usize,
+}
+
+impl HalfbandFir {
+    pub fn new() -> Self {
+        let center = (HALFBAND_TAPS / 2) as f32;
+        let mut coeffs = [0.0; HALFBAND_TAPS];
+
+        for (n, c) in coeffs.iter_mut().enumerate() {
+            let x = n as f32 - center;
+            let sinc = if x == 0.0 {
+                0.5
+            } else {
+                sinf(0.5 * PI * x) / (PI * x)
+            };
+
+            // Blackman window
+            let w = n as f32 / (HALFBAND_TAPS - 1) as f32;
+            let window = 0.42 - 0.5 * cosf(2.0 * PI * w) + 0.08 * cosf(4.0 * PI * w);
+
+            *c = sinc * window;
+        }
+
+        let sum: f32 = coeffs.iter().sum();
+        coeffs.iter_mut().for_each(|c| *c /= sum);
+
+        Self {
+            coeffs,
+            history: [0.0; HALFBAND_TAPS * 2],
+            pos: 0,
+        }
+    }
+
+    #[inline(always)]
+    pub fn process(&mut self, x: f32) -> f32 {
+        // The history is mirrored so the convolution never has to wrap.
+        self.history[self.pos] = x;
+        self.history[self.pos + HALFBAND_TAPS] = x;
+
+        let window = &self.history[self.pos + 1..self.pos + 1 + HALFBAND_TAPS];
+        let y = window
+            .iter()
+            .zip(self.coeffs.iter())
+            .map(|(s, c)| s * c)
+            .sum();
+
+        self.pos = (self.pos + 1) % HALFBAND_TAPS;
+        y
+    }
+
+    #[inline(always)]
+    pub fn upsample(&mut self, x: f32) -> [f32; 2] {
+        [self.process(x * 2.0), self.process(0.0)]
+    }
+
+    #[inline(always)]
+    pub fn downsample(&mut self, x: [f32; 2]) -> f32 {
+        self.process(x[0]);
+        self.process(x[1])
+    }
+
+    pub fn reset(&mut self) {
+        self.history = [0.0; HALFBAND_TAPS * 2];
+        self.pos = 0;
+    }
+}
+
+impl Default for HalfbandFir {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversampleFactor {
+    X1,
+    X2,
+    X4,
+}
+
+impl OversampleFactor {
+    pub fn ratio(&self) -> usize {
+        match self {
+            OversampleFactor::X1 => 1,
+            OversampleFactor::X2 => 2,
+            OversampleFactor::X4 => 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Oversampler {
+    factor: OversampleFactor,
+    up: [HalfbandFir; 2],
+    down: [HalfbandFir; 2],
+}
+
+impl Oversampler {
+    pub fn new(factor: OversampleFactor) -> Self {
+        Self {
+            factor,
+            up: [HalfbandFir::new(); 2],
+            down: [HalfbandFir::new(); 2],
+        }
+    }
+
+    pub fn factor(&self) -> OversampleFactor {
+        self.factor
+    }
+
+    pub fn set_factor(&mut self, factor: OversampleFactor) {
+        if self.factor != factor {
+            self.factor = factor;
+            self.reset();
+        }
+    }
+
+    // Runs `op` at the oversampled rate for a single input sample.
+    #[inline(always)]
+    pub fn process<F>(&mut self, x: f32, mut op: F) -> f32
+    where
+        F: FnMut(f32) -> f32,
+    {
+        match self.factor {
+            OversampleFactor::X1 => op(x),
+            OversampleFactor::X2 => {
+                let [a, b] = self.up[0].upsample(x);
+                self.down[0].downsample([op(a), op(b)])
+            }
+            OversampleFactor::X4 => {
+                let [a, b] = self.up[0].upsample(x);
+                let [a0, a1] = self.up[1].upsample(a);
+                let [b0, b1] = self.up[1].upsample(b);
+
+                let ya = self.down[1].downsample([op(a0), op(a1)]);
+                let yb = self.down[1].downsample([op(b0), op(b1)]);
+                self.down[0].downsample([ya, yb])
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.up.iter_mut().for_each(|f| f.reset());
+        self.down.iter_mut().for_each(|f| f.reset());
+    }
+}
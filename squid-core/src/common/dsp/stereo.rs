@@ -0,0 +1,117 @@
+use core::f32::consts::FRAC_PI_2;
+use core::simd::{LaneCount, Simd, SupportedLaneCount, num::SimdFloat};
+
+use sleef::f32x::{sincos_u35, sqrtf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanLaw {
+    // 0 dB in the center, the far side fades out linearly.
+    Linear,
+    // Constant power (sin/cos), -3 dB in the center.
+    Minus3Db,
+    // Geometric mean of the -3 dB and -6 dB laws.
+    Minus4_5Db,
+    // Constant gain sum, -6 dB in the center.
+    Minus6Db,
+}
+
+impl PanLaw {
+    pub fn center_gain(&self) -> f32 {
+        match self {
+            PanLaw::Linear => 1.0,
+            PanLaw::Minus3Db => core::f32::consts::FRAC_1_SQRT_2,
+            PanLaw::Minus4_5Db => 0.594_603_55,
+            PanLaw::Minus6Db => 0.5,
+        }
+    }
+}
+
+pub struct StereoImaging;
+
+impl StereoImaging {
+    // Returns (left, right) gains for a bipolar pan position in [-1, 1].
+    #[inline(always)]
+    pub fn pan_gains<const N: usize>(pan: Simd<f32, N>, law: PanLaw) -> (Simd<f32, N>, Simd<f32, N>)
+    where
+        LaneCount<N>: SupportedLaneCount,
+    {
+        let one = Simd::splat(1.0);
+        let pan = pan.simd_clamp(Simd::splat(-1.0), one);
+
+        match law {
+            PanLaw::Linear => ((one - pan).simd_min(one), (one + pan).simd_min(one)),
+            PanLaw::Minus3Db => {
+                let angle = (pan + one) * Simd::splat(0.5 * FRAC_PI_2);
+                let (sin, cos) = sincos_u35(angle);
+                (cos, sin)
+            }
+            PanLaw::Minus4_5Db => {
+                let x = (pan + one) * Simd::splat(0.5);
+                let (sin, cos) = sincos_u35(x * Simd::splat(FRAC_PI_2));
+                let zero = Simd::splat(0.0);
+                (
+                    sqrtf(((one - x) * cos).simd_max(zero)),
+                    sqrtf((x * sin).simd_max(zero)),
+                )
+            }
+            PanLaw::Minus6Db => {
+                let x = (pan + one) * Simd::splat(0.5);
+                (one - x, x)
+            }
+        }
+    }
+
+    // Same as `pan_gains`, normalized so the center position is unity gain.
+    #[inline(always)]
+    pub fn balance_gains<const N: usize>(
+        balance: Simd<f32, N>,
+        law: PanLaw,
+    ) -> (Simd<f32, N>, Simd<f32, N>)
+    where
+        LaneCount<N>: SupportedLaneCount,
+    {
+        let (l, r) = Self::pan_gains(balance, law);
+        let norm = Simd::splat(1.0 / law.center_gain());
+        (
+            (l * norm).simd_min(Simd::splat(1.0)),
+            (r * norm).simd_min(Simd::splat(1.0)),
+        )
+    }
+
+    #[inline(always)]
+    pub fn ms_encode<const N: usize>(
+        l: Simd<f32, N>,
+        r: Simd<f32, N>,
+    ) -> (Simd<f32, N>, Simd<f32, N>)
+    where
+        LaneCount<N>: SupportedLaneCount,
+    {
+        let half = Simd::splat(0.5);
+        ((l + r) * half, (l - r) * half)
+    }
+
+    #[inline(always)]
+    pub fn ms_decode<const N: usize>(
+        m: Simd<f32, N>,
+        s: Simd<f32, N>,
+    ) -> (Simd<f32, N>, Simd<f32, N>)
+    where
+        LaneCount<N>: SupportedLaneCount,
+    {
+        (m + s, m - s)
+    }
+
+    // 0 collapses to mono, 1 leaves the image untouched, 2 doubles the side signal.
+    #[inline(always)]
+    pub fn width<const N: usize>(
+        l: Simd<f32, N>,
+        r: Simd<f32, N>,
+        width: Simd<f32, N>,
+    ) -> (Simd<f32, N>, Simd<f32, N>)
+    where
+        LaneCount<N>: SupportedLaneCount,
+    {
+        let (m, s) = Self::ms_encode(l, r);
+        Self::ms_decode(m, s * width)
+    }
+}
@@ -0,0 +1,136 @@
+// Short-time Fourier transform with windowed overlap-add resynthesis. Samples
+// are pushed one at a time; every `hop` samples a frame is analysed, handed to
+// a callback as `SIZE / 2 + 1` complex bins and added back into the output.
+
+use crate::dsp::{fft::RealFft, window::Window};
+
+pub trait SpectralProcessor {
+    // `re` and `im` hold the bins of one frame of `channel`, DC first.
+    fn process_spectrum(&mut self, channel: usize, re: &mut [f32], im: &mut [f32]);
+    fn reset(&mut self, sample_rate: f32);
+}
+
+#[derive(Clone, Copy)]
+pub struct Stft<const SIZE: usize> {
+    fft: RealFft<SIZE>,
+    window: [f32; SIZE],
+    hop: usize,
+    gain: [f32; SIZE],
+
+    input: [f32; SIZE],
+    output: [f32; SIZE],
+    frame: [f32; SIZE],
+    re: [f32; SIZE],
+    im: [f32; SIZE],
+    pos: usize,
+}
+
+impl<const SIZE: usize> Stft<SIZE> {
+    // `overlap` is the number of frames covering each sample, a power of two.
+    // Windows that fall to zero at their ends need at least two, or the first
+    // sample of every frame would be lost.
+    pub fn new(window: Window, overlap: usize) -> Self {
+        let min_overlap = match window {
+            Window::Rectangular | Window::Hamming => 1,
+            _ => 2,
+        };
+        let overlap = overlap.clamp(min_overlap, SIZE).next_power_of_two();
+        let hop = SIZE / overlap;
+
+        let mut table = [0.0; SIZE];
+        window.fill(&mut table);
+
+        // The window is applied twice (analysis and synthesis), this undoes the
+        // summed squared windows over each sample. The sum only adds up to a
+        // constant for some windows and overlaps, so it is taken per sample.
+        let mut gain = [0.0; SIZE];
+        for (i, g) in gain.iter_mut().enumerate() {
+            let sum: f32 = table[i % hop..].iter().step_by(hop).map(|w| w * w).sum();
+            *g = if sum > 1e-6 { 1.0 / sum } else { 0.0 };
+        }
+
+        Self {
+            fft: RealFft::new(),
+            window: table,
+            hop,
+            gain,
+
+            input: [0.0; SIZE],
+            output: [0.0; SIZE],
+            frame: [0.0; SIZE],
+            re: [0.0; SIZE],
+            im: [0.0; SIZE],
+            pos: 0,
+        }
+    }
+
+    pub const fn bins(&self) -> usize {
+        SIZE / 2 + 1
+    }
+
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
+    // Delay between a sample going in and its resynthesis coming out.
+    pub const fn latency(&self) -> usize {
+        SIZE
+    }
+
+    #[inline(always)]
+    pub fn process<F>(&mut self, x: f32, op: F) -> f32
+    where
+        F: FnOnce(&mut [f32], &mut [f32]),
+    {
+        self.input[SIZE - self.hop + self.pos] = x;
+        let y = self.output[self.pos];
+
+        self.pos += 1;
+        if self.pos == self.hop {
+            self.pos = 0;
+            self.run_frame(op);
+        }
+        y
+    }
+
+    pub fn process_block<F>(&mut self, block: &mut [f32], mut op: F)
+    where
+        F: FnMut(&mut [f32], &mut [f32]),
+    {
+        for x in block.iter_mut() {
+            *x = self.process(*x, &mut op);
+        }
+    }
+
+    fn run_frame<F>(&mut self, op: F)
+    where
+        F: FnOnce(&mut [f32], &mut [f32]),
+    {
+        let bins = SIZE / 2 + 1;
+
+        for ((f, &x), &w) in self.frame.iter_mut().zip(&self.input).zip(&self.window) {
+            *f = x * w;
+        }
+        self.fft.forward(&self.frame, &mut self.re, &mut self.im);
+
+        op(&mut self.re[..bins], &mut self.im[..bins]);
+
+        self.fft
+            .inverse(&mut self.re, &mut self.im, &mut self.frame);
+
+        self.output.copy_within(self.hop.., 0);
+        self.output[SIZE - self.hop..].fill(0.0);
+        let frame = self.frame.iter().zip(&self.window).zip(&self.gain);
+        for (out, ((&f, &w), &g)) in self.output.iter_mut().zip(frame) {
+            *out += f * w * g;
+        }
+
+        self.input.copy_within(self.hop.., 0);
+    }
+
+    pub fn reset(&mut self) {
+        self.input = [0.0; SIZE];
+        self.output = [0.0; SIZE];
+        self.pos = 0;
+    }
+}
@@ -46,6 +46,16 @@ where
         self.buf = other.buf;
     }
 
+    #[inline(always)]
+    pub fn simd_chunks(&self) -> &[Simd<T, N>; S] {
+        &self.buf
+    }
+
+    #[inline(always)]
+    pub fn simd_chunks_mut(&mut self) -> &mut [Simd<T, N>; S] {
+        &mut self.buf
+    }
+
     #[inline(always)]
     pub fn as_slice(&self) -> &[T] {
         let len = S * N;
@@ -0,0 +1,41 @@
+use core::f64::consts::PI;
+
+use libm::{cos, sqrt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    // Square root of Hann, used as analysis and synthesis window at the same time.
+    SqrtHann,
+    Hamming,
+    Blackman,
+    BlackmanHarris,
+}
+
+impl Window {
+    // Periodic form, one sample shorter than the symmetric one, so that
+    // overlapping frames add up to a constant.
+    pub fn value(&self, i: usize, len: usize) -> f32 {
+        let x = 2.0 * PI * i as f64 / len as f64;
+
+        let w = match self {
+            Window::Rectangular => 1.0,
+            Window::Hann => 0.5 - 0.5 * cos(x),
+            Window::SqrtHann => sqrt(0.5 - 0.5 * cos(x)),
+            Window::Hamming => 0.54 - 0.46 * cos(x),
+            Window::Blackman => 0.42 - 0.5 * cos(x) + 0.08 * cos(2.0 * x),
+            Window::BlackmanHarris => {
+                0.35875 - 0.48829 * cos(x) + 0.14128 * cos(2.0 * x) - 0.01168 * cos(3.0 * x)
+            }
+        };
+        w as f32
+    }
+
+    pub fn fill(&self, out: &mut [f32]) {
+        let len = out.len();
+        for (i, w) in out.iter_mut().enumerate() {
+            *w = self.value(i, len);
+        }
+    }
+}
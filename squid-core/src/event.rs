@@ -1,13 +1,66 @@
 use core::array;
 
-use crate::FixedSpscQueue;
+use crate::{FixedSpscQueue, SysExChunk};
+
+// Full scale of `NotePitchBend` in semitones either way, the MPE default for
+// member channels.
+pub const NOTE_BEND_RANGE: f32 = 48.0;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Event {
     pub timing: u32,
+    pub channel: u8,
     pub data: EventData,
 }
 
+impl Event {
+    pub const fn new(timing: u32, data: EventData) -> Self {
+        Self {
+            timing,
+            channel: 0,
+            data,
+        }
+    }
+
+    pub const fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    // Key the event applies to, for note-on/off and per-note messages.
+    pub fn note(&self) -> Option<u8> {
+        match self.data {
+            EventData::NoteOn { note, .. }
+            | EventData::NoteOn16 { note, .. }
+            | EventData::NoteOff { note }
+            | EventData::NoteControlChange { note, .. }
+            | EventData::NoteControlChange32 { note, .. }
+            | EventData::NotePitchBend { note, .. }
+            | EventData::NotePitchBend32 { note, .. }
+            | EventData::NotePressure { note, .. }
+            | EventData::NotePressure32 { note, .. } => Some(note),
+            _ => None,
+        }
+    }
+
+    // Same event moved to another key. Non-note events are returned as is.
+    pub fn with_note(mut self, new_note: u8) -> Self {
+        match &mut self.data {
+            EventData::NoteOn { note, .. }
+            | EventData::NoteOn16 { note, .. }
+            | EventData::NoteOff { note }
+            | EventData::NoteControlChange { note, .. }
+            | EventData::NoteControlChange32 { note, .. }
+            | EventData::NotePitchBend { note, .. }
+            | EventData::NotePitchBend32 { note, .. }
+            | EventData::NotePressure { note, .. }
+            | EventData::NotePressure32 { note, .. } => *note = new_note,
+            _ => {}
+        }
+        self
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum EventData {
     NoteOn { note: u8, velocity: u8 },
@@ -15,12 +68,251 @@ pub enum EventData {
 
     ControlChange { control: u8, value: u8 },
     PitchBend { value: u16 },
+    ChannelPressure { pressure: u8 },
 
     NoteControlChange { note: u8, control: u8, value: u8 },
     NotePitchBend { note: u8, value: u16 },
     NotePressure { note: u8, pressure: u8 },
 
     ProgramChange { program: u8 },
+
+    // One piece of a system exclusive message, see `sysex_chunks`.
+    SysEx { chunk: SysExChunk },
+
+    // MIDI 2.0 resolution. Values use the whole range of their type, bends
+    // are centered at half of it, and a zero velocity is still a note on.
+    NoteOn16 { note: u8, velocity: u16 },
+    ControlChange32 { control: u8, value: u32 },
+    PitchBend32 { value: u32 },
+    ChannelPressure32 { pressure: u32 },
+    NoteControlChange32 { note: u8, control: u8, value: u32 },
+    NotePitchBend32 { note: u8, value: u32 },
+    NotePressure32 { note: u8, pressure: u32 },
+}
+
+impl EventData {
+    // Note on with a nonzero velocity, at either resolution.
+    pub fn is_note_on(&self) -> bool {
+        match *self {
+            EventData::NoteOn { velocity, .. } => velocity > 0,
+            EventData::NoteOn16 { .. } => true,
+            _ => false,
+        }
+    }
+
+    // Note off, or a MIDI 1.0 note on with zero velocity.
+    pub fn is_note_off(&self) -> bool {
+        matches!(
+            self,
+            EventData::NoteOff { .. } | EventData::NoteOn { velocity: 0, .. }
+        )
+    }
+
+    // 0 to 1 for note ons.
+    pub fn velocity(&self) -> Option<f32> {
+        match *self {
+            EventData::NoteOn { velocity, .. } => Some(velocity.min(127) as f32 / 127.0),
+            EventData::NoteOn16 { velocity, .. } => Some(velocity as f32 / u16::MAX as f32),
+            _ => None,
+        }
+    }
+
+    // The MIDI 2.0 form of MIDI 1.0 messages, scaled up so the minimum,
+    // center and maximum values stay where they were. A note on with zero
+    // velocity becomes a note off. Everything else is returned as is.
+    pub fn to_high_res(self) -> Self {
+        match self {
+            EventData::NoteOn { note, velocity: 0 } => EventData::NoteOff { note },
+            EventData::NoteOn { note, velocity } => EventData::NoteOn16 {
+                note,
+                velocity: scale_up(velocity as u32, 7, 16) as u16,
+            },
+            EventData::ControlChange { control, value } => EventData::ControlChange32 {
+                control,
+                value: scale_up(value as u32, 7, 32),
+            },
+            EventData::PitchBend { value } => EventData::PitchBend32 {
+                value: scale_up(value as u32, 14, 32),
+            },
+            EventData::ChannelPressure { pressure } => EventData::ChannelPressure32 {
+                pressure: scale_up(pressure as u32, 7, 32),
+            },
+            EventData::NoteControlChange {
+                note,
+                control,
+                value,
+            } => EventData::NoteControlChange32 {
+                note,
+                control,
+                value: scale_up(value as u32, 7, 32),
+            },
+            EventData::NotePitchBend { note, value } => EventData::NotePitchBend32 {
+                note,
+                value: scale_up(value as u32, 14, 32),
+            },
+            EventData::NotePressure { note, pressure } => EventData::NotePressure32 {
+                note,
+                pressure: scale_up(pressure as u32, 7, 32),
+            },
+            data => data,
+        }
+    }
+
+    // The MIDI 1.0 form of high resolution messages, keeping the top bits.
+    // Note ons keep a velocity of at least 1.
+    pub fn to_midi1(self) -> Self {
+        match self {
+            EventData::NoteOn16 { note, velocity } => EventData::NoteOn {
+                note,
+                velocity: (velocity >> 9).max(1) as u8,
+            },
+            EventData::ControlChange32 { control, value } => EventData::ControlChange {
+                control,
+                value: (value >> 25) as u8,
+            },
+            EventData::PitchBend32 { value } => EventData::PitchBend {
+                value: (value >> 18) as u16,
+            },
+            EventData::ChannelPressure32 { pressure } => EventData::ChannelPressure {
+                pressure: (pressure >> 25) as u8,
+            },
+            EventData::NoteControlChange32 {
+                note,
+                control,
+                value,
+            } => EventData::NoteControlChange {
+                note,
+                control,
+                value: (value >> 25) as u8,
+            },
+            EventData::NotePitchBend32 { note, value } => EventData::NotePitchBend {
+                note,
+                value: (value >> 18) as u16,
+            },
+            EventData::NotePressure32 { note, pressure } => EventData::NotePressure {
+                note,
+                pressure: (pressure >> 25) as u8,
+            },
+            data => data,
+        }
+    }
+}
+
+// Min-center-max scaling from the MIDI 2.0 translation rules: values up to
+// the center are shifted, the ones above it fill the new low bits by
+// repeating their own so the maximum maps to the maximum.
+pub fn scale_up(value: u32, from_bits: u32, to_bits: u32) -> u32 {
+    let shift = to_bits - from_bits;
+    let shifted = value << shift;
+    let center = 1 << (from_bits - 1);
+    if value <= center {
+        return shifted;
+    }
+
+    let repeat_bits = from_bits - 1;
+    let mut repeat = value & ((1 << repeat_bits) - 1);
+    if shift > repeat_bits {
+        repeat <<= shift - repeat_bits;
+    } else {
+        repeat >>= repeat_bits - shift;
+    }
+    let mut result = shifted;
+    while repeat != 0 {
+        result |= repeat;
+        repeat >>= repeat_bits;
+    }
+    result
+}
+
+// Fixed capacity list of events for one block, e.g. the output of an event
+// processor. Pushes past the capacity are dropped.
+#[derive(Clone, Copy)]
+pub struct EventBuffer<const CAPACITY: usize> {
+    events: [Event; CAPACITY],
+    len: usize,
+}
+
+impl<const CAPACITY: usize> EventBuffer<CAPACITY> {
+    pub const fn new() -> Self {
+        Self {
+            events: [Event::new(0, EventData::NoteOff { note: 0 }); CAPACITY],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, event: Event) -> bool {
+        if self.len == CAPACITY {
+            return false;
+        }
+        self.events[self.len] = event;
+        self.len += 1;
+        true
+    }
+
+    pub fn extend_from_slice(&mut self, events: &[Event]) {
+        for &event in events {
+            if !self.push(event) {
+                break;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    // Keeps the order of the events after it.
+    pub fn remove(&mut self, index: usize) -> Option<Event> {
+        if index >= self.len {
+            return None;
+        }
+        let event = self.events[index];
+        self.events.copy_within(index + 1..self.len, index);
+        self.len -= 1;
+        Some(event)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == CAPACITY
+    }
+
+    // Stable, so events on the same sample keep the order they were pushed in.
+    pub fn sort_by_timing(&mut self) {
+        let events = &mut self.events[..self.len];
+        for i in 1..events.len() {
+            let mut j = i;
+            while j > 0 && events[j - 1].timing > events[j].timing {
+                events.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+    }
+
+    pub fn as_slice(&self) -> &[Event] {
+        &self.events[..self.len]
+    }
+}
+
+impl<const CAPACITY: usize> Default for EventBuffer<CAPACITY> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const CAPACITY: usize> core::ops::Deref for EventBuffer<CAPACITY> {
+    type Target = [Event];
+
+    fn deref(&self) -> &[Event] {
+        self.as_slice()
+    }
 }
 
 pub struct EventSequence<const CAPACITY: usize> {
@@ -0,0 +1,448 @@
+use libm::{ceil, floor};
+
+use crate::{
+    Event, EventBuffer, EventData, MAX_BLOCK_SIZE, TimeInfo,
+    event_processors::{BlockEvents, EventProcessor},
+    process_context::ProcessContext,
+    rand::Rand,
+};
+
+pub const ARP_MAX_NOTES: usize = 32;
+pub const ARP_MAX_OCTAVES: u8 = 4;
+
+const PATTERN_CAPACITY: usize = ARP_MAX_NOTES * ARP_MAX_OCTAVES as usize * 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArpMode {
+    #[default]
+    Up,
+    Down,
+    UpDown,
+    Random,
+    AsPlayed,
+    Chord,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HeldNote {
+    note: u8,
+    velocity: u8,
+}
+
+#[derive(Clone, Copy)]
+struct NoteList {
+    notes: [HeldNote; ARP_MAX_NOTES],
+    len: usize,
+}
+
+impl NoteList {
+    const fn new() -> Self {
+        Self {
+            notes: [HeldNote {
+                note: 0,
+                velocity: 0,
+            }; ARP_MAX_NOTES],
+            len: 0,
+        }
+    }
+
+    fn as_slice(&self) -> &[HeldNote] {
+        &self.notes[..self.len]
+    }
+
+    fn push(&mut self, note: HeldNote) {
+        self.remove(note.note);
+        if self.len < ARP_MAX_NOTES {
+            self.notes[self.len] = note;
+            self.len += 1;
+        }
+    }
+
+    fn remove(&mut self, note: u8) {
+        if let Some(i) = self.as_slice().iter().position(|n| n.note == note) {
+            self.notes.copy_within(i + 1..self.len, i);
+            self.len -= 1;
+        }
+    }
+}
+
+// Turns held notes into a pattern of notes on a beat grid. Note events going
+// in are consumed, everything else passes through. Incoming events must be
+// sorted by timing.
+//
+// While the transport plays, steps sit on its grid. When it is stopped the
+// arp runs at the transport tempo from the first key pressed.
+pub struct Arpeggiator {
+    mode: ArpMode,
+    octaves: u8,
+    step_beats: f64,
+    gate: f64,
+    swing: f64,
+    latch: bool,
+
+    held: NoteList,
+    latched: NoteList,
+    sounding: NoteList,
+    off_beat: Option<f64>,
+    // Generated notes go out on the channel of the last key pressed.
+    channel: u8,
+
+    origin: f64,
+    beat: f64,
+    synced: bool,
+    next_step: u64,
+    step: usize,
+    rand: Rand,
+}
+
+impl Arpeggiator {
+    pub fn new() -> Self {
+        Self {
+            mode: ArpMode::Up,
+            octaves: 1,
+            step_beats: 0.25,
+            gate: 0.5,
+            swing: 0.0,
+            latch: false,
+
+            held: NoteList::new(),
+            latched: NoteList::new(),
+            sounding: NoteList::new(),
+            off_beat: None,
+            channel: 0,
+
+            origin: 0.0,
+            beat: 0.0,
+            synced: false,
+            next_step: 0,
+            step: 0,
+            rand: Rand::new(0x2545F491),
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: ArpMode) {
+        self.mode = mode;
+    }
+
+    pub fn mode(&self) -> ArpMode {
+        self.mode
+    }
+
+    pub fn set_octaves(&mut self, octaves: u8) {
+        self.octaves = octaves.clamp(1, ARP_MAX_OCTAVES);
+    }
+
+    // Step length in beats, 0.25 plays sixteenths in 4/4.
+    pub fn set_rate(&mut self, step_beats: f64) {
+        self.step_beats = step_beats.clamp(1.0 / 64.0, 16.0);
+    }
+
+    // Fraction of a step each note holds, 1 is legato.
+    pub fn set_gate(&mut self, gate: f32) {
+        self.gate = gate.clamp(0.01, 1.0) as f64;
+    }
+
+    // Delays every second step by this fraction of a step, 0.5 is a triplet
+    // shuffle.
+    pub fn set_swing(&mut self, swing: f32) {
+        self.swing = swing.clamp(0.0, 0.75) as f64;
+    }
+
+    // Keeps playing released notes until a new chord is pressed.
+    pub fn set_latch(&mut self, latch: bool) {
+        self.latch = latch;
+        if !latch {
+            self.latched = self.held;
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.active().len > 0
+    }
+
+    pub fn process<const CAPACITY: usize>(
+        &mut self,
+        events: &[Event],
+        time: &TimeInfo,
+        frames: usize,
+        out: &mut EventBuffer<CAPACITY>,
+    ) {
+        let samples_per_beat = time.samples_per_beat;
+        if samples_per_beat <= 0.0 {
+            return;
+        }
+
+        let start = if time.is_playing {
+            let beat = time.beat;
+            if !self.synced || (beat - self.beat).abs() * samples_per_beat > 0.5 {
+                self.origin = 0.0;
+                self.next_step = self.first_step_from(beat);
+            }
+            self.synced = true;
+            beat
+        } else {
+            self.synced = false;
+            self.beat
+        };
+
+        let block = Block {
+            start,
+            samples_per_beat,
+        };
+
+        for event in events {
+            let timing = (event.timing as usize).min(frames);
+            self.run_until(&block, timing as f64, out);
+
+            match event.data.to_midi1() {
+                EventData::NoteOn { note, velocity } if velocity > 0 => {
+                    self.channel = event.channel;
+                    self.press(&block, timing, note, velocity)
+                }
+                EventData::NoteOn { note, .. } | EventData::NoteOff { note } => {
+                    self.release(timing, note, out)
+                }
+                _ => {
+                    out.push(Event {
+                        timing: timing as u32,
+                        ..*event
+                    });
+                }
+            }
+        }
+        self.run_until(&block, frames as f64, out);
+
+        self.beat = block.beat_at(frames as f64);
+    }
+
+    // Note offs for whatever is sounding, at `timing`.
+    pub fn all_notes_off<const CAPACITY: usize>(
+        &mut self,
+        timing: u32,
+        out: &mut EventBuffer<CAPACITY>,
+    ) {
+        for n in self.sounding.as_slice() {
+            out.push(
+                Event::new(timing, EventData::NoteOff { note: n.note }).with_channel(self.channel),
+            );
+        }
+        self.sounding.len = 0;
+        self.off_beat = None;
+    }
+
+    pub fn reset(&mut self) {
+        self.held.len = 0;
+        self.latched.len = 0;
+        self.sounding.len = 0;
+        self.off_beat = None;
+        self.origin = 0.0;
+        self.beat = 0.0;
+        self.synced = false;
+        self.next_step = 0;
+        self.step = 0;
+    }
+
+    fn active(&self) -> &NoteList {
+        if self.latch {
+            &self.latched
+        } else {
+            &self.held
+        }
+    }
+
+    fn press(&mut self, block: &Block, timing: usize, note: u8, velocity: u8) {
+        let was_idle = !self.is_playing();
+
+        if self.held.len == 0 {
+            self.latched.len = 0;
+        }
+        let note = HeldNote { note, velocity };
+        self.held.push(note);
+        self.latched.push(note);
+
+        if was_idle {
+            let beat = block.beat_at(timing as f64);
+            self.step = 0;
+            if self.synced {
+                self.next_step = self.first_step_from(beat);
+            } else {
+                self.origin = beat;
+                self.next_step = 0;
+            }
+        }
+    }
+
+    fn release<const CAPACITY: usize>(
+        &mut self,
+        timing: usize,
+        note: u8,
+        out: &mut EventBuffer<CAPACITY>,
+    ) {
+        self.held.remove(note);
+        if !self.is_playing() {
+            self.all_notes_off(timing as u32, out);
+        }
+    }
+
+    fn step_beat(&self, step: u64) -> f64 {
+        let swing = if step % 2 == 1 { self.swing } else { 0.0 };
+        self.origin + (step as f64 + swing) * self.step_beats
+    }
+
+    fn first_step_from(&self, beat: f64) -> u64 {
+        let step = ceil((beat - self.origin) / self.step_beats - 1e-9).max(0.0) as u64;
+        // The swung step before the grid line may still be ahead.
+        if step > 0 && self.step_beat(step - 1) >= beat {
+            step - 1
+        } else {
+            step
+        }
+    }
+
+    // Emits note offs and steps that fall before `end`, in samples from the
+    // block start.
+    fn run_until<const CAPACITY: usize>(
+        &mut self,
+        block: &Block,
+        end: f64,
+        out: &mut EventBuffer<CAPACITY>,
+    ) {
+        loop {
+            let off = self.off_beat.map(|b| block.offset_of(b));
+            let step = if self.is_playing() {
+                Some(block.offset_of(self.step_beat(self.next_step)))
+            } else {
+                None
+            };
+
+            match (off, step) {
+                (Some(off), step) if off < end && step.is_none_or(|s| off <= s) => {
+                    self.all_notes_off(off as u32, out);
+                }
+                (_, Some(step)) if step < end => {
+                    self.trigger_step(step as u32, out);
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn trigger_step<const CAPACITY: usize>(
+        &mut self,
+        timing: u32,
+        out: &mut EventBuffer<CAPACITY>,
+    ) {
+        self.all_notes_off(timing, out);
+
+        let mut pattern = [HeldNote {
+            note: 0,
+            velocity: 0,
+        }; PATTERN_CAPACITY];
+        let len = self.build_pattern(&mut pattern);
+
+        let notes = match self.mode {
+            _ if len == 0 => &[][..],
+            ArpMode::Chord => {
+                let count = self.active().len;
+                let octave = self.step % self.octaves as usize;
+                let start = (octave * count).min(len);
+                &pattern[start..(start + count).min(len)]
+            }
+            ArpMode::Random => {
+                let i = self.rand.next_range_u32(0, len as u32 - 1) as usize;
+                &pattern[i..i + 1]
+            }
+            _ => {
+                let i = self.step % len;
+                &pattern[i..i + 1]
+            }
+        };
+
+        for &n in notes {
+            out.push(
+                Event::new(
+                    timing,
+                    EventData::NoteOn {
+                        note: n.note,
+                        velocity: n.velocity,
+                    },
+                )
+                .with_channel(self.channel),
+            );
+            self.sounding.push(n);
+        }
+
+        let start = self.step_beat(self.next_step);
+        let length = self.step_beat(self.next_step + 1) - start;
+        self.off_beat = Some(start + length * self.gate);
+
+        self.step += 1;
+        self.next_step += 1;
+    }
+
+    fn build_pattern(&self, pattern: &mut [HeldNote; PATTERN_CAPACITY]) -> usize {
+        let mut base = *self.active();
+        if self.mode != ArpMode::AsPlayed {
+            base.notes[..base.len].sort_unstable_by_key(|n| n.note);
+        }
+
+        let mut len = 0;
+        for octave in 0..self.octaves {
+            for n in base.as_slice() {
+                let note = n.note as u16 + octave as u16 * 12;
+                if note <= 127 {
+                    pattern[len] = HeldNote {
+                        note: note as u8,
+                        velocity: n.velocity,
+                    };
+                    len += 1;
+                }
+            }
+        }
+
+        match self.mode {
+            ArpMode::Down => pattern[..len].reverse(),
+            ArpMode::UpDown if len > 2 => {
+                // Back down without repeating the top and bottom notes.
+                for i in (1..len - 1).rev() {
+                    pattern[len + (len - 1 - i) - 1] = pattern[i];
+                }
+                len += len - 2;
+            }
+            _ => {}
+        }
+        len
+    }
+}
+
+impl EventProcessor for Arpeggiator {
+    fn process(&mut self, ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents) {
+        Arpeggiator::process(self, input, &ctx.time, MAX_BLOCK_SIZE, output);
+    }
+
+    fn reset(&mut self, _sample_rate: f32) {
+        Arpeggiator::reset(self);
+    }
+}
+
+impl Default for Arpeggiator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Block {
+    start: f64,
+    samples_per_beat: f64,
+}
+
+impl Block {
+    fn beat_at(&self, offset: f64) -> f64 {
+        self.start + offset / self.samples_per_beat
+    }
+
+    fn offset_of(&self, beat: f64) -> f64 {
+        // Nudged so steps landing exactly on a sample don't round down.
+        floor((beat - self.start) * self.samples_per_beat + 1e-6).max(0.0)
+    }
+}
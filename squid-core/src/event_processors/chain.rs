@@ -0,0 +1,101 @@
+use crate::{
+    AudioNode, Event,
+    event_processors::{BlockEvents, EventProcessor},
+    process_context::{FixedBuf, ProcessContext},
+};
+
+// Two processors run back to back, build longer chains with `then`.
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+    scratch: BlockEvents,
+}
+
+impl<A: EventProcessor, B: EventProcessor> Chain<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self {
+            first,
+            second,
+            scratch: BlockEvents::new(),
+        }
+    }
+
+    pub fn first(&mut self) -> &mut A {
+        &mut self.first
+    }
+
+    pub fn second(&mut self) -> &mut B {
+        &mut self.second
+    }
+}
+
+impl<A: EventProcessor, B: EventProcessor> EventProcessor for Chain<A, B> {
+    fn process(&mut self, ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents) {
+        self.scratch.clear();
+        self.first.process(ctx, input, &mut self.scratch);
+        self.second.process(ctx, &self.scratch, output);
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        self.scratch.clear();
+        self.first.reset(sample_rate);
+        self.second.reset(sample_rate);
+    }
+}
+
+pub trait EventProcessorExt: EventProcessor + Sized {
+    fn then<B: EventProcessor>(self, next: B) -> Chain<Self, B> {
+        Chain::new(self, next)
+    }
+
+    // Puts this processor in front of an instrument.
+    fn into_node<N: AudioNode>(self, node: N) -> EventFxNode<Self, N> {
+        EventFxNode::new(self, node)
+    }
+}
+
+impl<P: EventProcessor> EventProcessorExt for P {}
+
+// An instrument that only sees events after they went through `processor`.
+pub struct EventFxNode<P, N> {
+    processor: P,
+    node: N,
+    events: BlockEvents,
+}
+
+impl<P: EventProcessor, N: AudioNode> EventFxNode<P, N> {
+    pub fn new(processor: P, node: N) -> Self {
+        Self {
+            processor,
+            node,
+            events: BlockEvents::new(),
+        }
+    }
+
+    pub fn processor(&mut self) -> &mut P {
+        &mut self.processor
+    }
+
+    pub fn node(&mut self) -> &mut N {
+        &mut self.node
+    }
+}
+
+impl<P: EventProcessor, N: AudioNode> AudioNode for EventFxNode<P, N> {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        self.events.clear();
+        self.processor.process(ctx, ctx.events, &mut self.events);
+
+        let ctx = ProcessContext {
+            events: &self.events,
+            ..*ctx
+        };
+        self.node.process(&ctx, outputs);
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        self.events.clear();
+        self.processor.reset(sample_rate);
+        self.node.reset(sample_rate);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{
+    Event,
+    event_processors::{BlockEvents, EventProcessor},
+    process_context::ProcessContext,
+};
+
+// Lets through events on the enabled channels, optionally all moved to one
+// output channel.
+pub struct ChannelFilter {
+    mask: u16,
+    remap: Option<u8>,
+}
+
+impl ChannelFilter {
+    pub fn all() -> Self {
+        Self {
+            mask: u16::MAX,
+            remap: None,
+        }
+    }
+
+    pub fn only(channel: u8) -> Self {
+        let mut filter = Self::all();
+        filter.mask = 0;
+        filter.set_enabled(channel, true);
+        filter
+    }
+
+    pub fn set_enabled(&mut self, channel: u8, enabled: bool) {
+        if channel < 16 {
+            if enabled {
+                self.mask |= 1 << channel;
+            } else {
+                self.mask &= !(1 << channel);
+            }
+        }
+    }
+
+    pub fn is_enabled(&self, channel: u8) -> bool {
+        channel < 16 && self.mask & (1 << channel) != 0
+    }
+
+    pub fn set_remap(&mut self, channel: Option<u8>) {
+        self.remap = channel;
+    }
+}
+
+impl EventProcessor for ChannelFilter {
+    fn process(&mut self, _ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents) {
+        for &event in input {
+            if self.is_enabled(event.channel) {
+                output.push(match self.remap {
+                    Some(channel) => event.with_channel(channel),
+                    None => event,
+                });
+            }
+        }
+    }
+
+    fn reset(&mut self, _sample_rate: f32) {}
+}
+
+impl Default for ChannelFilter {
+    fn default() -> Self {
+        Self::all()
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{
+    Event, EventData,
+    chord::{ChordQuality, MAX_CHORD_NOTES},
+    event_processors::{BlockEvents, EventProcessor},
+    process_context::ProcessContext,
+};
+
+// Plays a whole chord from every key. Overlapping chords that share a note
+// only release it once the last key holding it goes up.
+pub struct ChordTrigger {
+    intervals: [i8; MAX_CHORD_NOTES],
+    len: usize,
+    held: [[u8; 128]; 16],
+    // Shape each key was pressed with, so changing it doesn't strand notes.
+    pressed: [[i8; MAX_CHORD_NOTES]; 128],
+    pressed_len: [u8; 128],
+}
+
+impl ChordTrigger {
+    pub fn new(quality: ChordQuality) -> Self {
+        let mut trigger = Self {
+            intervals: [0; MAX_CHORD_NOTES],
+            len: 0,
+            held: [[0; 128]; 16],
+            pressed: [[0; MAX_CHORD_NOTES]; 128],
+            pressed_len: [0; 128],
+        };
+        trigger.set_quality(quality);
+        trigger
+    }
+
+    pub fn set_quality(&mut self, quality: ChordQuality) {
+        let intervals = quality.intervals();
+        for (out, &i) in self.intervals.iter_mut().zip(intervals) {
+            *out = i as i8;
+        }
+        self.len = intervals.len();
+    }
+
+    // Semitones from the played key, e.g. [0, 7, 12] for a power chord.
+    pub fn set_intervals(&mut self, intervals: &[i8]) {
+        self.len = intervals.len().min(MAX_CHORD_NOTES);
+        self.intervals[..self.len].copy_from_slice(&intervals[..self.len]);
+    }
+
+    pub fn intervals(&self) -> &[i8] {
+        &self.intervals[..self.len]
+    }
+}
+
+impl EventProcessor for ChordTrigger {
+    fn process(&mut self, _ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents) {
+        for &event in input {
+            let channel = event.channel as usize % 16;
+
+            match event.note() {
+                Some(note) if event.data.is_note_on() => {
+                    self.pressed[note as usize] = self.intervals;
+                    self.pressed_len[note as usize] = self.len as u8;
+
+                    for &i in &self.intervals[..self.len] {
+                        let Some(n) = offset(note, i) else { continue };
+                        self.held[channel][n as usize] += 1;
+                        output.push(event.with_note(n));
+                    }
+                }
+                Some(note) if event.data.is_note_off() => {
+                    let len = self.pressed_len[note as usize] as usize;
+                    self.pressed_len[note as usize] = 0;
+
+                    for &i in &self.pressed[note as usize][..len] {
+                        let Some(n) = offset(note, i) else { continue };
+                        let count = &mut self.held[channel][n as usize];
+                        if *count > 0 {
+                            *count -= 1;
+                            if *count == 0 {
+                                output.push(Event {
+                                    data: EventData::NoteOff { note: n },
+                                    ..event
+                                });
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    output.push(event);
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self, _sample_rate: f32) {
+        self.held = [[0; 128]; 16];
+        self.pressed_len = [0; 128];
+    }
+}
+
+fn offset(note: u8, interval: i8) -> Option<u8> {
+    let n = note as i16 + interval as i16;
+    (0..=127).contains(&n).then_some(n as u8)
+}
@@ -0,0 +1,153 @@
+use crate::{
+    Event, EventData, MAX_BLOCK_SIZE,
+    event_processors::{BlockEvents, EventProcessor},
+    process_context::ProcessContext,
+    rand::Rand,
+};
+
+// Random late starts and velocity changes. Events can only move later, ones
+// pushed past the block come out at the start of the next. Note offs keep
+// the delay of their note on so lengths stay intact.
+//
+// Delayed events wait in a fixed buffer. When it is full new ones are
+// dropped, except note offs: they cancel their note on if it is still
+// waiting, or else go out at the end of the block, so no note is left
+// hanging.
+pub struct Humanize {
+    timing_ms: f32,
+    velocity: u8,
+    delays: [[u16; 128]; 16],
+    pending: BlockEvents,
+    rand: Rand,
+}
+
+impl Humanize {
+    pub const MAX_TIMING_MS: f32 = 100.0;
+
+    pub fn new(timing_ms: f32, velocity: u8) -> Self {
+        let mut humanize = Self {
+            timing_ms: 0.0,
+            velocity: 0,
+            delays: [[0; 128]; 16],
+            pending: BlockEvents::new(),
+            rand: Rand::new(0x6C8E9CF5),
+        };
+        humanize.set_timing_ms(timing_ms);
+        humanize.set_velocity(velocity);
+        humanize
+    }
+
+    // Largest delay a note can get.
+    pub fn set_timing_ms(&mut self, ms: f32) {
+        self.timing_ms = ms.clamp(0.0, Self::MAX_TIMING_MS);
+    }
+
+    // Largest change either way.
+    pub fn set_velocity(&mut self, amount: u8) {
+        self.velocity = amount.min(127);
+    }
+
+    pub fn set_seed(&mut self, seed: u32) {
+        self.rand = Rand::new(seed);
+    }
+}
+
+impl EventProcessor for Humanize {
+    fn process(&mut self, ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents) {
+        let frames = MAX_BLOCK_SIZE as u32;
+        let max_delay = (self.timing_ms * 0.001 * ctx.sample_rate) as u32;
+
+        let mut carry = BlockEvents::new();
+        let mut route = |event: Event| {
+            if event.timing < frames {
+                output.push(event);
+                return;
+            }
+            let carried = Event {
+                timing: event.timing - frames,
+                ..event
+            };
+            if carry.push(carried) || !event.data.is_note_off() {
+                return;
+            }
+            let waiting = carry.iter().position(|e| {
+                e.channel == event.channel && e.data.is_note_on() && e.note() == event.note()
+            });
+            match waiting {
+                Some(i) => {
+                    carry.remove(i);
+                }
+                None => {
+                    output.push(Event {
+                        timing: frames - 1,
+                        ..event
+                    });
+                }
+            }
+        };
+
+        for &event in self.pending.iter() {
+            route(event);
+        }
+
+        for &event in input {
+            let channel = event.channel as usize % 16;
+            let event = match event.note() {
+                Some(note) if event.data.is_note_on() => {
+                    let delay = self.rand.next_range_u32(0, max_delay);
+                    self.delays[channel][note as usize] = delay as u16;
+
+                    // The spread is in MIDI 1.0 steps at either resolution.
+                    let spread = self.velocity as f32;
+                    let offset = self.rand.next_range_f32(-spread, spread);
+                    let data = match event.data {
+                        EventData::NoteOn16 { note, velocity } => {
+                            let offset = offset * u16::MAX as f32 / 127.0;
+                            let velocity = (velocity as f32 + offset).round();
+                            EventData::NoteOn16 {
+                                note,
+                                velocity: velocity.clamp(1.0, u16::MAX as f32) as u16,
+                            }
+                        }
+                        EventData::NoteOn { note, velocity } => {
+                            let velocity = (velocity as f32 + offset).round();
+                            EventData::NoteOn {
+                                note,
+                                velocity: velocity.clamp(1.0, 127.0) as u8,
+                            }
+                        }
+                        data => data,
+                    };
+
+                    Event {
+                        timing: event.timing + delay,
+                        data,
+                        ..event
+                    }
+                }
+                _ => match event.note() {
+                    Some(note) => Event {
+                        timing: event.timing + self.delays[channel][note as usize] as u32,
+                        ..event
+                    },
+                    None => event,
+                },
+            };
+            route(event);
+        }
+
+        output.sort_by_timing();
+        self.pending = carry;
+    }
+
+    fn reset(&mut self, _sample_rate: f32) {
+        self.pending.clear();
+        self.delays = [[0; 128]; 16];
+    }
+}
+
+impl Default for Humanize {
+    fn default() -> Self {
+        Self::new(10.0, 8)
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{
+    Event,
+    event_processors::{BlockEvents, EventProcessor},
+    process_context::ProcessContext,
+};
+
+// Passes notes inside low..=high, optionally moving them to another channel.
+// Two ranges in parallel make a keyboard split.
+pub struct KeyRange {
+    pub low: u8,
+    pub high: u8,
+    pub channel: Option<u8>,
+}
+
+impl KeyRange {
+    pub fn new(low: u8, high: u8) -> Self {
+        Self {
+            low: low.min(high),
+            high: high.max(low),
+            channel: None,
+        }
+    }
+
+    pub fn routed_to(mut self, channel: u8) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    pub fn contains(&self, note: u8) -> bool {
+        (self.low..=self.high).contains(&note)
+    }
+}
+
+impl EventProcessor for KeyRange {
+    fn process(&mut self, _ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents) {
+        for &event in input {
+            match event.note() {
+                Some(note) if !self.contains(note) => {}
+                Some(_) => {
+                    output.push(match self.channel {
+                        Some(channel) => event.with_channel(channel),
+                        None => event,
+                    });
+                }
+                None => {
+                    output.push(event);
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self, _sample_rate: f32) {}
+}
+
+impl Default for KeyRange {
+    fn default() -> Self {
+        Self::new(0, 127)
+    }
+}
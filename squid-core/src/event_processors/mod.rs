@@ -0,0 +1,25 @@
+use crate::{Event, EventBuffer, process_context::ProcessContext};
+
+pub const MAX_BLOCK_EVENTS: usize = 256;
+
+pub type BlockEvents = EventBuffer<MAX_BLOCK_EVENTS>;
+
+// Event-to-event stage in front of an instrument, the MIDI counterpart of an
+// `AudioNode`. Both slices are sorted by timing, events that don't fit in
+// `output` are dropped.
+pub trait EventProcessor {
+    fn process(&mut self, ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents);
+    fn reset(&mut self, sample_rate: f32);
+}
+
+pub mod arpeggiator;
+pub mod chain;
+pub mod channel_filter;
+pub mod chord_trigger;
+pub mod humanize;
+pub mod key_range;
+pub mod mpe;
+pub mod note_repeat;
+pub mod step_sequencer;
+pub mod transpose;
+pub mod velocity_curve;
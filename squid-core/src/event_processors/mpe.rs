@@ -0,0 +1,339 @@
+use core::ops::Range;
+
+use crate::{
+    Event, EventData, NOTE_BEND_RANGE,
+    event_processors::{BlockEvents, EventProcessor},
+    process_context::ProcessContext,
+};
+
+pub const MPE_LOWER_MASTER: u8 = 0;
+pub const MPE_UPPER_MASTER: u8 = 15;
+
+const CC_DATA_ENTRY: u8 = 6;
+const CC_DATA_ENTRY_LSB: u8 = 38;
+const CC_TIMBRE: u8 = 74;
+const CC_RPN_LSB: u8 = 100;
+const CC_RPN_MSB: u8 = 101;
+
+const RPN_BEND_RANGE: u16 = 0;
+const RPN_MPE_CONFIG: u16 = 6;
+const RPN_NULL: u16 = 0x3fff;
+
+const CENTER: u32 = 1 << 31;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MpeSide {
+    // Master on channel 1, members counting up from channel 2.
+    Lower,
+    // Master on channel 16, members counting down from channel 15.
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MpeZone {
+    pub members: u8,
+    // Semitones either way, for per-note bends on member channels.
+    pub member_bend_range: f32,
+    // Semitones either way, for bends on the master channel that move the
+    // whole zone.
+    pub master_bend_range: f32,
+}
+
+impl MpeZone {
+    // With the bend ranges the MPE spec starts from.
+    pub fn new(members: u8) -> Self {
+        Self {
+            members: members.min(15),
+            member_bend_range: 48.0,
+            master_bend_range: 2.0,
+        }
+    }
+}
+
+// Turns MPE input into per-note events. On member channels, pitch bend,
+// channel pressure and CC74 (timbre) become `NotePitchBend`, `NotePressure`
+// and `NoteControlChange` for the notes held on that channel, and new notes
+// get the channel's current expression right after their note on. Pitch bend
+// on a master channel is added to the per-note bends of the whole zone,
+// scaled by its own range. Other master channel messages and channels
+// outside both zones pass through unchanged.
+//
+// Per-note events use the high resolution variants and bends are rescaled to
+// `NOTE_BEND_RANGE`. Zones and bend ranges follow MPE configuration messages
+// and RPN 0 in the input.
+pub struct MpeInput {
+    lower: Option<MpeZone>,
+    upper: Option<MpeZone>,
+
+    // Per channel.
+    rpn: [u16; 16],
+    notes: [u128; 16],
+    bend: [u32; 16],
+    // Of the lower and upper master channels.
+    master_bend: [u32; 2],
+    pressure: [u32; 16],
+    timbre: [u32; 16],
+}
+
+impl MpeInput {
+    // No zones, everything passes through until configured.
+    pub fn new() -> Self {
+        Self {
+            lower: None,
+            upper: None,
+
+            rpn: [RPN_NULL; 16],
+            notes: [0; 16],
+            bend: [CENTER; 16],
+            master_bend: [CENTER; 2],
+            pressure: [0; 16],
+            timbre: [CENTER; 16],
+        }
+    }
+
+    // The common single zone setup, master on channel 1.
+    pub fn lower(members: u8) -> Self {
+        let mut mpe = Self::new();
+        mpe.set_zone(MpeSide::Lower, Some(MpeZone::new(members)));
+        mpe
+    }
+
+    pub fn zone(&self, side: MpeSide) -> Option<MpeZone> {
+        match side {
+            MpeSide::Lower => self.lower,
+            MpeSide::Upper => self.upper,
+        }
+    }
+
+    // Zones can't overlap, so the other one shrinks to make room, or goes
+    // away if nothing is left of it.
+    pub fn set_zone(&mut self, side: MpeSide, zone: Option<MpeZone>) {
+        let zone = zone.filter(|z| z.members > 0).map(|mut z| {
+            z.members = z.members.min(15);
+            z
+        });
+        let room = 14u8.saturating_sub(zone.map_or(0, |z| z.members));
+        let (this, other) = match side {
+            MpeSide::Lower => (&mut self.lower, &mut self.upper),
+            MpeSide::Upper => (&mut self.upper, &mut self.lower),
+        };
+        *this = zone;
+        if let Some(z) = other {
+            z.members = z.members.min(room);
+            if z.members == 0 {
+                *other = None;
+            }
+        }
+    }
+
+    // Zone the channel belongs to, and whether it is its master channel.
+    pub fn zone_of(&self, channel: u8) -> Option<(MpeSide, bool)> {
+        if let Some(zone) = self.lower {
+            if channel == MPE_LOWER_MASTER {
+                return Some((MpeSide::Lower, true));
+            }
+            if channel > MPE_LOWER_MASTER && channel <= zone.members {
+                return Some((MpeSide::Lower, false));
+            }
+        }
+        if let Some(zone) = self.upper {
+            if channel == MPE_UPPER_MASTER {
+                return Some((MpeSide::Upper, true));
+            }
+            if channel < MPE_UPPER_MASTER && channel >= MPE_UPPER_MASTER - zone.members {
+                return Some((MpeSide::Upper, false));
+            }
+        }
+        None
+    }
+
+    // RPN messages on zone channels, and on the two channels a zone can be
+    // configured from, are used here and not passed on.
+    fn rpn_control(&mut self, channel: u8, control: u8, value: u8) -> bool {
+        let ch = channel as usize % 16;
+        let value = value & 0x7f;
+        match control {
+            CC_RPN_MSB => self.rpn[ch] = (value as u16) << 7 | (self.rpn[ch] & 0x7f),
+            CC_RPN_LSB => self.rpn[ch] = (self.rpn[ch] & !0x7f) | value as u16,
+            CC_DATA_ENTRY => match self.rpn[ch] {
+                RPN_MPE_CONFIG if channel == MPE_LOWER_MASTER => {
+                    self.set_zone(MpeSide::Lower, Some(MpeZone::new(value)));
+                }
+                RPN_MPE_CONFIG if channel == MPE_UPPER_MASTER => {
+                    self.set_zone(MpeSide::Upper, Some(MpeZone::new(value)));
+                }
+                RPN_BEND_RANGE => self.set_bend_range(channel, |_| value as f32),
+                _ => {}
+            },
+            CC_DATA_ENTRY_LSB if self.rpn[ch] == RPN_BEND_RANGE => {
+                self.set_bend_range(channel, |range| {
+                    libm::floorf(range) + value.min(99) as f32 / 100.0
+                });
+            }
+            _ => return false,
+        }
+        self.zone_of(channel).is_some()
+            || channel == MPE_LOWER_MASTER
+            || channel == MPE_UPPER_MASTER
+    }
+
+    fn set_bend_range(&mut self, channel: u8, range: impl Fn(f32) -> f32) {
+        let Some((side, master)) = self.zone_of(channel) else {
+            return;
+        };
+        let zone = match side {
+            MpeSide::Lower => &mut self.lower,
+            MpeSide::Upper => &mut self.upper,
+        };
+        if let Some(zone) = zone {
+            let target = if master {
+                &mut zone.master_bend_range
+            } else {
+                &mut zone.member_bend_range
+            };
+            *target = range(*target).clamp(0.0, 96.0);
+        }
+    }
+
+    // Member channels of the zone.
+    fn members(&self, side: MpeSide) -> Range<u8> {
+        let members = self.zone(side).map_or(0, |z| z.members);
+        match side {
+            MpeSide::Lower => MPE_LOWER_MASTER + 1..MPE_LOWER_MASTER + 1 + members,
+            MpeSide::Upper => MPE_UPPER_MASTER - members..MPE_UPPER_MASTER,
+        }
+    }
+
+    // Member and master bend together, in `NOTE_BEND_RANGE`.
+    fn note_bend(&self, side: MpeSide, ch: usize) -> u32 {
+        let (member, master) = self.zone(side).map_or((0.0, 0.0), |z| {
+            (z.member_bend_range as f64, z.master_bend_range as f64)
+        });
+        let center = CENTER as f64;
+        let bend = |value: u32, range: f64| (value as f64 - center) / center * range;
+        let semitones = bend(self.bend[ch], member) + bend(self.master_bend[side as usize], master);
+        (center + semitones / NOTE_BEND_RANGE as f64 * center).clamp(0.0, u32::MAX as f64) as u32
+    }
+
+    // Master bends move every note in the zone and aren't passed on.
+    fn master_event(&mut self, side: MpeSide, event: Event, output: &mut BlockEvents) {
+        let EventData::PitchBend32 { value } = event.data.to_high_res() else {
+            output.push(event);
+            return;
+        };
+        self.master_bend[side as usize] = value;
+        for channel in self.members(side) {
+            let value = self.note_bend(side, channel as usize);
+            let event = Event { channel, ..event };
+            self.expression(event, output, |note| EventData::NotePitchBend32 {
+                note,
+                value,
+            });
+        }
+    }
+
+    // One event per note held on the channel of `event`.
+    fn expression(&self, event: Event, output: &mut BlockEvents, data: impl Fn(u8) -> EventData) {
+        let mut notes = self.notes[event.channel as usize % 16];
+        while notes != 0 {
+            let note = notes.trailing_zeros() as u8;
+            output.push(Event {
+                data: data(note),
+                ..event
+            });
+            notes &= notes - 1;
+        }
+    }
+
+    fn member_event(&mut self, side: MpeSide, event: Event, output: &mut BlockEvents) {
+        let ch = event.channel as usize % 16;
+        match event.data.to_high_res() {
+            EventData::NoteOn16 { note, .. } => {
+                let note = note & 0x7f;
+                self.notes[ch] |= 1 << note;
+                output.push(event);
+                for data in [
+                    EventData::NotePitchBend32 {
+                        note,
+                        value: self.note_bend(side, ch),
+                    },
+                    EventData::NotePressure32 {
+                        note,
+                        pressure: self.pressure[ch],
+                    },
+                    EventData::NoteControlChange32 {
+                        note,
+                        control: CC_TIMBRE,
+                        value: self.timbre[ch],
+                    },
+                ] {
+                    output.push(Event { data, ..event });
+                }
+            }
+            EventData::NoteOff { note } => {
+                self.notes[ch] &= !(1 << (note & 0x7f));
+                output.push(event);
+            }
+            EventData::PitchBend32 { value } => {
+                self.bend[ch] = value;
+                let value = self.note_bend(side, ch);
+                self.expression(event, output, |note| EventData::NotePitchBend32 {
+                    note,
+                    value,
+                });
+            }
+            EventData::ChannelPressure32 { pressure } => {
+                self.pressure[ch] = pressure;
+                self.expression(event, output, |note| EventData::NotePressure32 {
+                    note,
+                    pressure,
+                });
+            }
+            EventData::ControlChange32 {
+                control: CC_TIMBRE,
+                value,
+            } => {
+                self.timbre[ch] = value;
+                self.expression(event, output, |note| EventData::NoteControlChange32 {
+                    note,
+                    control: CC_TIMBRE,
+                    value,
+                });
+            }
+            _ => {
+                output.push(event);
+            }
+        }
+    }
+}
+
+impl EventProcessor for MpeInput {
+    fn process(&mut self, _ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents) {
+        for &event in input {
+            if let EventData::ControlChange { control, value } = event.data.to_midi1()
+                && self.rpn_control(event.channel, control, value)
+            {
+                continue;
+            }
+            match self.zone_of(event.channel) {
+                Some((side, false)) => self.member_event(side, event, output),
+                Some((side, true)) => self.master_event(side, event, output),
+                None => {
+                    output.push(event);
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self, _sample_rate: f32) {
+        let zones = (self.lower, self.upper);
+        *self = Self::new();
+        (self.lower, self.upper) = zones;
+    }
+}
+
+impl Default for MpeInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
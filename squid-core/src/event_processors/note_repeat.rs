@@ -0,0 +1,188 @@
+use crate::{
+    Event, EventData, MAX_BLOCK_SIZE,
+    event_processors::{BlockEvents, EventProcessor},
+    process_context::ProcessContext,
+};
+
+const MAX_HELD: usize = 16;
+
+#[derive(Clone, Copy)]
+struct Held {
+    note: u8,
+    channel: u8,
+    velocity: u8,
+    sounding: bool,
+    next_on: u64,
+    off_at: Option<u64>,
+}
+
+// Retriggers held keys at a fixed interval, like the note repeat on drum
+// machines.
+pub struct NoteRepeat {
+    interval_ms: f32,
+    sync_beats: Option<f64>,
+    gate: f32,
+    sample_rate: f32,
+    samples_per_beat: f64,
+    now: u64,
+    held: [Option<Held>; MAX_HELD],
+}
+
+impl NoteRepeat {
+    pub fn new(interval_ms: f32) -> Self {
+        let mut repeat = Self {
+            interval_ms: 125.0,
+            sync_beats: None,
+            gate: 0.5,
+            sample_rate: 44100.0,
+            samples_per_beat: 22050.0,
+            now: 0,
+            held: [None; MAX_HELD],
+        };
+        repeat.set_interval_ms(interval_ms);
+        repeat
+    }
+
+    pub fn set_interval_ms(&mut self, ms: f32) {
+        self.interval_ms = ms.clamp(5.0, 4000.0);
+    }
+
+    // Repeats every `beats` at the host tempo instead of the fixed interval,
+    // None goes back to milliseconds.
+    pub fn set_sync(&mut self, beats: Option<f64>) {
+        self.sync_beats = beats.map(|b| b.clamp(1.0 / 64.0, 16.0));
+    }
+
+    // Fraction of the interval each repeat holds, 1 is legato.
+    pub fn set_gate(&mut self, gate: f32) {
+        self.gate = gate.clamp(0.01, 1.0);
+    }
+
+    fn interval(&self) -> u64 {
+        let samples = match self.sync_beats {
+            Some(beats) => beats * self.samples_per_beat,
+            None => (self.interval_ms * 0.001 * self.sample_rate) as f64,
+        };
+        (samples as u64).max(1)
+    }
+
+    fn off_time(&self, on: u64) -> Option<u64> {
+        (self.gate < 1.0).then(|| on + (self.interval() as f32 * self.gate).max(1.0) as u64)
+    }
+
+    // Emits repeats and note offs due before `end`, in absolute samples.
+    fn advance(&mut self, start: u64, end: u64, output: &mut BlockEvents) {
+        loop {
+            let next = self
+                .held
+                .iter()
+                .enumerate()
+                .filter_map(|(i, h)| h.map(|h| (i, h)))
+                .flat_map(|(i, h)| {
+                    let off = h.off_at.filter(|_| h.sounding).map(|t| (t, 0, i));
+                    [off, Some((h.next_on, 1, i))]
+                })
+                .flatten()
+                .filter(|&(t, _, _)| t < end)
+                .min();
+
+            let Some((time, kind, i)) = next else {
+                break;
+            };
+            let timing = (time - start) as u32;
+            let Some(mut held) = self.held[i] else {
+                break;
+            };
+
+            if kind == 0 || held.sounding {
+                output.push(
+                    Event::new(timing, EventData::NoteOff { note: held.note })
+                        .with_channel(held.channel),
+                );
+                held.sounding = false;
+            }
+            if kind == 1 {
+                output.push(
+                    Event::new(
+                        timing,
+                        EventData::NoteOn {
+                            note: held.note,
+                            velocity: held.velocity,
+                        },
+                    )
+                    .with_channel(held.channel),
+                );
+                held.sounding = true;
+                held.next_on = time + self.interval();
+                held.off_at = self.off_time(time);
+            }
+            self.held[i] = Some(held);
+        }
+    }
+}
+
+impl EventProcessor for NoteRepeat {
+    fn process(&mut self, ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents) {
+        self.sample_rate = ctx.sample_rate;
+        self.samples_per_beat = ctx.time.samples_per_beat;
+        let start = self.now;
+
+        for &event in input {
+            let time = start + event.timing as u64;
+            self.advance(start, time, output);
+
+            let channel = event.channel;
+            match event.data.to_midi1() {
+                EventData::NoteOn { note, velocity } if velocity > 0 => {
+                    output.push(event);
+                    let held = Held {
+                        note,
+                        channel,
+                        velocity,
+                        sounding: true,
+                        next_on: time + self.interval(),
+                        off_at: self.off_time(time),
+                    };
+                    if let Some(slot) = self.held.iter_mut().find(|h| h.is_none()) {
+                        *slot = Some(held);
+                    }
+                }
+                EventData::NoteOn { note, .. } | EventData::NoteOff { note } => {
+                    let slot = self
+                        .held
+                        .iter_mut()
+                        .find(|h| h.is_some_and(|h| h.note == note && h.channel == channel));
+                    match slot {
+                        Some(slot) => {
+                            if slot.is_some_and(|h| h.sounding) {
+                                output.push(event);
+                            }
+                            *slot = None;
+                        }
+                        None => {
+                            output.push(event);
+                        }
+                    }
+                }
+                _ => {
+                    output.push(event);
+                }
+            }
+        }
+
+        self.now = start + MAX_BLOCK_SIZE as u64;
+        self.advance(start, self.now, output);
+    }
+
+    fn reset(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.now = 0;
+        self.held = [None; MAX_HELD];
+    }
+}
+
+impl Default for NoteRepeat {
+    fn default() -> Self {
+        Self::new(125.0)
+    }
+}
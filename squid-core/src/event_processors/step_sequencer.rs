@@ -0,0 +1,483 @@
+use libm::{ceil, floor};
+
+use crate::{
+    Event, EventBuffer, EventData, MAX_BLOCK_SIZE, TimeInfo,
+    event_processors::{BlockEvents, EventProcessor},
+    process_context::ProcessContext,
+    rand::Rand,
+};
+
+pub const MAX_STEPS: usize = 64;
+pub const MAX_TRACKS: usize = 8;
+pub const MAX_RATCHETS: u8 = 8;
+
+const SCHEDULE_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    pub active: bool,
+    pub note: u8,
+    pub velocity: u8,
+    // Fraction of the step (or of each ratchet) the note holds.
+    pub gate: f32,
+    pub probability: f32,
+    // Number of evenly spaced repeats inside the step.
+    pub ratchet: u8,
+    // Holds into the next step so a mono synth can glide to it.
+    pub slide: bool,
+}
+
+impl Step {
+    pub const OFF: Step = Step {
+        active: false,
+        note: 60,
+        velocity: 100,
+        gate: 0.5,
+        probability: 1.0,
+        ratchet: 1,
+        slide: false,
+    };
+
+    pub const fn note(note: u8, velocity: u8) -> Self {
+        Self {
+            active: true,
+            note,
+            velocity,
+            ..Self::OFF
+        }
+    }
+}
+
+impl Default for Step {
+    fn default() -> Self {
+        Self::OFF
+    }
+}
+
+// Hits spread as evenly as possible over `steps`, bit i set for step i.
+// Rotation moves the pattern later by that many steps.
+pub fn euclidean(hits: usize, steps: usize, rotation: usize) -> u64 {
+    let steps = steps.min(MAX_STEPS);
+    if steps == 0 {
+        return 0;
+    }
+    let hits = hits.min(steps);
+
+    let mut mask = 0u64;
+    for i in 0..steps {
+        if (i * hits) % steps < hits {
+            mask |= 1 << ((i + rotation) % steps);
+        }
+    }
+    mask
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Track {
+    steps: [Step; MAX_STEPS],
+    len: usize,
+    step_beats: f64,
+    pub channel: u8,
+    pub muted: bool,
+}
+
+impl Track {
+    pub fn new(len: usize, step_beats: f64) -> Self {
+        let mut track = Self {
+            steps: [Step::OFF; MAX_STEPS],
+            len: 1,
+            step_beats: 0.25,
+            channel: 0,
+            muted: false,
+        };
+        track.set_len(len);
+        track.set_rate(step_beats);
+        track
+    }
+
+    pub fn euclidean(hits: usize, len: usize, rotation: usize, step: Step) -> Self {
+        let mut track = Self::new(len, 0.25);
+        track.fill_euclidean(hits, rotation, step);
+        track
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps().iter().all(|s| !s.active)
+    }
+
+    pub fn set_len(&mut self, len: usize) {
+        self.len = len.clamp(1, MAX_STEPS);
+    }
+
+    // Step length in beats.
+    pub fn set_rate(&mut self, step_beats: f64) {
+        self.step_beats = step_beats.clamp(1.0 / 64.0, 16.0);
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.step_beats
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps[..self.len]
+    }
+
+    pub fn step(&self, index: usize) -> Option<&Step> {
+        self.steps().get(index)
+    }
+
+    pub fn step_mut(&mut self, index: usize) -> Option<&mut Step> {
+        self.steps[..self.len].get_mut(index)
+    }
+
+    pub fn set_step(&mut self, index: usize, step: Step) {
+        if let Some(s) = self.step_mut(index) {
+            *s = step;
+        }
+    }
+
+    // Replaces every step, hits get `step` and the rest are turned off.
+    pub fn fill_euclidean(&mut self, hits: usize, rotation: usize, step: Step) {
+        let mask = euclidean(hits, self.len, rotation);
+        for (i, s) in self.steps[..self.len].iter_mut().enumerate() {
+            *s = if mask & (1 << i) != 0 {
+                Step {
+                    active: true,
+                    ..step
+                }
+            } else {
+                Step::OFF
+            };
+        }
+    }
+}
+
+impl Default for Track {
+    fn default() -> Self {
+        Self::new(16, 0.25)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Pattern {
+    pub tracks: [Option<Track>; MAX_TRACKS],
+}
+
+impl Pattern {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_track(mut self, index: usize, track: Track) -> Self {
+        if let Some(slot) = self.tracks.get_mut(index) {
+            *slot = Some(track);
+        }
+        self
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Scheduled {
+    beat: f64,
+    channel: u8,
+    data: EventData,
+}
+
+// Plays a `Pattern` against the transport position. Every track loops on its
+// own length and rate from the bar the pattern started on, so tracks of
+// different lengths drift against each other as polymeters.
+pub struct StepSequencer {
+    pattern: Pattern,
+    queued_pattern: Option<Pattern>,
+    queued_tracks: [Option<Track>; MAX_TRACKS],
+
+    origin: f64,
+    beat: f64,
+    running: bool,
+    next_step: [u64; MAX_TRACKS],
+    // Note and channel held over from a slide into the same key, released
+    // by the step it tied into.
+    tied: [Option<(u8, u8)>; MAX_TRACKS],
+
+    scheduled: [Scheduled; SCHEDULE_CAPACITY],
+    scheduled_len: usize,
+    rand: Rand,
+}
+
+impl StepSequencer {
+    pub fn new(pattern: Pattern) -> Self {
+        Self {
+            pattern,
+            queued_pattern: None,
+            queued_tracks: [None; MAX_TRACKS],
+
+            origin: 0.0,
+            beat: 0.0,
+            running: false,
+            next_step: [0; MAX_TRACKS],
+            tied: [None; MAX_TRACKS],
+
+            scheduled: [Scheduled {
+                beat: 0.0,
+                channel: 0,
+                data: EventData::NoteOff { note: 0 },
+            }; SCHEDULE_CAPACITY],
+            scheduled_len: 0,
+            rand: Rand::new(0x9E3779B9),
+        }
+    }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    // Swapped in on the next bar line.
+    pub fn queue_pattern(&mut self, pattern: Pattern) {
+        self.queued_pattern = Some(pattern);
+    }
+
+    // Replaces a single track on the next bar line.
+    pub fn queue_track(&mut self, index: usize, track: Track) {
+        if let Some(slot) = self.queued_tracks.get_mut(index) {
+            *slot = Some(track);
+        }
+    }
+
+    // Applies right away, for edits while the pattern keeps its timing.
+    pub fn pattern_mut(&mut self) -> &mut Pattern {
+        &mut self.pattern
+    }
+
+    pub fn process<const CAPACITY: usize>(
+        &mut self,
+        time: &TimeInfo,
+        frames: usize,
+        out: &mut EventBuffer<CAPACITY>,
+    ) {
+        let samples_per_beat = time.samples_per_beat;
+        if !time.is_playing || samples_per_beat <= 0.0 {
+            if self.running {
+                self.running = false;
+                self.flush_note_offs(out);
+            }
+            return;
+        }
+
+        let start = time.beat;
+        let end = start + frames as f64 / samples_per_beat;
+
+        if !self.running || (start - self.beat).abs() * samples_per_beat > 0.5 {
+            self.flush_note_offs(out);
+            if start < self.origin {
+                self.origin = 0.0;
+            }
+            self.resync(start);
+            self.running = true;
+        }
+
+        let first = out.len();
+        let mut segment_start = start;
+        loop {
+            let next_bar = time.next_bar_beat(segment_start);
+            let segment_end = next_bar.min(end);
+
+            self.schedule_steps(segment_end, samples_per_beat);
+            self.emit(start, segment_end, samples_per_beat, out);
+
+            if next_bar > end {
+                break;
+            }
+            self.switch_at(next_bar);
+            if next_bar == end {
+                break;
+            }
+            segment_start = next_bar;
+        }
+        self.beat = end;
+
+        if out.len() > first {
+            out.sort_by_timing();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.origin = 0.0;
+        self.beat = 0.0;
+        self.running = false;
+        self.next_step = [0; MAX_TRACKS];
+        self.tied = [None; MAX_TRACKS];
+        self.scheduled_len = 0;
+    }
+
+    fn switch_at(&mut self, bar: f64) {
+        let mut changed = false;
+        if let Some(pattern) = self.queued_pattern.take() {
+            self.pattern = pattern;
+            self.origin = bar;
+            changed = true;
+        }
+        for (slot, queued) in self.pattern.tracks.iter_mut().zip(&mut self.queued_tracks) {
+            if let Some(track) = queued.take() {
+                *slot = Some(track);
+                changed = true;
+            }
+        }
+        if changed {
+            self.resync(bar);
+        }
+    }
+
+    fn resync(&mut self, beat: f64) {
+        for (track, next) in self.pattern.tracks.iter().zip(&mut self.next_step) {
+            if let Some(track) = track {
+                *next = ceil((beat - self.origin) / track.step_beats - 1e-9).max(0.0) as u64;
+            }
+        }
+    }
+
+    // Moves every step starting before `end` into the schedule.
+    fn schedule_steps(&mut self, end: f64, samples_per_beat: f64) {
+        for i in 0..MAX_TRACKS {
+            let Some(track) = self.pattern.tracks[i] else {
+                if let Some((note, channel)) = self.tied[i].take() {
+                    self.schedule(self.beat, channel, EventData::NoteOff { note });
+                }
+                continue;
+            };
+
+            loop {
+                let index = self.next_step[i];
+                let step_start = self.origin + index as f64 * track.step_beats;
+                if step_start >= end {
+                    break;
+                }
+                self.next_step[i] += 1;
+
+                let step = track.steps[index as usize % track.len];
+                let plays = !track.muted && step.active && self.rand.next_f32() < step.probability;
+
+                // A tie only carries on into the same key, anything else ends
+                // it the way a slide would.
+                let mut tied = false;
+                if let Some((note, channel)) = self.tied[i].take() {
+                    tied = plays && note == step.note && channel == track.channel;
+                    if !tied {
+                        let off = step_start + if plays { 1.0 / samples_per_beat } else { 0.0 };
+                        self.schedule(off, channel, EventData::NoteOff { note });
+                    }
+                }
+                if !plays {
+                    continue;
+                }
+
+                let next = track.steps[(index as usize + 1) % track.len];
+                let slides = step.slide && next.active;
+                // Sliding into the same key holds the note instead, a note off
+                // right after its retrigger would cut it.
+                let ties = slides && next.note == step.note;
+
+                let ratchets = step.ratchet.clamp(1, MAX_RATCHETS);
+                let length = track.step_beats / ratchets as f64;
+                for r in 0..ratchets {
+                    let on = step_start + r as f64 * length;
+                    let last = r + 1 == ratchets;
+
+                    if !(tied && r == 0) {
+                        self.schedule(
+                            on,
+                            track.channel,
+                            EventData::NoteOn {
+                                note: step.note,
+                                velocity: step.velocity,
+                            },
+                        );
+                    }
+                    if last && ties {
+                        self.tied[i] = Some((step.note, track.channel));
+                        continue;
+                    }
+                    let off = if slides && last {
+                        // Released a sample after the next note starts.
+                        step_start + track.step_beats + 1.0 / samples_per_beat
+                    } else {
+                        on + length * step.gate.clamp(0.01, 1.0) as f64
+                    };
+                    self.schedule(off, track.channel, EventData::NoteOff { note: step.note });
+                }
+            }
+        }
+    }
+
+    fn schedule(&mut self, beat: f64, channel: u8, data: EventData) {
+        if self.scheduled_len < SCHEDULE_CAPACITY {
+            self.scheduled[self.scheduled_len] = Scheduled {
+                beat,
+                channel,
+                data,
+            };
+            self.scheduled_len += 1;
+        }
+    }
+
+    fn emit<const CAPACITY: usize>(
+        &mut self,
+        block_start: f64,
+        end: f64,
+        samples_per_beat: f64,
+        out: &mut EventBuffer<CAPACITY>,
+    ) {
+        // Kept in schedule order so a note off lands before a note on that
+        // shares its sample.
+        let mut kept = 0;
+        for i in 0..self.scheduled_len {
+            let event = self.scheduled[i];
+            if event.beat < end {
+                let timing = floor((event.beat - block_start) * samples_per_beat + 1e-6).max(0.0);
+                out.push(Event::new(timing as u32, event.data).with_channel(event.channel));
+            } else {
+                self.scheduled[kept] = event;
+                kept += 1;
+            }
+        }
+        self.scheduled_len = kept;
+    }
+
+    // Releases every pending note off at the start of the block and drops
+    // notes that haven't started yet.
+    fn flush_note_offs<const CAPACITY: usize>(&mut self, out: &mut EventBuffer<CAPACITY>) {
+        let scheduled = &self.scheduled[..self.scheduled_len];
+        for (i, event) in scheduled.iter().enumerate() {
+            let EventData::NoteOff { note } = event.data else {
+                continue;
+            };
+            let started = !scheduled[..i].iter().any(|e| {
+                e.channel == event.channel
+                    && matches!(e.data, EventData::NoteOn { note: n, .. } if n == note)
+            });
+            if started {
+                out.push(Event::new(0, event.data).with_channel(event.channel));
+            }
+        }
+        self.scheduled_len = 0;
+
+        for (note, channel) in self.tied.iter_mut().filter_map(Option::take) {
+            out.push(Event::new(0, EventData::NoteOff { note }).with_channel(channel));
+        }
+    }
+}
+
+// Input events pass through and are merged with the pattern's notes.
+impl EventProcessor for StepSequencer {
+    fn process(&mut self, ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents) {
+        output.extend_from_slice(input);
+        StepSequencer::process(self, &ctx.time, MAX_BLOCK_SIZE, output);
+    }
+
+    fn reset(&mut self, _sample_rate: f32) {
+        StepSequencer::reset(self);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    Event,
+    event_processors::{BlockEvents, EventProcessor},
+    process_context::ProcessContext,
+};
+
+const NONE: u8 = u8::MAX;
+
+// Shifts every note. Note offs follow the shift their note on got, so
+// changing the amount while keys are held doesn't leave notes hanging.
+pub struct Transpose {
+    semitones: i8,
+    sounding: [[u8; 128]; 16],
+}
+
+impl Transpose {
+    pub fn new(semitones: i8) -> Self {
+        Self {
+            semitones,
+            sounding: [[NONE; 128]; 16],
+        }
+    }
+
+    pub fn set_semitones(&mut self, semitones: i8) {
+        self.semitones = semitones;
+    }
+
+    pub fn semitones(&self) -> i8 {
+        self.semitones
+    }
+}
+
+impl EventProcessor for Transpose {
+    fn process(&mut self, _ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents) {
+        for event in input {
+            let Some(note) = event.note() else {
+                output.push(*event);
+                continue;
+            };
+            let slot = &mut self.sounding[event.channel as usize % 16][note as usize];
+
+            let target = match event.data {
+                _ if event.data.is_note_on() => {
+                    let target = shifted(note, self.semitones);
+                    *slot = target.unwrap_or(NONE);
+                    target
+                }
+                _ if event.data.is_note_off() => {
+                    let target = *slot;
+                    *slot = NONE;
+                    (target != NONE).then_some(target)
+                }
+                _ if *slot != NONE => Some(*slot),
+                _ => shifted(note, self.semitones),
+            };
+
+            if let Some(target) = target {
+                output.push(event.with_note(target));
+            }
+        }
+    }
+
+    fn reset(&mut self, _sample_rate: f32) {
+        self.sounding = [[NONE; 128]; 16];
+    }
+}
+
+impl Default for Transpose {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+fn shifted(note: u8, semitones: i8) -> Option<u8> {
+    let note = note as i16 + semitones as i16;
+    (0..=127).contains(&note).then_some(note as u8)
+}
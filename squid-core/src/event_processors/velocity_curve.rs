@@ -0,0 +1,91 @@
+use libm::powf;
+
+use crate::{
+    Event, EventData,
+    event_processors::{BlockEvents, EventProcessor},
+    process_context::ProcessContext,
+};
+
+// Reshapes note-on velocities. Positive curves make soft playing louder,
+// negative ones need a harder touch. The result is scaled into min..=max.
+pub struct VelocityCurve {
+    exponent: f32,
+    min: u8,
+    max: u8,
+    fixed: Option<u8>,
+}
+
+impl VelocityCurve {
+    pub fn new() -> Self {
+        Self {
+            exponent: 1.0,
+            min: 1,
+            max: 127,
+            fixed: None,
+        }
+    }
+
+    // -1..=1, 0 leaves the response linear.
+    pub fn set_curve(&mut self, curve: f32) {
+        self.exponent = powf(4.0, -curve.clamp(-1.0, 1.0));
+    }
+
+    pub fn set_range(&mut self, min: u8, max: u8) {
+        self.min = min.clamp(1, 127);
+        self.max = max.clamp(self.min, 127);
+    }
+
+    // Every note plays at this velocity, like a fixed-velocity switch.
+    pub fn set_fixed(&mut self, velocity: Option<u8>) {
+        self.fixed = velocity.map(|v| v.clamp(1, 127));
+    }
+
+    pub fn apply(&self, velocity: u8) -> u8 {
+        (self.apply_normalized(velocity.min(127) as f32 / 127.0) * 127.0 + 0.5) as u8
+    }
+
+    // 0 to 1 in and out, for high resolution velocities.
+    pub fn apply_normalized(&self, velocity: f32) -> f32 {
+        if let Some(fixed) = self.fixed {
+            return fixed as f32 / 127.0;
+        }
+        let x = powf(velocity.clamp(0.0, 1.0), self.exponent);
+        let range = (self.max - self.min) as f32;
+        (self.min as f32 + x * range) / 127.0
+    }
+}
+
+impl EventProcessor for VelocityCurve {
+    fn process(&mut self, _ctx: &ProcessContext, input: &[Event], output: &mut BlockEvents) {
+        for &event in input {
+            let event = match event.data {
+                EventData::NoteOn { note, velocity } if velocity > 0 => Event {
+                    data: EventData::NoteOn {
+                        note,
+                        velocity: self.apply(velocity),
+                    },
+                    ..event
+                },
+                EventData::NoteOn16 { note, velocity } => Event {
+                    data: EventData::NoteOn16 {
+                        note,
+                        velocity: (self.apply_normalized(velocity as f32 / u16::MAX as f32)
+                            * u16::MAX as f32
+                            + 0.5) as u16,
+                    },
+                    ..event
+                },
+                _ => event,
+            };
+            output.push(event);
+        }
+    }
+
+    fn reset(&mut self, _sample_rate: f32) {}
+}
+
+impl Default for VelocityCurve {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -9,19 +9,35 @@ pub mod buffer;
 pub mod common;
 pub mod config;
 pub mod event;
+pub mod event_processors;
 pub mod frequency;
+pub mod metering;
+pub mod midi;
+pub mod mts;
 pub mod note;
 pub mod plugin;
+pub mod preset;
+pub mod sysex;
 pub mod telemetry;
+pub mod theory;
 pub mod timing;
+pub mod tuning;
 
 pub use audio_nodes::*;
 pub use buffer::*;
 pub use common::*;
 pub use config::*;
 pub use event::*;
+pub use event_processors::*;
 pub use frequency::*;
+pub use metering::*;
+pub use midi::*;
+pub use mts::*;
 pub use note::*;
 pub use plugin::*;
+pub use preset::*;
+pub use sysex::*;
 pub use telemetry::*;
+pub use theory::*;
 pub use timing::*;
+pub use tuning::*;
@@ -0,0 +1,502 @@
+// Level and loudness metering. `Meter` runs on the audio thread and publishes
+// into `MeterReadings`, which any other thread can read without locking.
+// Loudness follows ITU-R BS.1770 / EBU R128 (momentary, short-term, integrated
+// and loudness range as in EBU Tech 3342).
+
+use core::{
+    f32::consts::PI,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use libm::{cosf, log10f, powf, sinf, sqrtf};
+
+use crate::{AtomicF32, SILENCE_DB, dsp::filters::k_weighting::KWeighting};
+
+pub const RMS_MIN_WINDOW_MS: f32 = 10.0;
+pub const RMS_MAX_WINDOW_MS: f32 = 3000.0;
+const RMS_MAX_SLICES: usize = (RMS_MAX_WINDOW_MS / RMS_MIN_WINDOW_MS) as usize;
+
+const LOUDNESS_BLOCK_MS: f32 = 100.0;
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+
+const ABSOLUTE_GATE: f32 = -70.0;
+const INTEGRATED_RELATIVE_GATE: f32 = -10.0;
+const RANGE_RELATIVE_GATE: f32 = -20.0;
+
+const HISTOGRAM_MAX: f32 = 10.0;
+const HISTOGRAM_STEP: f32 = 0.1;
+const HISTOGRAM_BINS: usize = ((HISTOGRAM_MAX - ABSOLUTE_GATE) / HISTOGRAM_STEP) as usize;
+
+// Peak fall back as in IEC 60268-18, 20 dB in 1.7 s.
+const PEAK_FALL_DB_PER_SEC: f32 = 20.0 / 1.7;
+
+const TRUE_PEAK_PHASES: usize = 4;
+const TRUE_PEAK_TAPS: usize = 12;
+
+pub fn gain_to_db(gain: f32) -> f32 {
+    if gain > 0.0 {
+        (20.0 * log10f(gain)).max(SILENCE_DB)
+    } else {
+        SILENCE_DB
+    }
+}
+
+fn power_to_lufs(power: f32) -> f32 {
+    if power > 0.0 {
+        (-0.691 + 10.0 * log10f(power)).max(SILENCE_DB)
+    } else {
+        SILENCE_DB
+    }
+}
+
+pub struct MeterReadings {
+    sample_peak: [AtomicF32; 2],
+    true_peak: [AtomicF32; 2],
+    max_true_peak: AtomicF32,
+    rms: [AtomicF32; 2],
+    momentary: AtomicF32,
+    short_term: AtomicF32,
+    integrated: AtomicF32,
+    range: AtomicF32,
+    reset: AtomicBool,
+}
+
+impl MeterReadings {
+    pub const fn new() -> Self {
+        Self {
+            sample_peak: [AtomicF32::new(0.0), AtomicF32::new(0.0)],
+            true_peak: [AtomicF32::new(0.0), AtomicF32::new(0.0)],
+            max_true_peak: AtomicF32::new(0.0),
+            rms: [AtomicF32::new(0.0), AtomicF32::new(0.0)],
+            momentary: AtomicF32::new(SILENCE_DB),
+            short_term: AtomicF32::new(SILENCE_DB),
+            integrated: AtomicF32::new(SILENCE_DB),
+            range: AtomicF32::new(0.0),
+            reset: AtomicBool::new(false),
+        }
+    }
+
+    // Held sample peak, linear.
+    pub fn sample_peak(&self, channel: usize) -> f32 {
+        self.sample_peak[channel].load()
+    }
+
+    // Held inter-sample peak, linear.
+    pub fn true_peak(&self, channel: usize) -> f32 {
+        self.true_peak[channel].load()
+    }
+
+    // Highest true peak since the last loudness reset, linear.
+    pub fn max_true_peak(&self) -> f32 {
+        self.max_true_peak.load()
+    }
+
+    pub fn rms(&self, channel: usize) -> f32 {
+        self.rms[channel].load()
+    }
+
+    // LUFS
+    pub fn momentary(&self) -> f32 {
+        self.momentary.load()
+    }
+
+    // LUFS
+    pub fn short_term(&self) -> f32 {
+        self.short_term.load()
+    }
+
+    // LUFS
+    pub fn integrated(&self) -> f32 {
+        self.integrated.load()
+    }
+
+    // LU
+    pub fn loudness_range(&self) -> f32 {
+        self.range.load()
+    }
+
+    // Restarts integrated loudness, loudness range and the true peak maximum.
+    // Picked up by the audio thread on its next block.
+    pub fn request_reset(&self) {
+        self.reset.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Default for MeterReadings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 4x oversampled peak detection, the interpolator is a windowed sinc split
+// into four polyphase branches.
+#[derive(Clone, Copy)]
+struct TruePeak {
+    coeffs: [[f32; TRUE_PEAK_TAPS]; TRUE_PEAK_PHASES],
+    history: [f32; TRUE_PEAK_TAPS * 2],
+    pos: usize,
+}
+
+impl TruePeak {
+    fn new() -> Self {
+        let len = TRUE_PEAK_TAPS * TRUE_PEAK_PHASES;
+        let center = (len - 1) as f32 * 0.5;
+        let mut coeffs = [[0.0; TRUE_PEAK_TAPS]; TRUE_PEAK_PHASES];
+
+        for (p, phase) in coeffs.iter_mut().enumerate() {
+            for (k, c) in phase.iter_mut().enumerate() {
+                let n = k * TRUE_PEAK_PHASES + p;
+                let x = (n as f32 - center) / TRUE_PEAK_PHASES as f32;
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    sinf(PI * x) / (PI * x)
+                };
+
+                // Blackman window
+                let w = n as f32 / (len - 1) as f32;
+                let window = 0.42 - 0.5 * cosf(2.0 * PI * w) + 0.08 * cosf(4.0 * PI * w);
+
+                *c = sinc * window;
+            }
+
+            let sum: f32 = phase.iter().sum();
+            phase.iter_mut().for_each(|c| *c /= sum);
+        }
+
+        Self {
+            coeffs,
+            history: [0.0; TRUE_PEAK_TAPS * 2],
+            pos: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn process(&mut self, x: f32) -> f32 {
+        self.history[self.pos] = x;
+        self.history[self.pos + TRUE_PEAK_TAPS] = x;
+        self.pos = (self.pos + 1) % TRUE_PEAK_TAPS;
+
+        // Newest sample last.
+        let window = &self.history[self.pos..self.pos + TRUE_PEAK_TAPS];
+        self.coeffs.iter().fold(x.abs(), |peak, phase| {
+            let y: f32 = window.iter().rev().zip(phase).map(|(s, c)| s * c).sum();
+            peak.max(y.abs())
+        })
+    }
+
+    fn reset(&mut self) {
+        self.history = [0.0; TRUE_PEAK_TAPS * 2];
+        self.pos = 0;
+    }
+}
+
+// Sliding RMS over whole 10 ms slices.
+#[derive(Clone, Copy)]
+struct RmsWindow {
+    slices: [f32; RMS_MAX_SLICES],
+    pos: usize,
+    window: usize,
+    acc: f32,
+    count: usize,
+}
+
+impl RmsWindow {
+    fn new() -> Self {
+        Self {
+            slices: [0.0; RMS_MAX_SLICES],
+            pos: 0,
+            window: 30,
+            acc: 0.0,
+            count: 0,
+        }
+    }
+
+    // Returns the new RMS value when a slice completes.
+    #[inline(always)]
+    fn process(&mut self, x: f32, slice_len: usize) -> Option<f32> {
+        self.acc += x * x;
+        self.count += 1;
+        if self.count < slice_len {
+            return None;
+        }
+
+        self.slices[self.pos] = self.acc;
+        self.pos = (self.pos + 1) % RMS_MAX_SLICES;
+        self.acc = 0.0;
+        self.count = 0;
+
+        let sum: f32 = (1..=self.window)
+            .map(|back| self.slices[(self.pos + RMS_MAX_SLICES - back) % RMS_MAX_SLICES])
+            .sum();
+        Some(sqrtf(sum / (self.window * slice_len) as f32))
+    }
+
+    fn reset(&mut self) {
+        self.slices = [0.0; RMS_MAX_SLICES];
+        self.acc = 0.0;
+        self.count = 0;
+    }
+}
+
+// Loudness values above the absolute gate in 0.1 LU bins. Keeps gating exact
+// to the bin width for programmes of any length without allocating.
+#[derive(Clone, Copy)]
+struct LoudnessHistogram {
+    counts: [u32; HISTOGRAM_BINS],
+    total: u32,
+}
+
+impl LoudnessHistogram {
+    fn new() -> Self {
+        Self {
+            counts: [0; HISTOGRAM_BINS],
+            total: 0,
+        }
+    }
+
+    fn bin_loudness(bin: usize) -> f32 {
+        ABSOLUTE_GATE + (bin as f32 + 0.5) * HISTOGRAM_STEP
+    }
+
+    fn bin_power(bin: usize) -> f32 {
+        powf(10.0, (Self::bin_loudness(bin) + 0.691) / 10.0)
+    }
+
+    fn add(&mut self, loudness: f32) {
+        if loudness <= ABSOLUTE_GATE {
+            return;
+        }
+        let bin = ((loudness - ABSOLUTE_GATE) / HISTOGRAM_STEP) as usize;
+        self.counts[bin.min(HISTOGRAM_BINS - 1)] += 1;
+        self.total += 1;
+    }
+
+    // First bin at or above `relative_gate` LU below the mean of all values.
+    fn gate_bin(&self, relative_gate: f32) -> Option<usize> {
+        if self.total == 0 {
+            return None;
+        }
+
+        let mean = self.mean_power(0)?;
+        let threshold = power_to_lufs(mean) + relative_gate;
+        let bin = ((threshold - ABSOLUTE_GATE) / HISTOGRAM_STEP).max(0.0) as usize;
+        Some(bin.min(HISTOGRAM_BINS - 1))
+    }
+
+    fn mean_power(&self, from_bin: usize) -> Option<f32> {
+        let (sum, count) = self.counts[from_bin..].iter().enumerate().fold(
+            (0.0f64, 0u32),
+            |(sum, count), (i, &n)| {
+                let power = Self::bin_power(from_bin + i) as f64;
+                (sum + power * n as f64, count + n)
+            },
+        );
+        (count > 0).then(|| (sum / count as f64) as f32)
+    }
+
+    fn integrated(&self) -> f32 {
+        self.gate_bin(INTEGRATED_RELATIVE_GATE)
+            .and_then(|bin| self.mean_power(bin))
+            .map_or(SILENCE_DB, power_to_lufs)
+    }
+
+    fn range(&self) -> f32 {
+        let Some(gate) = self.gate_bin(RANGE_RELATIVE_GATE) else {
+            return 0.0;
+        };
+        let count: u32 = self.counts[gate..].iter().sum();
+        if count == 0 {
+            return 0.0;
+        }
+
+        let percentile = |p: f32| {
+            let target = (p * (count - 1) as f32) as u32;
+            let mut seen = 0;
+            for (i, &n) in self.counts[gate..].iter().enumerate() {
+                seen += n;
+                if seen > target {
+                    return Self::bin_loudness(gate + i);
+                }
+            }
+            Self::bin_loudness(HISTOGRAM_BINS - 1)
+        };
+
+        percentile(0.95) - percentile(0.10)
+    }
+
+    fn reset(&mut self) {
+        self.counts = [0; HISTOGRAM_BINS];
+        self.total = 0;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Meter {
+    sample_rate: f32,
+    rms_slice_len: usize,
+    rms_window_ms: f32,
+
+    k_weighting: [KWeighting; 2],
+    true_peak: [TruePeak; 2],
+    rms: [RmsWindow; 2],
+    held_peaks: [[f32; 2]; 2],
+
+    block_len: usize,
+    block_acc: f64,
+    block_count: usize,
+    blocks: [f32; SHORT_TERM_BLOCKS],
+    block_pos: usize,
+    blocks_seen: usize,
+
+    integrated: LoudnessHistogram,
+    range: LoudnessHistogram,
+}
+
+impl Meter {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut meter = Self {
+            sample_rate,
+            rms_slice_len: 1,
+            rms_window_ms: 300.0,
+
+            k_weighting: [KWeighting::new(sample_rate); 2],
+            true_peak: [TruePeak::new(); 2],
+            rms: [RmsWindow::new(); 2],
+            held_peaks: [[0.0; 2]; 2],
+
+            block_len: 1,
+            block_acc: 0.0,
+            block_count: 0,
+            blocks: [0.0; SHORT_TERM_BLOCKS],
+            block_pos: 0,
+            blocks_seen: 0,
+
+            integrated: LoudnessHistogram::new(),
+            range: LoudnessHistogram::new(),
+        };
+        meter.set_sample_rate(sample_rate);
+        meter
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.rms_slice_len = ((RMS_MIN_WINDOW_MS * 0.001 * sample_rate) as usize).max(1);
+        self.block_len = ((LOUDNESS_BLOCK_MS * 0.001 * sample_rate) as usize).max(1);
+        for filter in &mut self.k_weighting {
+            filter.update_coeffs(sample_rate);
+        }
+        self.set_rms_window_ms(self.rms_window_ms);
+        self.reset();
+    }
+
+    // Rounded to whole 10 ms slices.
+    pub fn set_rms_window_ms(&mut self, ms: f32) {
+        self.rms_window_ms = ms.clamp(RMS_MIN_WINDOW_MS, RMS_MAX_WINDOW_MS);
+        let slices = (self.rms_window_ms / RMS_MIN_WINDOW_MS) as usize;
+        for rms in &mut self.rms {
+            rms.window = slices.clamp(1, RMS_MAX_SLICES);
+        }
+    }
+
+    pub fn reset_loudness(&mut self) {
+        self.integrated.reset();
+        self.range.reset();
+    }
+
+    pub fn reset(&mut self) {
+        for ch in 0..2 {
+            self.k_weighting[ch].reset();
+            self.true_peak[ch].reset();
+            self.rms[ch].reset();
+        }
+        self.held_peaks = [[0.0; 2]; 2];
+        self.block_acc = 0.0;
+        self.block_count = 0;
+        self.blocks = [0.0; SHORT_TERM_BLOCKS];
+        self.block_pos = 0;
+        self.blocks_seen = 0;
+        self.reset_loudness();
+    }
+
+    pub fn process(&mut self, left: &[f32], right: &[f32], readings: &MeterReadings) {
+        if readings.reset.swap(false, Ordering::Relaxed) {
+            self.reset_loudness();
+            readings.max_true_peak.store(0.0);
+            readings.integrated.store(SILENCE_DB);
+            readings.range.store(0.0);
+        }
+
+        let mut sample_peak = [0.0f32; 2];
+        let mut true_peak = [0.0f32; 2];
+
+        for (&l, &r) in left.iter().zip(right) {
+            for (ch, x) in [l, r].into_iter().enumerate() {
+                sample_peak[ch] = sample_peak[ch].max(x.abs());
+                true_peak[ch] = true_peak[ch].max(self.true_peak[ch].process(x));
+
+                if let Some(rms) = self.rms[ch].process(x, self.rms_slice_len) {
+                    readings.rms[ch].store(rms);
+                }
+            }
+
+            let kl = self.k_weighting[0].process(l) as f64;
+            let kr = self.k_weighting[1].process(r) as f64;
+            self.block_acc += kl * kl + kr * kr;
+            self.block_count += 1;
+
+            if self.block_count == self.block_len {
+                self.finish_block(readings);
+            }
+        }
+
+        let secs = left.len() as f32 / self.sample_rate;
+        let fall = powf(10.0, -PEAK_FALL_DB_PER_SEC * secs / 20.0);
+        let [held_sample, held_true] = &mut self.held_peaks;
+
+        for ch in 0..2 {
+            held_sample[ch] = (held_sample[ch] * fall).max(sample_peak[ch]);
+            held_true[ch] = (held_true[ch] * fall).max(true_peak[ch]);
+            readings.sample_peak[ch].store(held_sample[ch]);
+            readings.true_peak[ch].store(held_true[ch]);
+        }
+        readings
+            .max_true_peak
+            .fetch_max_positive(true_peak[0].max(true_peak[1]));
+    }
+
+    fn finish_block(&mut self, readings: &MeterReadings) {
+        self.blocks[self.block_pos] = (self.block_acc / self.block_len as f64) as f32;
+        self.block_pos = (self.block_pos + 1) % SHORT_TERM_BLOCKS;
+        self.blocks_seen += 1;
+        self.block_acc = 0.0;
+        self.block_count = 0;
+
+        let window_power = |count: usize| {
+            let sum: f32 = (1..=count)
+                .map(|back| {
+                    self.blocks[(self.block_pos + SHORT_TERM_BLOCKS - back) % SHORT_TERM_BLOCKS]
+                })
+                .sum();
+            sum / count as f32
+        };
+
+        // 400 ms gating blocks with 75% overlap.
+        if self.blocks_seen >= MOMENTARY_BLOCKS {
+            let momentary = power_to_lufs(window_power(MOMENTARY_BLOCKS));
+            readings.momentary.store(momentary);
+
+            self.integrated.add(momentary);
+            readings.integrated.store(self.integrated.integrated());
+        }
+
+        if self.blocks_seen >= SHORT_TERM_BLOCKS {
+            let short_term = power_to_lufs(window_power(SHORT_TERM_BLOCKS));
+            readings.short_term.store(short_term);
+
+            self.range.add(short_term);
+            readings.range.store(self.range.range());
+        }
+    }
+}
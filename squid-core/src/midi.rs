@@ -0,0 +1,267 @@
+use crate::{Event, EventData, SysExChunk, SysExStatus};
+
+pub const UMP_MIDI1: u8 = 0x2;
+pub const UMP_SYSEX7: u8 = 0x3;
+pub const UMP_MIDI2: u8 = 0x4;
+
+const NOTE_OFF: u8 = 0x80;
+const NOTE_ON: u8 = 0x90;
+const POLY_PRESSURE: u8 = 0xa0;
+const CONTROL_CHANGE: u8 = 0xb0;
+const PROGRAM_CHANGE: u8 = 0xc0;
+const CHANNEL_PRESSURE: u8 = 0xd0;
+const PITCH_BEND: u8 = 0xe0;
+
+// MIDI 2.0 only opcodes, in the same nibble as the status above.
+const REGISTERED_PER_NOTE: u8 = 0x00;
+const ASSIGNABLE_PER_NOTE: u8 = 0x10;
+const PER_NOTE_PITCH_BEND: u8 = 0x60;
+
+impl Event {
+    // One MIDI 1.0 channel voice message. Running status and system
+    // messages aren't handled.
+    pub fn from_midi1_bytes(timing: u32, bytes: &[u8]) -> Option<Self> {
+        let (&status, data) = bytes.split_first()?;
+        if !(0x80..0xf0).contains(&status) {
+            return None;
+        }
+        let d1 = data.first().map(|b| b & 0x7f);
+        let d2 = data.get(1).map(|b| b & 0x7f);
+        let two = || Some((d1?, d2?));
+
+        let data = match status & 0xf0 {
+            NOTE_OFF => EventData::NoteOff { note: two()?.0 },
+            NOTE_ON => {
+                let (note, velocity) = two()?;
+                EventData::NoteOn { note, velocity }
+            }
+            POLY_PRESSURE => {
+                let (note, pressure) = two()?;
+                EventData::NotePressure { note, pressure }
+            }
+            CONTROL_CHANGE => {
+                let (control, value) = two()?;
+                EventData::ControlChange { control, value }
+            }
+            PROGRAM_CHANGE => EventData::ProgramChange { program: d1? },
+            CHANNEL_PRESSURE => EventData::ChannelPressure { pressure: d1? },
+            _ => {
+                let (lsb, msb) = two()?;
+                EventData::PitchBend {
+                    value: (msb as u16) << 7 | lsb as u16,
+                }
+            }
+        };
+        Some(Event::new(timing, data).with_channel(status & 0x0f))
+    }
+
+    // MIDI 1.0 bytes and how many of them are used. High resolution values
+    // are scaled down. Per-note bends and controllers and SysEx have no
+    // channel voice form.
+    pub fn to_midi1_bytes(&self) -> Option<([u8; 3], usize)> {
+        let ch = self.channel & 0x0f;
+        let (status, d1, d2) = match self.data.to_midi1() {
+            EventData::NoteOff { note } => (NOTE_OFF, note, Some(0)),
+            EventData::NoteOn { note, velocity } => (NOTE_ON, note, Some(velocity)),
+            EventData::NotePressure { note, pressure } => (POLY_PRESSURE, note, Some(pressure)),
+            EventData::ControlChange { control, value } => (CONTROL_CHANGE, control, Some(value)),
+            EventData::ProgramChange { program } => (PROGRAM_CHANGE, program, None),
+            EventData::ChannelPressure { pressure } => (CHANNEL_PRESSURE, pressure, None),
+            EventData::PitchBend { value } => (
+                PITCH_BEND,
+                (value & 0x7f) as u8,
+                Some((value >> 7 & 0x7f) as u8),
+            ),
+            _ => return None,
+        };
+        Some(match d2 {
+            Some(d2) => ([status | ch, d1 & 0x7f, d2 & 0x7f], 3),
+            None => ([status | ch, d1 & 0x7f, 0], 2),
+        })
+    }
+}
+
+// A Universal MIDI Packet of up to two words: MIDI 1.0 and MIDI 2.0 channel
+// voice messages and 7 bit SysEx. Events without a MIDI 1.0 form, and all
+// high resolution ones, are sent as MIDI 2.0 messages so nothing is lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ump {
+    words: [u32; 2],
+}
+
+impl Ump {
+    // Packet length in words from its first word, for walking a stream of
+    // packets including the kinds this type doesn't hold.
+    pub fn word_count(first: u32) -> usize {
+        match first >> 28 {
+            0x0 | 0x1 | 0x2 | 0x6 | 0x7 => 1,
+            0x3 | 0x4 | 0x8 | 0x9 | 0xa => 2,
+            0xb | 0xc => 3,
+            _ => 4,
+        }
+    }
+
+    // None unless `words` holds exactly one packet of a supported type.
+    pub fn from_words(words: &[u32]) -> Option<Self> {
+        let &first = words.first()?;
+        let kind = (first >> 28) as u8;
+        if !matches!(kind, UMP_MIDI1 | UMP_SYSEX7 | UMP_MIDI2)
+            || words.len() != Self::word_count(first)
+        {
+            return None;
+        }
+        Some(Self {
+            words: [first, words.get(1).copied().unwrap_or(0)],
+        })
+    }
+
+    pub fn words(&self) -> &[u32] {
+        &self.words[..Self::word_count(self.words[0])]
+    }
+
+    pub fn message_type(&self) -> u8 {
+        (self.words[0] >> 28) as u8
+    }
+
+    pub fn group(&self) -> u8 {
+        (self.words[0] >> 24 & 0x0f) as u8
+    }
+
+    pub fn from_event(event: &Event, group: u8) -> Option<Self> {
+        let header = |kind: u8, status: u8| {
+            (kind as u32) << 28 | (group as u32 & 0x0f) << 24 | (status as u32) << 16
+        };
+        let ch = event.channel & 0x0f;
+        let midi2 = |opcode: u8, index: (u8, u8), data: u32| Self {
+            words: [
+                header(UMP_MIDI2, opcode | ch) | (index.0 as u32 & 0x7f) << 8 | index.1 as u32,
+                data,
+            ],
+        };
+
+        if let EventData::SysEx { chunk } = event.data {
+            let status = match chunk.status {
+                SysExStatus::Complete => 0,
+                SysExStatus::Start => 1,
+                SysExStatus::Continue => 2,
+                SysExStatus::End => 3,
+            };
+            let mut b = [0u32; 6];
+            for (b, &byte) in b.iter_mut().zip(chunk.bytes()) {
+                *b = byte as u32 & 0x7f;
+            }
+            let count = chunk.bytes().len() as u8;
+            return Some(Self {
+                words: [
+                    header(UMP_SYSEX7, status << 4 | count) | b[0] << 8 | b[1],
+                    b[2] << 24 | b[3] << 16 | b[4] << 8 | b[5],
+                ],
+            });
+        }
+
+        if let Some((bytes, _)) = event.to_midi1_bytes()
+            && event.data.to_midi1() == event.data
+        {
+            return Some(Self {
+                words: [
+                    header(UMP_MIDI1, bytes[0]) | (bytes[1] as u32) << 8 | bytes[2] as u32,
+                    0,
+                ],
+            });
+        }
+
+        Some(match event.data.to_high_res() {
+            EventData::NoteOn16 { note, velocity } => {
+                midi2(NOTE_ON, (note, 0), (velocity as u32) << 16)
+            }
+            EventData::NoteOff { note } => midi2(NOTE_OFF, (note, 0), 0),
+            EventData::NotePressure32 { note, pressure } => {
+                midi2(POLY_PRESSURE, (note, 0), pressure)
+            }
+            EventData::ControlChange32 { control, value } => {
+                midi2(CONTROL_CHANGE, (control, 0), value)
+            }
+            EventData::ProgramChange { program } => {
+                midi2(PROGRAM_CHANGE, (0, 0), (program as u32 & 0x7f) << 24)
+            }
+            EventData::ChannelPressure32 { pressure } => midi2(CHANNEL_PRESSURE, (0, 0), pressure),
+            EventData::PitchBend32 { value } => midi2(PITCH_BEND, (0, 0), value),
+            EventData::NotePitchBend32 { note, value } => {
+                midi2(PER_NOTE_PITCH_BEND, (note, 0), value)
+            }
+            // Controller numbers become registered per-note controllers.
+            EventData::NoteControlChange32 {
+                note,
+                control,
+                value,
+            } => midi2(REGISTERED_PER_NOTE, (note, control), value),
+            _ => return None,
+        })
+    }
+
+    // UMP carries no timing, it is up to the caller.
+    pub fn to_event(&self, timing: u32) -> Option<Event> {
+        let [w0, w1] = self.words;
+        let status = (w0 >> 16) as u8;
+        let b1 = (w0 >> 8) as u8 & 0x7f;
+        let b2 = w0 as u8;
+
+        match self.message_type() {
+            UMP_MIDI1 => Event::from_midi1_bytes(timing, &[status, b1, b2 & 0x7f]),
+            UMP_SYSEX7 => {
+                let status = match status >> 4 {
+                    0 => SysExStatus::Complete,
+                    1 => SysExStatus::Start,
+                    2 => SysExStatus::Continue,
+                    3 => SysExStatus::End,
+                    _ => return None,
+                };
+                let count = (w0 >> 16 & 0x0f) as usize;
+                let bytes = [
+                    b1,
+                    b2 & 0x7f,
+                    (w1 >> 24) as u8 & 0x7f,
+                    (w1 >> 16) as u8 & 0x7f,
+                    (w1 >> 8) as u8 & 0x7f,
+                    w1 as u8 & 0x7f,
+                ];
+                let chunk = SysExChunk::new(status, &bytes[..count.min(bytes.len())]);
+                Some(Event::new(timing, EventData::SysEx { chunk }))
+            }
+            UMP_MIDI2 => {
+                let data = match status & 0xf0 {
+                    NOTE_OFF => EventData::NoteOff { note: b1 },
+                    NOTE_ON => EventData::NoteOn16 {
+                        note: b1,
+                        velocity: (w1 >> 16) as u16,
+                    },
+                    POLY_PRESSURE => EventData::NotePressure32 {
+                        note: b1,
+                        pressure: w1,
+                    },
+                    CONTROL_CHANGE => EventData::ControlChange32 {
+                        control: b1,
+                        value: w1,
+                    },
+                    PROGRAM_CHANGE => EventData::ProgramChange {
+                        program: (w1 >> 24) as u8 & 0x7f,
+                    },
+                    CHANNEL_PRESSURE => EventData::ChannelPressure32 { pressure: w1 },
+                    PITCH_BEND => EventData::PitchBend32 { value: w1 },
+                    PER_NOTE_PITCH_BEND => EventData::NotePitchBend32 {
+                        note: b1,
+                        value: w1,
+                    },
+                    REGISTERED_PER_NOTE | ASSIGNABLE_PER_NOTE => EventData::NoteControlChange32 {
+                        note: b1,
+                        control: b2,
+                        value: w1,
+                    },
+                    _ => return None,
+                };
+                Some(Event::new(timing, data).with_channel(status & 0x0f))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use libm::exp2;
+
+use crate::{MIDI_NOTE_COUNT, SYSEX_NON_REALTIME, SYSEX_REALTIME, Tuning};
+
+const MTS_SUB_ID: u8 = 0x08;
+
+const BULK_DUMP: u8 = 0x01;
+const SINGLE_NOTE: u8 = 0x02;
+const BANK_DUMP: u8 = 0x04;
+const BANK_SINGLE_NOTE: u8 = 0x07;
+const OCTAVE_1_BYTE: u8 = 0x08;
+const OCTAVE_2_BYTE: u8 = 0x09;
+
+const NAME_BYTES: usize = 16;
+const FREQ_BYTES: usize = 3;
+const DUMP_BYTES: usize = MIDI_NOTE_COUNT * FREQ_BYTES;
+
+// MTS frequencies are relative to 12-TET at this pitch.
+const MTS_A4: f64 = 440.0;
+
+// A MIDI Tuning Standard message, borrowed from a SysEx payload. Device ids
+// aren't filtered, and tuning program and bank numbers are reported but not
+// used to pick between stored tunings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MtsMessage<'a> {
+    // Three byte frequencies for all 128 keys.
+    Dump {
+        bank: u8,
+        program: u8,
+        name: &'a [u8],
+        data: &'a [u8],
+    },
+    // Four bytes per key: the key and its frequency.
+    SingleNote {
+        bank: u8,
+        program: u8,
+        changes: &'a [u8],
+    },
+    // Cents away from 12-TET for each pitch class from C, on the channels
+    // in the mask.
+    Octave {
+        channels: u16,
+        cents: [f64; 12],
+    },
+}
+
+impl<'a> MtsMessage<'a> {
+    // `payload` is the SysEx message without F0 and F7. None for anything
+    // that isn't a well formed MTS message.
+    pub fn parse(payload: &'a [u8]) -> Option<Self> {
+        let [kind, _device, MTS_SUB_ID, sub_id, data @ ..] = payload else {
+            return None;
+        };
+        if *kind != SYSEX_NON_REALTIME && *kind != SYSEX_REALTIME {
+            return None;
+        }
+
+        match *sub_id {
+            BULK_DUMP | BANK_DUMP => {
+                let (bank, data) = match *sub_id {
+                    BANK_DUMP => data.split_first()?,
+                    _ => (&0, data),
+                };
+                let (&program, data) = data.split_first()?;
+                let (name, rest) = data.split_at_checked(NAME_BYTES)?;
+                let (dump, checksum) = rest.split_at_checked(DUMP_BYTES)?;
+
+                // The checksum covers everything before it, when sent.
+                let sum_len = payload.len() - checksum.len();
+                if let Some(&checksum) = checksum.first()
+                    && payload[..sum_len].iter().fold(0, |a, b| a ^ b) & 0x7f != checksum
+                {
+                    return None;
+                }
+                Some(Self::Dump {
+                    bank: *bank,
+                    program,
+                    name,
+                    data: dump,
+                })
+            }
+            SINGLE_NOTE | BANK_SINGLE_NOTE => {
+                let (bank, data) = match *sub_id {
+                    BANK_SINGLE_NOTE => data.split_first()?,
+                    _ => (&0, data),
+                };
+                let [program, count, changes @ ..] = data else {
+                    return None;
+                };
+                let len = (*count as usize * 4).min(changes.len() / 4 * 4);
+                Some(Self::SingleNote {
+                    bank: *bank,
+                    program: *program,
+                    changes: &changes[..len],
+                })
+            }
+            OCTAVE_1_BYTE | OCTAVE_2_BYTE => {
+                let [ff, gg, hh, offsets @ ..] = data else {
+                    return None;
+                };
+                let channels =
+                    (*hh as u16 & 0x7f) | (*gg as u16 & 0x7f) << 7 | (*ff as u16 & 0x03) << 14;
+
+                let mut cents = [0.0; 12];
+                if *sub_id == OCTAVE_1_BYTE {
+                    for (c, &v) in cents.iter_mut().zip(offsets.get(..12)?) {
+                        *c = v as f64 - 64.0;
+                    }
+                } else {
+                    for (c, v) in cents.iter_mut().zip(offsets.get(..24)?.chunks_exact(2)) {
+                        let v = (v[0] as u16) << 7 | v[1] as u16;
+                        *c = (v as f64 - 8192.0) * 100.0 / 8192.0;
+                    }
+                }
+                Some(Self::Octave { channels, cents })
+            }
+            _ => None,
+        }
+    }
+
+    // Retunes `tuning` in place. Octave messages only apply if their mask
+    // shares a channel with `channels`. Returns whether anything changed.
+    pub fn apply(&self, tuning: &mut Tuning, channels: u16) -> bool {
+        match *self {
+            Self::Dump { data, .. } => {
+                let mut changed = false;
+                for (note, freq) in data.chunks_exact(FREQ_BYTES).enumerate() {
+                    if let Some(freq) = mts_frequency(freq) {
+                        tuning.set_note_frequency(note as u8, freq);
+                        changed = true;
+                    }
+                }
+                changed
+            }
+            Self::SingleNote { changes, .. } => {
+                let mut changed = false;
+                for change in changes.chunks_exact(4) {
+                    if let Some(freq) = mts_frequency(&change[1..]) {
+                        tuning.set_note_frequency(change[0] & 0x7f, freq);
+                        changed = true;
+                    }
+                }
+                changed
+            }
+            Self::Octave {
+                channels: mask,
+                cents,
+            } => {
+                if mask & channels == 0 {
+                    return false;
+                }
+                for note in 0..MIDI_NOTE_COUNT {
+                    let semitones = note as f64 - 69.0 + cents[note % 12] / 100.0;
+                    tuning.set_note_frequency(note as u8, (MTS_A4 * exp2(semitones / 12.0)) as f32);
+                }
+                true
+            }
+        }
+    }
+}
+
+// A 12-TET key plus a 14 bit fraction of a semitone, 7F 7F 7F means no
+// change.
+fn mts_frequency(bytes: &[u8]) -> Option<f32> {
+    let &[key, msb, lsb] = bytes else {
+        return None;
+    };
+    if key == 0x7f && msb == 0x7f && lsb == 0x7f {
+        return None;
+    }
+    let fraction = ((msb as u16 & 0x7f) << 7 | lsb as u16 & 0x7f) as f64 / 16384.0;
+    let semitones = (key & 0x7f) as f64 + fraction - 69.0;
+    Some((MTS_A4 * exp2(semitones / 12.0)) as f32)
+}
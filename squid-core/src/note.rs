@@ -1,5 +1,41 @@
+use core::fmt;
+use core::str::FromStr;
+
 use crate::frequency::Frequency;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteError {
+    InvalidPitchClass(u8),
+    OutOfRange(i16),
+    InvalidName,
+    InvalidScale,
+    UnknownChord,
+}
+
+impl fmt::Display for NoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoteError::InvalidPitchClass(value) => {
+                write!(f, "Invalid u8 value for PitchClass: {}", value)
+            }
+            NoteError::OutOfRange(midi) => {
+                write!(f, "MIDI note {} is outside the valid range (0-127)", midi)
+            }
+            NoteError::InvalidName => write!(f, "Invalid note name"),
+            NoteError::InvalidScale => write!(f, "Invalid scale intervals"),
+            NoteError::UnknownChord => write!(f, "Unknown chord symbol"),
+        }
+    }
+}
+
+// How black keys are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Spelling {
+    #[default]
+    Sharp,
+    Flat,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[repr(u8)]
 pub enum PitchClass {
@@ -18,23 +54,129 @@ pub enum PitchClass {
 }
 
 impl PitchClass {
+    pub const ALL: [PitchClass; 12] = [
+        PitchClass::C,
+        PitchClass::CSharp,
+        PitchClass::D,
+        PitchClass::DSharp,
+        PitchClass::E,
+        PitchClass::F,
+        PitchClass::FSharp,
+        PitchClass::G,
+        PitchClass::GSharp,
+        PitchClass::A,
+        PitchClass::ASharp,
+        PitchClass::B,
+    ];
+
     pub fn from_u8(value: u8) -> Self {
-        match value {
-            0 => PitchClass::C,
-            1 => PitchClass::CSharp,
-            2 => PitchClass::D,
-            3 => PitchClass::DSharp,
-            4 => PitchClass::E,
-            5 => PitchClass::F,
-            6 => PitchClass::FSharp,
-            7 => PitchClass::G,
-            8 => PitchClass::GSharp,
-            9 => PitchClass::A,
-            10 => PitchClass::ASharp,
-            11 => PitchClass::B,
-            _ => panic!("Invalid u8 value for PitchClass: {}", value),
+        match Self::try_from_u8(value) {
+            Ok(pitch_class) => pitch_class,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn try_from_u8(value: u8) -> Result<Self, NoteError> {
+        Self::ALL
+            .get(value as usize)
+            .copied()
+            .ok_or(NoteError::InvalidPitchClass(value))
+    }
+
+    // Wraps any semitone offset into an octave.
+    pub fn from_semitones(semitones: i32) -> Self {
+        Self::ALL[semitones.rem_euclid(12) as usize]
+    }
+
+    pub fn transpose(self, semitones: i32) -> Self {
+        Self::from_semitones(self as i32 + semitones)
+    }
+
+    pub fn is_natural(self) -> bool {
+        matches!(
+            self,
+            PitchClass::C
+                | PitchClass::D
+                | PitchClass::E
+                | PitchClass::F
+                | PitchClass::G
+                | PitchClass::A
+                | PitchClass::B
+        )
+    }
+
+    pub fn name(self, spelling: Spelling) -> &'static str {
+        const SHARPS: [&str; 12] = [
+            "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+        ];
+        const FLATS: [&str; 12] = [
+            "C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B",
+        ];
+        match spelling {
+            Spelling::Sharp => SHARPS[self as usize],
+            Spelling::Flat => FLATS[self as usize],
+        }
+    }
+}
+
+impl TryFrom<u8> for PitchClass {
+    type Error = NoteError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::try_from_u8(value)
+    }
+}
+
+impl fmt::Display for PitchClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name(Spelling::Sharp))
+    }
+}
+
+impl FromStr for PitchClass {
+    type Err = NoteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_pitch(s)? {
+            (semitones, "") => Ok(Self::from_semitones(semitones)),
+            _ => Err(NoteError::InvalidName),
+        }
+    }
+}
+
+// Letter plus any number of accidentals, returned as semitones above the C of
+// the same octave (so "Cb" is -1) together with whatever follows.
+pub(crate) fn parse_pitch(s: &str) -> Result<(i32, &str), NoteError> {
+    let mut chars = s.trim().char_indices();
+    let letter = match chars.next() {
+        Some((_, c)) => c.to_ascii_uppercase(),
+        None => return Err(NoteError::InvalidName),
+    };
+    let mut semitones = match letter {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return Err(NoteError::InvalidName),
+    };
+
+    let rest = s.trim();
+    let mut end = rest.len();
+    for (i, c) in chars {
+        match c {
+            '#' | '♯' => semitones += 1,
+            'b' | '♭' => semitones -= 1,
+            'x' | '𝄪' => semitones += 2,
+            _ => {
+                end = i;
+                break;
+            }
         }
     }
+    Ok((semitones, &rest[end..]))
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -57,24 +199,39 @@ impl Note {
     }
 
     pub fn to_midi(&self) -> u8 {
+        match self.try_to_midi() {
+            Ok(midi) => midi,
+            Err(_) => panic!("Note {:?} is outside the valid MIDI range (0-127)", self),
+        }
+    }
+
+    pub fn try_to_midi(&self) -> Result<u8, NoteError> {
         let midi_val = (self.octave as i16 + 1) * 12 + self.pitch_class as i16;
-        if midi_val < 0 || midi_val > 127 {
-            panic!("Note {:?} is outside the valid MIDI range (0-127)", self);
+        if !(0..=127).contains(&midi_val) {
+            return Err(NoteError::OutOfRange(midi_val));
         }
-        midi_val as u8
+        Ok(midi_val as u8)
     }
 
     pub fn from_midi(midi_note: u8) -> Self {
-        if midi_note > 127 {
-            panic!("Invalid MIDI note number: {}", midi_note);
+        match Self::try_from_midi(midi_note) {
+            Ok(note) => note,
+            Err(_) => panic!("Invalid MIDI note number: {}", midi_note),
         }
-        let octave = (midi_note as i8 / 12) - 1;
-        let pitch_class_val = midi_note % 12;
-        let pitch_class = PitchClass::from_u8(pitch_class_val);
-        Self {
-            pitch_class,
-            octave,
+    }
+
+    pub fn try_from_midi(midi_note: u8) -> Result<Self, NoteError> {
+        Self::try_from_midi_i16(midi_note as i16)
+    }
+
+    pub(crate) fn try_from_midi_i16(midi_note: i16) -> Result<Self, NoteError> {
+        if !(0..=127).contains(&midi_note) {
+            return Err(NoteError::OutOfRange(midi_note));
         }
+        Ok(Self {
+            pitch_class: PitchClass::from_semitones(midi_note as i32),
+            octave: (midi_note / 12 - 1) as i8,
+        })
     }
 
     pub fn to_frequency(&self) -> Frequency {
@@ -82,8 +239,67 @@ impl Note {
     }
 
     pub fn transpose(&self, semitones: i8) -> Self {
-        let current_midi = self.to_midi() as i16;
-        let new_midi = current_midi + semitones as i16;
-        Note::from_midi(new_midi as u8)
+        match self.checked_transpose(semitones) {
+            Ok(note) => note,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    pub fn checked_transpose(&self, semitones: i8) -> Result<Self, NoteError> {
+        Self::try_from_midi_i16(self.try_to_midi()? as i16 + semitones as i16)
+    }
+
+    pub fn spelled(self, spelling: Spelling) -> SpelledNote {
+        SpelledNote {
+            note: self,
+            spelling,
+        }
+    }
+}
+
+impl TryFrom<u8> for Note {
+    type Error = NoteError;
+
+    fn try_from(midi_note: u8) -> Result<Self, Self::Error> {
+        Self::try_from_midi(midi_note)
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.spelled(Spelling::Sharp).fmt(f)
+    }
+}
+
+// "C#4", "Db4", "cb3" (same as B2), "A-1". Octave numbers follow scientific
+// pitch notation where MIDI note 60 is C4.
+impl FromStr for Note {
+    type Err = NoteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (semitones, octave) = parse_pitch(s)?;
+        let octave: i16 = octave.parse().map_err(|_| NoteError::InvalidName)?;
+        let midi = (octave + 1)
+            .checked_mul(12)
+            .and_then(|m| m.checked_add(semitones as i16))
+            .ok_or(NoteError::InvalidName)?;
+        Self::try_from_midi_i16(midi)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpelledNote {
+    pub note: Note,
+    pub spelling: Spelling,
+}
+
+impl fmt::Display for SpelledNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            self.note.pitch_class.name(self.spelling),
+            self.note.octave
+        )
     }
 }
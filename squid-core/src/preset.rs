@@ -0,0 +1,137 @@
+use crate::{EventData, FixedSpscQueue};
+
+const CC_BANK_SELECT: u8 = 0;
+const CC_BANK_SELECT_LSB: u8 = 32;
+
+pub const PRESET_QUEUE_LEN: usize = 16;
+
+// Where a preset is stored: the bank from CC0 and CC32 as one 14 bit number,
+// and the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProgramId {
+    pub bank: u16,
+    pub program: u8,
+}
+
+impl ProgramId {
+    pub const fn new(bank: u16, program: u8) -> Self {
+        Self {
+            bank: bank & 0x3fff,
+            program: program & 0x7f,
+        }
+    }
+}
+
+// Settings of a node that can be captured and restored as a whole. Presets
+// are plain values, so they can be prepared on another thread and copied in.
+pub trait Presets {
+    type Preset: Copy;
+
+    fn preset(&self) -> Self::Preset;
+    fn load_preset(&mut self, preset: &Self::Preset);
+}
+
+// Presets on their way from a control thread to the audio thread.
+pub type PresetQueue<P> = FixedSpscQueue<(ProgramId, P), PRESET_QUEUE_LEN>;
+
+// Up to `N` stored presets, in place so looking one up never allocates.
+#[derive(Clone, Copy)]
+pub struct PresetBank<P, const N: usize> {
+    programs: [Option<(ProgramId, P)>; N],
+}
+
+impl<P: Copy, const N: usize> PresetBank<P, N> {
+    pub fn new() -> Self {
+        Self {
+            programs: [None; N],
+        }
+    }
+
+    // Replaces what is stored as `id`, or takes a free slot. Returns false
+    // when the bank is full.
+    pub fn store(&mut self, id: ProgramId, preset: P) -> bool {
+        let slot = self
+            .programs
+            .iter()
+            .position(|p| p.is_some_and(|(i, _)| i == id))
+            .or_else(|| self.programs.iter().position(Option::is_none));
+        match slot {
+            Some(slot) => {
+                self.programs[slot] = Some((id, preset));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get(&self, id: ProgramId) -> Option<&P> {
+        self.programs
+            .iter()
+            .flatten()
+            .find(|(i, _)| *i == id)
+            .map(|(_, preset)| preset)
+    }
+
+    pub fn remove(&mut self, id: ProgramId) -> Option<P> {
+        let slot = self
+            .programs
+            .iter_mut()
+            .find(|p| p.is_some_and(|(i, _)| i == id))?;
+        slot.take().map(|(_, preset)| preset)
+    }
+
+    pub fn clear(&mut self) {
+        self.programs = [None; N];
+    }
+
+    pub fn programs(&self) -> impl Iterator<Item = (ProgramId, &P)> {
+        self.programs
+            .iter()
+            .flatten()
+            .map(|(id, preset)| (*id, preset))
+    }
+}
+
+impl<P: Copy, const N: usize> Default for PresetBank<P, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Follows bank select and turns program changes into ids. As in MIDI, a
+// bank select only takes effect with the next program change.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProgramSelect {
+    bank: u16,
+}
+
+impl ProgramSelect {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bank(&self) -> u16 {
+        self.bank
+    }
+
+    pub fn handle(&mut self, data: EventData) -> Option<ProgramId> {
+        match data.to_midi1() {
+            EventData::ControlChange {
+                control: CC_BANK_SELECT,
+                value,
+            } => {
+                self.bank = (value as u16 & 0x7f) << 7 | self.bank & 0x7f;
+                None
+            }
+            EventData::ControlChange {
+                control: CC_BANK_SELECT_LSB,
+                value,
+            } => {
+                self.bank = self.bank & !0x7f | value as u16 & 0x7f;
+                None
+            }
+            EventData::ProgramChange { program } => Some(ProgramId::new(self.bank, program)),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// System exclusive messages travel through events in fixed size chunks, the
+// way MIDI 2.0 packs them into SysEx7 packets. Payloads exclude the F0 and
+// F7 framing bytes and only hold 7 bit data.
+pub const SYSEX_CHUNK_BYTES: usize = 6;
+
+// Longest message `SysExAssembler` users in this crate keep, enough for an
+// MTS bulk tuning dump.
+pub const MAX_SYSEX_BYTES: usize = 512;
+
+pub const SYSEX_NON_REALTIME: u8 = 0x7e;
+pub const SYSEX_REALTIME: u8 = 0x7f;
+pub const SYSEX_ALL_CALL: u8 = 0x7f;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SysExStatus {
+    // The whole message fits in one chunk.
+    Complete,
+    Start,
+    Continue,
+    End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SysExChunk {
+    pub status: SysExStatus,
+    len: u8,
+    data: [u8; SYSEX_CHUNK_BYTES],
+}
+
+impl SysExChunk {
+    // Bytes past `SYSEX_CHUNK_BYTES` are dropped.
+    pub fn new(status: SysExStatus, bytes: &[u8]) -> Self {
+        let len = bytes.len().min(SYSEX_CHUNK_BYTES);
+        let mut data = [0; SYSEX_CHUNK_BYTES];
+        data[..len].copy_from_slice(&bytes[..len]);
+        Self {
+            status,
+            len: len as u8,
+            data,
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+// Splits a payload into the chunks to send it as.
+pub fn sysex_chunks(payload: &[u8]) -> impl Iterator<Item = SysExChunk> + '_ {
+    let count = payload.len().div_ceil(SYSEX_CHUNK_BYTES).max(1);
+    (0..count).map(move |i| {
+        let status = match (i, count) {
+            (_, 1) => SysExStatus::Complete,
+            (0, _) => SysExStatus::Start,
+            (i, count) if i == count - 1 => SysExStatus::End,
+            _ => SysExStatus::Continue,
+        };
+        let start = i * SYSEX_CHUNK_BYTES;
+        let end = (start + SYSEX_CHUNK_BYTES).min(payload.len());
+        SysExChunk::new(status, &payload[start..end])
+    })
+}
+
+// Joins chunks back into messages of up to `CAPACITY` bytes. Longer
+// messages, and ones whose chunks arrive out of order, are dropped whole.
+#[derive(Clone, Copy)]
+pub struct SysExAssembler<const CAPACITY: usize> {
+    data: [u8; CAPACITY],
+    len: usize,
+    receiving: bool,
+}
+
+impl<const CAPACITY: usize> SysExAssembler<CAPACITY> {
+    pub const fn new() -> Self {
+        Self {
+            data: [0; CAPACITY],
+            len: 0,
+            receiving: false,
+        }
+    }
+
+    // Returns the payload once the last chunk of a message is in.
+    pub fn push(&mut self, chunk: &SysExChunk) -> Option<&[u8]> {
+        match chunk.status {
+            SysExStatus::Complete | SysExStatus::Start => {
+                self.len = 0;
+                self.receiving = true;
+            }
+            SysExStatus::Continue | SysExStatus::End if !self.receiving => return None,
+            _ => {}
+        }
+
+        let bytes = chunk.bytes();
+        if self.len + bytes.len() > CAPACITY {
+            self.reset();
+            return None;
+        }
+        self.data[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+
+        match chunk.status {
+            SysExStatus::Complete | SysExStatus::End => {
+                self.receiving = false;
+                Some(&self.data[..self.len])
+            }
+            _ => None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.receiving = false;
+    }
+}
+
+impl<const CAPACITY: usize> Default for SysExAssembler<CAPACITY> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,7 +2,7 @@ use core::cell::UnsafeCell;
 use core::cmp;
 use core::mem::MaybeUninit;
 use core::ptr;
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 
 pub struct FixedSpscQueue<T, const N: usize> {
     buffer: [UnsafeCell<MaybeUninit<T>>; N],
@@ -172,3 +172,42 @@ impl<T, const N: usize> FixedSpscQueue<T, N> {
         self.tail.store(head, Ordering::Release);
     }
 }
+
+// f32 shared between threads, stored as its bit pattern.
+pub struct AtomicF32(AtomicU32);
+
+impl AtomicF32 {
+    pub const fn new(value: f32) -> Self {
+        Self(AtomicU32::new(value.to_bits()))
+    }
+
+    #[inline]
+    pub fn load(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    #[inline]
+    pub fn store(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn swap(&self, value: f32) -> f32 {
+        f32::from_bits(self.0.swap(value.to_bits(), Ordering::Relaxed))
+    }
+
+    // Only valid for values >= 0, whose bit patterns sort like the numbers.
+    #[inline]
+    pub fn fetch_max_positive(&self, value: f32) -> f32 {
+        f32::from_bits(
+            self.0
+                .fetch_max(value.max(0.0).to_bits(), Ordering::Relaxed),
+        )
+    }
+}
+
+impl Default for AtomicF32 {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
@@ -0,0 +1,291 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::note::{Note, NoteError, PitchClass, Spelling, parse_pitch};
+
+pub const MAX_CHORD_NOTES: usize = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Sus2,
+    Sus4,
+    Major6,
+    Minor6,
+    Dominant7,
+    Major7,
+    Minor7,
+    MinorMajor7,
+    HalfDiminished7,
+    Diminished7,
+    Add9,
+    Dominant9,
+    Major9,
+    Minor9,
+    Dominant11,
+    Minor11,
+    Dominant13,
+    Major13,
+    Minor13,
+}
+
+impl ChordQuality {
+    pub const ALL: [ChordQuality; 23] = [
+        ChordQuality::Major,
+        ChordQuality::Minor,
+        ChordQuality::Diminished,
+        ChordQuality::Augmented,
+        ChordQuality::Sus2,
+        ChordQuality::Sus4,
+        ChordQuality::Major6,
+        ChordQuality::Minor6,
+        ChordQuality::Dominant7,
+        ChordQuality::Major7,
+        ChordQuality::Minor7,
+        ChordQuality::MinorMajor7,
+        ChordQuality::HalfDiminished7,
+        ChordQuality::Diminished7,
+        ChordQuality::Add9,
+        ChordQuality::Dominant9,
+        ChordQuality::Major9,
+        ChordQuality::Minor9,
+        ChordQuality::Dominant11,
+        ChordQuality::Minor11,
+        ChordQuality::Dominant13,
+        ChordQuality::Major13,
+        ChordQuality::Minor13,
+    ];
+
+    // Semitones above the root, lowest first.
+    pub const fn intervals(self) -> &'static [u8] {
+        match self {
+            ChordQuality::Major => &[0, 4, 7],
+            ChordQuality::Minor => &[0, 3, 7],
+            ChordQuality::Diminished => &[0, 3, 6],
+            ChordQuality::Augmented => &[0, 4, 8],
+            ChordQuality::Sus2 => &[0, 2, 7],
+            ChordQuality::Sus4 => &[0, 5, 7],
+            ChordQuality::Major6 => &[0, 4, 7, 9],
+            ChordQuality::Minor6 => &[0, 3, 7, 9],
+            ChordQuality::Dominant7 => &[0, 4, 7, 10],
+            ChordQuality::Major7 => &[0, 4, 7, 11],
+            ChordQuality::Minor7 => &[0, 3, 7, 10],
+            ChordQuality::MinorMajor7 => &[0, 3, 7, 11],
+            ChordQuality::HalfDiminished7 => &[0, 3, 6, 10],
+            ChordQuality::Diminished7 => &[0, 3, 6, 9],
+            ChordQuality::Add9 => &[0, 4, 7, 14],
+            ChordQuality::Dominant9 => &[0, 4, 7, 10, 14],
+            ChordQuality::Major9 => &[0, 4, 7, 11, 14],
+            ChordQuality::Minor9 => &[0, 3, 7, 10, 14],
+            ChordQuality::Dominant11 => &[0, 4, 7, 10, 14, 17],
+            ChordQuality::Minor11 => &[0, 3, 7, 10, 14, 17],
+            ChordQuality::Dominant13 => &[0, 4, 7, 10, 14, 17, 21],
+            ChordQuality::Major13 => &[0, 4, 7, 11, 14, 17, 21],
+            ChordQuality::Minor13 => &[0, 3, 7, 10, 14, 17, 21],
+        }
+    }
+
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            ChordQuality::Major => "",
+            ChordQuality::Minor => "m",
+            ChordQuality::Diminished => "dim",
+            ChordQuality::Augmented => "aug",
+            ChordQuality::Sus2 => "sus2",
+            ChordQuality::Sus4 => "sus4",
+            ChordQuality::Major6 => "6",
+            ChordQuality::Minor6 => "m6",
+            ChordQuality::Dominant7 => "7",
+            ChordQuality::Major7 => "maj7",
+            ChordQuality::Minor7 => "m7",
+            ChordQuality::MinorMajor7 => "mmaj7",
+            ChordQuality::HalfDiminished7 => "m7b5",
+            ChordQuality::Diminished7 => "dim7",
+            ChordQuality::Add9 => "add9",
+            ChordQuality::Dominant9 => "9",
+            ChordQuality::Major9 => "maj9",
+            ChordQuality::Minor9 => "m9",
+            ChordQuality::Dominant11 => "11",
+            ChordQuality::Minor11 => "m11",
+            ChordQuality::Dominant13 => "13",
+            ChordQuality::Major13 => "maj13",
+            ChordQuality::Minor13 => "m13",
+        }
+    }
+}
+
+impl FromStr for ChordQuality {
+    type Err = NoteError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let alias = match s {
+            "M" | "maj" => "",
+            "min" | "-" => "m",
+            "o" | "°" => "dim",
+            "+" => "aug",
+            "sus" => "sus4",
+            "M7" | "Δ" | "Δ7" => "maj7",
+            "min7" | "-7" => "m7",
+            "ø" | "ø7" => "m7b5",
+            "o7" | "°7" => "dim7",
+            "mM7" | "m(maj7)" => "mmaj7",
+            other => other,
+        };
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|q| q.symbol() == alias)
+            .ok_or(NoteError::UnknownChord)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Voicing {
+    #[default]
+    Close,
+    // Second note from the top moved down an octave.
+    Drop2,
+    // Third note from the top moved down an octave.
+    Drop3,
+    // Every other note above the bass moved up an octave.
+    Spread,
+}
+
+// Concrete notes of a chord as MIDI numbers, lowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    root: PitchClass,
+    quality: ChordQuality,
+    notes: [u8; MAX_CHORD_NOTES],
+    len: usize,
+}
+
+impl Chord {
+    pub fn new(root: Note, quality: ChordQuality) -> Result<Self, NoteError> {
+        let base = root.try_to_midi()?;
+        let mut chord = Self {
+            root: root.pitch_class,
+            quality,
+            notes: [0; MAX_CHORD_NOTES],
+            len: quality.intervals().len(),
+        };
+        for (out, &i) in chord.notes.iter_mut().zip(quality.intervals()) {
+            *out = Note::try_from_midi_i16(base as i16 + i as i16)?.to_midi();
+        }
+        Ok(chord)
+    }
+
+    // Chord symbol such as "Cmaj7" or "F#m9", rooted in `octave`. As with
+    // notes, accidentals can cross into the next octave, "Cb" in octave 4 is
+    // B3.
+    pub fn parse(symbol: &str, octave: i8) -> Result<Self, NoteError> {
+        let (semitones, suffix) = parse_pitch(symbol)?;
+        let root = Note::try_from_midi_i16((octave as i16 + 1) * 12 + semitones as i16)?;
+        Self::new(root, suffix.parse()?)
+    }
+
+    pub fn root(&self) -> PitchClass {
+        self.root
+    }
+
+    pub fn quality(&self) -> ChordQuality {
+        self.quality
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn midi_notes(&self) -> &[u8] {
+        &self.notes[..self.len]
+    }
+
+    pub fn notes(&self) -> impl Iterator<Item = Note> + '_ {
+        self.midi_notes().iter().map(|&n| Note::from_midi(n))
+    }
+
+    // Moves the lowest note up an octave `count` times.
+    pub fn inversion(mut self, count: usize) -> Result<Self, NoteError> {
+        for _ in 0..count {
+            let lowest = Note::try_from_midi_i16(self.notes[0] as i16 + 12)?.to_midi();
+            self.notes.copy_within(1..self.len, 0);
+            self.notes[self.len - 1] = lowest;
+            self.sort();
+        }
+        Ok(self)
+    }
+
+    pub fn voicing(mut self, voicing: Voicing) -> Result<Self, NoteError> {
+        let len = self.len;
+        match voicing {
+            Voicing::Close => {}
+            Voicing::Drop2 if len >= 2 => self.shift(len - 2, -12)?,
+            Voicing::Drop3 if len >= 3 => self.shift(len - 3, -12)?,
+            Voicing::Spread => {
+                for i in (1..len).step_by(2) {
+                    self.shift(i, 12)?;
+                }
+            }
+            _ => {}
+        }
+        self.sort();
+        Ok(self)
+    }
+
+    pub fn transpose(mut self, semitones: i8) -> Result<Self, NoteError> {
+        for i in 0..self.len {
+            self.shift(i, semitones as i16)?;
+        }
+        self.root = self.root.transpose(semitones as i32);
+        Ok(self)
+    }
+
+    fn shift(&mut self, index: usize, semitones: i16) -> Result<(), NoteError> {
+        let note = Note::try_from_midi_i16(self.notes[index] as i16 + semitones)?;
+        self.notes[index] = note.to_midi();
+        Ok(())
+    }
+
+    fn sort(&mut self) {
+        self.notes[..self.len].sort_unstable();
+    }
+
+    pub fn spelled(&self, spelling: Spelling) -> SpelledChord {
+        SpelledChord {
+            root: self.root,
+            quality: self.quality,
+            spelling,
+        }
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.spelled(Spelling::Sharp).fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpelledChord {
+    pub root: PitchClass,
+    pub quality: ChordQuality,
+    pub spelling: Spelling,
+}
+
+impl fmt::Display for SpelledChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            self.root.name(self.spelling),
+            self.quality.symbol()
+        )
+    }
+}
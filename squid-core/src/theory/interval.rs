@@ -0,0 +1,106 @@
+use core::fmt;
+use core::ops::{Add, Neg, Sub};
+
+use crate::note::{Note, NoteError};
+
+// Distance in semitones. Negative intervals point down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Interval(pub i8);
+
+impl Interval {
+    pub const UNISON: Interval = Interval(0);
+    pub const MINOR_SECOND: Interval = Interval(1);
+    pub const MAJOR_SECOND: Interval = Interval(2);
+    pub const MINOR_THIRD: Interval = Interval(3);
+    pub const MAJOR_THIRD: Interval = Interval(4);
+    pub const PERFECT_FOURTH: Interval = Interval(5);
+    pub const TRITONE: Interval = Interval(6);
+    pub const PERFECT_FIFTH: Interval = Interval(7);
+    pub const MINOR_SIXTH: Interval = Interval(8);
+    pub const MAJOR_SIXTH: Interval = Interval(9);
+    pub const MINOR_SEVENTH: Interval = Interval(10);
+    pub const MAJOR_SEVENTH: Interval = Interval(11);
+    pub const OCTAVE: Interval = Interval(12);
+    pub const MINOR_NINTH: Interval = Interval(13);
+    pub const MAJOR_NINTH: Interval = Interval(14);
+    pub const PERFECT_ELEVENTH: Interval = Interval(17);
+    pub const MAJOR_THIRTEENTH: Interval = Interval(21);
+
+    pub const fn semitones(self) -> i8 {
+        self.0
+    }
+
+    pub fn between(from: Note, to: Note) -> Result<Self, NoteError> {
+        Ok(Interval(
+            to.try_to_midi()? as i8 - from.try_to_midi()? as i8,
+        ))
+    }
+
+    // Folded into a single octave, always upwards.
+    pub fn simple(self) -> Self {
+        Interval(self.0.rem_euclid(12))
+    }
+
+    pub fn octaves(self) -> i8 {
+        self.0.div_euclid(12)
+    }
+
+    pub fn inverted(self) -> Self {
+        Interval((12 - self.simple().0) % 12)
+    }
+
+    pub fn ratio(self) -> f32 {
+        libm::exp2f(self.0 as f32 / 12.0)
+    }
+
+    // Short name of the simple interval, e.g. "m3" or "P5".
+    pub fn name(self) -> &'static str {
+        const NAMES: [&str; 12] = [
+            "P1", "m2", "M2", "m3", "M3", "P4", "TT", "P5", "m6", "M6", "m7", "M7",
+        ];
+        NAMES[self.simple().0 as usize]
+    }
+
+    pub fn apply(self, note: Note) -> Result<Note, NoteError> {
+        note.checked_transpose(self.0)
+    }
+}
+
+impl Add for Interval {
+    type Output = Interval;
+
+    fn add(self, rhs: Interval) -> Interval {
+        Interval(self.0 + rhs.0)
+    }
+}
+
+impl Sub for Interval {
+    type Output = Interval;
+
+    fn sub(self, rhs: Interval) -> Interval {
+        Interval(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Interval {
+    type Output = Interval;
+
+    fn neg(self) -> Interval {
+        Interval(-self.0)
+    }
+}
+
+// Compound intervals keep the octave count, "P5+1" is a twelfth.
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 < 0 {
+            f.write_str("-")?;
+        }
+        let up = Interval(self.0.abs());
+        f.write_str(up.name())?;
+        match up.octaves() {
+            0 => Ok(()),
+            octaves => write!(f, "+{}", octaves),
+        }
+    }
+}
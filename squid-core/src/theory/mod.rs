@@ -0,0 +1,3 @@
+pub mod chord;
+pub mod interval;
+pub mod scale;
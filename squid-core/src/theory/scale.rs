@@ -0,0 +1,143 @@
+use crate::note::{Note, NoteError, PitchClass};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScaleKind {
+    Major,
+    NaturalMinor,
+    HarmonicMinor,
+    MelodicMinor,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+    MajorPentatonic,
+    MinorPentatonic,
+    Blues,
+    WholeTone,
+    Chromatic,
+}
+
+impl ScaleKind {
+    pub const fn intervals(self) -> &'static [u8] {
+        match self {
+            ScaleKind::Major => &[0, 2, 4, 5, 7, 9, 11],
+            ScaleKind::NaturalMinor => &[0, 2, 3, 5, 7, 8, 10],
+            ScaleKind::HarmonicMinor => &[0, 2, 3, 5, 7, 8, 11],
+            ScaleKind::MelodicMinor => &[0, 2, 3, 5, 7, 9, 11],
+            ScaleKind::Dorian => &[0, 2, 3, 5, 7, 9, 10],
+            ScaleKind::Phrygian => &[0, 1, 3, 5, 7, 8, 10],
+            ScaleKind::Lydian => &[0, 2, 4, 6, 7, 9, 11],
+            ScaleKind::Mixolydian => &[0, 2, 4, 5, 7, 9, 10],
+            ScaleKind::Locrian => &[0, 1, 3, 5, 6, 8, 10],
+            ScaleKind::MajorPentatonic => &[0, 2, 4, 7, 9],
+            ScaleKind::MinorPentatonic => &[0, 3, 5, 7, 10],
+            ScaleKind::Blues => &[0, 3, 5, 6, 7, 10],
+            ScaleKind::WholeTone => &[0, 2, 4, 6, 8, 10],
+            ScaleKind::Chromatic => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        }
+    }
+}
+
+// Up to twelve pitch classes above a root, repeating every octave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Scale {
+    root: PitchClass,
+    intervals: [u8; 12],
+    len: usize,
+}
+
+impl Scale {
+    pub fn new(root: PitchClass, kind: ScaleKind) -> Self {
+        Self::custom(root, kind.intervals()).unwrap()
+    }
+
+    // Semitones above the root, starting at 0 and strictly rising below 12.
+    pub fn custom(root: PitchClass, intervals: &[u8]) -> Result<Self, NoteError> {
+        let valid = intervals.first() == Some(&0)
+            && intervals.windows(2).all(|w| w[0] < w[1])
+            && intervals.last().is_some_and(|&i| i < 12);
+        if !valid {
+            return Err(NoteError::InvalidScale);
+        }
+
+        let mut scale = Self {
+            root,
+            intervals: [0; 12],
+            len: intervals.len(),
+        };
+        scale.intervals[..intervals.len()].copy_from_slice(intervals);
+        Ok(scale)
+    }
+
+    pub fn root(&self) -> PitchClass {
+        self.root
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn intervals(&self) -> &[u8] {
+        &self.intervals[..self.len]
+    }
+
+    pub fn pitch_classes(&self) -> impl Iterator<Item = PitchClass> + '_ {
+        self.intervals()
+            .iter()
+            .map(|&i| self.root.transpose(i as i32))
+    }
+
+    pub fn contains(&self, pitch_class: PitchClass) -> bool {
+        let offset = (pitch_class as i32 - self.root as i32).rem_euclid(12) as u8;
+        self.intervals().contains(&offset)
+    }
+
+    // Same notes starting from another degree, e.g. mode 1 of C major is
+    // D dorian.
+    pub fn mode(&self, degree: usize) -> Self {
+        let degree = degree % self.len;
+        let shift = self.intervals[degree];
+        let mut mode = Self {
+            root: self.root.transpose(shift as i32),
+            intervals: [0; 12],
+            len: self.len,
+        };
+        for (i, out) in mode.intervals[..self.len].iter_mut().enumerate() {
+            *out = (self.intervals[(degree + i) % self.len] + 12 - shift) % 12;
+        }
+        mode
+    }
+
+    // Degree 0 is the root in `octave`, negative degrees go down.
+    pub fn note(&self, degree: i32, octave: i8) -> Result<Note, NoteError> {
+        let len = self.len as i32;
+        let semitones =
+            degree.div_euclid(len) * 12 + self.intervals[degree.rem_euclid(len) as usize] as i32;
+        let root = (octave as i32 + 1) * 12 + self.root as i32;
+        let midi = root + semitones;
+        let midi = i16::try_from(midi).map_err(|_| NoteError::OutOfRange(i16::MAX))?;
+        Note::try_from_midi_i16(midi)
+    }
+
+    // Closest note of the scale, ties resolve downwards.
+    pub fn quantize(&self, note: Note) -> Result<Note, NoteError> {
+        let midi = note.try_to_midi()? as i16;
+        let offset = (midi - self.root as i16).rem_euclid(12);
+        let distance = |i: &u8| {
+            let d = (*i as i16 - offset).rem_euclid(12);
+            if d > 6 { d - 12 } else { d }
+        };
+        let shift = self
+            .intervals()
+            .iter()
+            .map(distance)
+            .min_by_key(|d| (d.abs(), *d))
+            .unwrap_or(0);
+        Note::try_from_midi_i16(midi + shift)
+    }
+}
@@ -1,41 +1,273 @@
-use libm::floor;
+use core::fmt;
 
-#[derive(Debug, Clone, Copy)]
+use libm::{exp, floor, log, round};
+
+// Ticks per quarter note in tick positions and song positions.
+pub const PPQ: u32 = 960;
+pub const MAX_TEMPO_POINTS: usize = 64;
+pub const MAX_SIGNATURE_CHANGES: usize = 32;
+pub const MIN_BPM: f32 = 1.0;
+pub const MAX_BPM: f32 = 1000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimingError {
+    EmptyMap,
+    TooManyPoints,
+    // The first entry doesn't start at beat 0 / bar 0, or entries aren't
+    // strictly rising.
+    InvalidOrder,
+    InvalidTempo,
+    InvalidSignature,
+    InvalidLoop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimeSignature {
+    pub numerator: u8,
+    pub denominator: u8,
+}
+
+impl TimeSignature {
+    pub const COMMON: TimeSignature = TimeSignature {
+        numerator: 4,
+        denominator: 4,
+    };
+
+    // The denominator must be a power of two up to 64.
+    pub fn new(numerator: u8, denominator: u8) -> Result<Self, TimingError> {
+        if numerator == 0 || !denominator.is_power_of_two() || denominator > 64 {
+            return Err(TimingError::InvalidSignature);
+        }
+        Ok(Self {
+            numerator,
+            denominator,
+        })
+    }
+
+    // Length of one counted beat in quarter notes, 0.5 for x/8.
+    pub fn beat_length(&self) -> f64 {
+        4.0 / self.denominator as f64
+    }
+
+    // Length of a bar in quarter notes.
+    pub fn beats_per_bar(&self) -> f64 {
+        self.numerator as f64 * self.beat_length()
+    }
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self::COMMON
+    }
+}
+
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+// Tempo from `beat` on. With `ramp` set the tempo glides linearly, per beat,
+// to the next point's tempo instead of jumping there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempoPoint {
+    pub beat: f64,
+    pub bpm: f32,
+    pub ramp: bool,
+}
+
+impl TempoPoint {
+    pub const fn new(beat: f64, bpm: f32) -> Self {
+        Self {
+            beat,
+            bpm,
+            ramp: false,
+        }
+    }
+
+    pub const fn ramp(beat: f64, bpm: f32) -> Self {
+        Self {
+            beat,
+            bpm,
+            ramp: true,
+        }
+    }
+}
+
+// Signature in effect from the start of `bar`, counted from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureChange {
+    pub bar: u32,
+    pub signature: TimeSignature,
+}
+
+// Bars and beats count from 1, ticks from 0. Beats are in units of the time
+// signature denominator and ticks use `PPQ` per quarter note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SongPosition {
+    pub bar: u32,
+    pub beat: u32,
+    pub tick: u32,
+}
+
+impl fmt::Display for SongPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.bar, self.beat, self.tick)
+    }
+}
+
+// Beats are quarter notes, bars are counted from 0 and may be fractional.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct TransportPosition {
     pub current_sample: u64,
     pub beat: f64,
     pub bar: f64,
     pub phase_in_beat: f64,
     pub phase_in_bar: f64,
+    pub ticks: u64,
+    pub song_position: SongPosition,
+    pub bpm: f32,
+    pub time_signature: TimeSignature,
+    pub is_playing: bool,
+}
+
+// What a node sees of the transport for one block: the state at the first
+// sample plus enough to place beats inside the block. Tempo changes within the
+// block are not reflected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeInfo {
+    pub block_start: u64,
+    pub is_playing: bool,
+    pub bpm: f64,
+    pub samples_per_beat: f64,
+    pub time_signature: TimeSignature,
+    pub beat: f64,
+    pub bar: f64,
+    pub bar_start_beat: f64,
+    pub song_position: SongPosition,
+    // Loop region in beats, only set while looping.
+    pub loop_region: Option<(f64, f64)>,
+}
+
+impl TimeInfo {
+    // A stopped transport at the start of the song.
+    pub fn stopped(sample_rate: f32, bpm: f64) -> Self {
+        Self {
+            block_start: 0,
+            is_playing: false,
+            bpm,
+            samples_per_beat: 60.0 / bpm * sample_rate as f64,
+            time_signature: TimeSignature::COMMON,
+            beat: 0.0,
+            bar: 0.0,
+            bar_start_beat: 0.0,
+            song_position: SongPosition {
+                bar: 1,
+                beat: 1,
+                tick: 0,
+            },
+            loop_region: None,
+        }
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.loop_region.is_some()
+    }
+
+    // Beat `offset` samples into the block, wrapped like the transport does
+    // when the block crosses the loop end.
+    pub fn beat_at(&self, offset: f64) -> f64 {
+        let beat = self.beat + offset / self.samples_per_beat;
+        match self.loop_region {
+            Some((start, end)) if self.beat < end && beat >= end => {
+                start + (beat - end) % (end - start)
+            }
+            _ => beat,
+        }
+    }
+
+    // Samples from the block start to `beat`, negative if it has passed.
+    pub fn offset_of(&self, beat: f64) -> f64 {
+        (beat - self.beat) * self.samples_per_beat
+    }
+
+    // First bar line strictly after `beat`, assuming the signature holds.
+    pub fn next_bar_beat(&self, beat: f64) -> f64 {
+        let length = self.time_signature.beats_per_bar();
+        let bars = floor((beat - self.bar_start_beat) / length + 1e-9);
+        self.bar_start_beat + (bars + 1.0) * length
+    }
+}
+
+impl Default for TimeInfo {
+    fn default() -> Self {
+        Self::stopped(44100.0, 120.0)
+    }
 }
 
 pub struct Transport {
     sample_rate: f32,
-    bpm: f32,
     is_playing: bool,
     current_sample: u64,
-    samples_per_beat: f64,
-    beats_per_bar: f64,
+
+    tempo: [TempoPoint; MAX_TEMPO_POINTS],
+    // Seconds from the start to each tempo point.
+    tempo_seconds: [f64; MAX_TEMPO_POINTS],
+    tempo_len: usize,
+
+    signatures: [SignatureChange; MAX_SIGNATURE_CHANGES],
+    // Beat each signature change starts on.
+    signature_beats: [f64; MAX_SIGNATURE_CHANGES],
+    signatures_len: usize,
+
+    loop_region: Option<(f64, f64)>,
+    looping: bool,
 }
 
 impl Transport {
     pub fn new(sample_rate: f32, bpm: f32) -> Self {
         let mut transport = Self {
             sample_rate,
-            bpm: 0.0,
             is_playing: false,
             current_sample: 0,
-            samples_per_beat: 0.0,
-            beats_per_bar: 4.0,
+
+            tempo: [TempoPoint::new(0.0, 120.0); MAX_TEMPO_POINTS],
+            tempo_seconds: [0.0; MAX_TEMPO_POINTS],
+            tempo_len: 1,
+
+            signatures: [SignatureChange {
+                bar: 0,
+                signature: TimeSignature::COMMON,
+            }; MAX_SIGNATURE_CHANGES],
+            signature_beats: [0.0; MAX_SIGNATURE_CHANGES],
+            signatures_len: 1,
+
+            loop_region: None,
+            looping: false,
         };
         transport.set_bpm(bpm);
         transport
     }
 
     pub fn tick(&mut self) {
-        if self.is_playing {
-            self.current_sample += 1;
+        self.advance(1);
+    }
+
+    // Moves the playhead on by a block, wrapping around the loop region when
+    // the block crosses its end.
+    pub fn advance(&mut self, frames: usize) {
+        if !self.is_playing {
+            return;
         }
+
+        let mut next = self.current_sample + frames as u64;
+        if let Some((start, end)) = self.loop_samples()
+            && self.current_sample < end
+            && next >= end
+        {
+            next = start + (next - end) % (end - start);
+        }
+        self.current_sample = next;
     }
 
     pub fn play(&mut self) {
@@ -50,43 +282,304 @@ impl Transport {
         self.current_sample = 0;
     }
 
+    pub fn seek(&mut self, sample: u64) {
+        self.current_sample = sample;
+    }
+
+    pub fn seek_to_beat(&mut self, beat: f64) {
+        self.current_sample = round(self.sample_at_beat(beat)) as u64;
+    }
+
+    pub fn seek_to_bar(&mut self, bar: u32) {
+        self.seek_to_beat(self.beat_at_bar(bar));
+    }
+
+    // Replaces the tempo map with a single tempo, clamped to `MIN_BPM` to
+    // `MAX_BPM`. Tempos that aren't finite are ignored. The playhead keeps
+    // its musical position.
     pub fn set_bpm(&mut self, new_bpm: f32) {
-        self.bpm = new_bpm.max(1.0);
-        let seconds_per_beat = 60.0 / self.bpm as f64;
-        self.samples_per_beat = seconds_per_beat * self.sample_rate as f64;
+        if new_bpm.is_finite() {
+            let point = TempoPoint::new(0.0, new_bpm.clamp(MIN_BPM, MAX_BPM));
+            let _ = self.set_tempo_map(&[point]);
+        }
     }
 
+    // The first point must sit on beat 0 and tempos within `MIN_BPM` to
+    // `MAX_BPM`. The playhead keeps its musical position.
+    pub fn set_tempo_map(&mut self, points: &[TempoPoint]) -> Result<(), TimingError> {
+        if points.is_empty() {
+            return Err(TimingError::EmptyMap);
+        }
+        if points.len() > MAX_TEMPO_POINTS {
+            return Err(TimingError::TooManyPoints);
+        }
+        if points[0].beat != 0.0 || points.windows(2).any(|w| w[0].beat >= w[1].beat) {
+            return Err(TimingError::InvalidOrder);
+        }
+        if points.iter().any(|p| !(MIN_BPM..=MAX_BPM).contains(&p.bpm)) {
+            return Err(TimingError::InvalidTempo);
+        }
+
+        let beat = self.beat_at_sample(self.current_sample as f64);
+
+        self.tempo[..points.len()].copy_from_slice(points);
+        self.tempo_len = points.len();
+        for i in 1..self.tempo_len {
+            let span = self.tempo[i].beat - self.tempo[i - 1].beat;
+            self.tempo_seconds[i] = self.tempo_seconds[i - 1] + self.segment_seconds(i - 1, span);
+        }
+
+        self.seek_to_beat(beat);
+        Ok(())
+    }
+
+    pub fn tempo_map(&self) -> &[TempoPoint] {
+        &self.tempo[..self.tempo_len]
+    }
+
+    // Tempo at the playhead.
     pub fn bpm(&self) -> f32 {
-        self.bpm
+        self.bpm_at_beat(self.beat_at_sample(self.current_sample as f64)) as f32
+    }
+
+    pub fn bpm_at_beat(&self, beat: f64) -> f64 {
+        let i = self.tempo_segment(beat);
+        let point = self.tempo[i];
+        match self.ramp_slope(i) {
+            Some(slope) => point.bpm as f64 + slope * (beat.max(0.0) - point.beat),
+            None => point.bpm as f64,
+        }
+    }
+
+    pub fn set_time_signature(&mut self, signature: TimeSignature) -> Result<(), TimingError> {
+        let change = SignatureChange { bar: 0, signature };
+        self.set_signature_map(&[change])
+    }
+
+    // The first change must be on bar 0. Bars after a change keep their
+    // numbers, so the playhead keeps its sample position rather than its
+    // beat.
+    pub fn set_signature_map(&mut self, changes: &[SignatureChange]) -> Result<(), TimingError> {
+        if changes.is_empty() {
+            return Err(TimingError::EmptyMap);
+        }
+        if changes.len() > MAX_SIGNATURE_CHANGES {
+            return Err(TimingError::TooManyPoints);
+        }
+        if changes[0].bar != 0 || changes.windows(2).any(|w| w[0].bar >= w[1].bar) {
+            return Err(TimingError::InvalidOrder);
+        }
+        for change in changes {
+            TimeSignature::new(change.signature.numerator, change.signature.denominator)?;
+        }
+
+        self.signatures[..changes.len()].copy_from_slice(changes);
+        self.signatures_len = changes.len();
+        for i in 1..self.signatures_len {
+            let previous = self.signatures[i - 1];
+            let bars = (self.signatures[i].bar - previous.bar) as f64;
+            self.signature_beats[i] =
+                self.signature_beats[i - 1] + bars * previous.signature.beats_per_bar();
+        }
+        Ok(())
+    }
+
+    pub fn signature_map(&self) -> &[SignatureChange] {
+        &self.signatures[..self.signatures_len]
+    }
+
+    // Signature at the playhead.
+    pub fn time_signature(&self) -> TimeSignature {
+        self.signature_at_beat(self.beat_at_sample(self.current_sample as f64))
+    }
+
+    pub fn signature_at_beat(&self, beat: f64) -> TimeSignature {
+        self.signatures[self.signature_segment(beat)].signature
+    }
+
+    // Loops between two beats while `looping` is on.
+    pub fn set_loop(&mut self, start_beat: f64, end_beat: f64) -> Result<(), TimingError> {
+        if !(start_beat >= 0.0 && end_beat > start_beat && end_beat.is_finite()) {
+            return Err(TimingError::InvalidLoop);
+        }
+        self.loop_region = Some((start_beat, end_beat));
+        self.looping = true;
+        Ok(())
+    }
+
+    pub fn clear_loop(&mut self) {
+        self.loop_region = None;
+        self.looping = false;
+    }
+
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping && self.loop_region.is_some();
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    pub fn loop_region(&self) -> Option<(f64, f64)> {
+        self.loop_region
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    // At the tempo under the playhead.
+    pub fn samples_per_beat(&self) -> f64 {
+        let bpm = self.bpm_at_beat(self.beat_at_sample(self.current_sample as f64));
+        60.0 / bpm * self.sample_rate as f64
+    }
+
+    // In quarter notes, for the bar under the playhead.
+    pub fn beats_per_bar(&self) -> f64 {
+        self.time_signature().beats_per_bar()
     }
 
     pub fn is_playing(&self) -> bool {
         self.is_playing
     }
 
-    pub fn get_position(&self) -> TransportPosition {
-        if !self.is_playing || self.samples_per_beat == 0.0 {
-            return TransportPosition {
-                current_sample: self.current_sample,
-                beat: 0.0,
-                bar: 0.0,
-                phase_in_beat: 0.0,
-                phase_in_bar: 0.0,
-            };
+    pub fn current_sample(&self) -> u64 {
+        self.current_sample
+    }
+
+    pub fn beat_at_sample(&self, sample: f64) -> f64 {
+        let seconds = sample.max(0.0) / self.sample_rate as f64;
+        let i = self.tempo_seconds[..self.tempo_len]
+            .iter()
+            .rposition(|&s| s <= seconds)
+            .unwrap_or(0);
+
+        let point = self.tempo[i];
+        let elapsed = seconds - self.tempo_seconds[i];
+        let bpm = point.bpm as f64;
+        match self.ramp_slope(i) {
+            Some(slope) => point.beat + bpm * (exp(slope * elapsed / 60.0) - 1.0) / slope,
+            None => point.beat + elapsed * bpm / 60.0,
         }
+    }
+
+    // Fractional, so callers can round the way they need.
+    pub fn sample_at_beat(&self, beat: f64) -> f64 {
+        let beat = beat.max(0.0);
+        let i = self.tempo_segment(beat);
+        let seconds = self.tempo_seconds[i] + self.segment_seconds(i, beat - self.tempo[i].beat);
+        seconds * self.sample_rate as f64
+    }
+
+    pub fn beat_at_bar(&self, bar: u32) -> f64 {
+        let i = self
+            .signature_map()
+            .iter()
+            .rposition(|c| c.bar <= bar)
+            .unwrap_or(0);
+        let change = self.signatures[i];
+        self.signature_beats[i] + (bar - change.bar) as f64 * change.signature.beats_per_bar()
+    }
+
+    // First bar line strictly after `beat`.
+    pub fn next_bar_beat(&self, beat: f64) -> f64 {
+        let (bar, _) = self.bar_at_beat(beat);
+        self.beat_at_bar(bar + 1)
+    }
+
+    // Bar index and the quarter notes into that bar.
+    pub fn bar_at_beat(&self, beat: f64) -> (u32, f64) {
+        let beat = beat.max(0.0);
+        let i = self.signature_segment(beat);
+        let change = self.signatures[i];
+        let length = change.signature.beats_per_bar();
+        let offset = beat - self.signature_beats[i];
 
-        let current_sample_f64 = self.current_sample as f64;
+        let bars = floor(offset / length + 1e-9);
+        let in_bar = (offset - bars * length).max(0.0);
+        (change.bar + bars as u32, in_bar)
+    }
+
+    pub fn get_position(&self) -> TransportPosition {
+        let beat = self.beat_at_sample(self.current_sample as f64);
+        let signature = self.signature_at_beat(beat);
+        let (bar, in_bar) = self.bar_at_beat(beat);
+        let bar_length = signature.beats_per_bar();
 
-        let beat = current_sample_f64 / self.samples_per_beat;
-        let samples_per_bar = self.samples_per_beat * self.beats_per_bar;
-        let bar = current_sample_f64 / samples_per_bar;
+        let beat_length = signature.beat_length();
+        let counted = floor(in_bar / beat_length + 1e-9).min(signature.numerator as f64 - 1.0);
+        let tick = floor((in_bar - counted * beat_length) * PPQ as f64 + 1e-6);
 
         TransportPosition {
             current_sample: self.current_sample,
             beat,
-            bar,
-            phase_in_beat: floor(beat),
-            phase_in_bar: floor(bar),
+            bar: bar as f64 + in_bar / bar_length,
+            phase_in_beat: beat - floor(beat),
+            phase_in_bar: in_bar / bar_length,
+            ticks: floor(beat * PPQ as f64 + 1e-6) as u64,
+            song_position: SongPosition {
+                bar: bar + 1,
+                beat: counted as u32 + 1,
+                tick: tick as u32,
+            },
+            bpm: self.bpm_at_beat(beat) as f32,
+            time_signature: signature,
+            is_playing: self.is_playing,
+        }
+    }
+
+    pub fn time_info(&self) -> TimeInfo {
+        let position = self.get_position();
+        let (bar, _) = self.bar_at_beat(position.beat);
+        TimeInfo {
+            block_start: self.current_sample,
+            is_playing: self.is_playing,
+            bpm: self.bpm_at_beat(position.beat),
+            samples_per_beat: self.samples_per_beat(),
+            time_signature: position.time_signature,
+            beat: position.beat,
+            bar: position.bar,
+            bar_start_beat: self.beat_at_bar(bar),
+            song_position: position.song_position,
+            loop_region: self.loop_region.filter(|_| self.looping),
+        }
+    }
+
+    fn loop_samples(&self) -> Option<(u64, u64)> {
+        let (start, end) = self.loop_region.filter(|_| self.looping)?;
+        let start = round(self.sample_at_beat(start)) as u64;
+        let end = round(self.sample_at_beat(end)) as u64;
+        (end > start).then_some((start, end))
+    }
+
+    fn tempo_segment(&self, beat: f64) -> usize {
+        self.tempo_map()
+            .iter()
+            .rposition(|p| p.beat <= beat)
+            .unwrap_or(0)
+    }
+
+    fn signature_segment(&self, beat: f64) -> usize {
+        self.signature_beats[..self.signatures_len]
+            .iter()
+            .rposition(|&b| b <= beat + 1e-9)
+            .unwrap_or(0)
+    }
+
+    // Tempo change per beat while ramping towards the next point.
+    fn ramp_slope(&self, i: usize) -> Option<f64> {
+        let point = self.tempo[i];
+        let next = self.tempo_map().get(i + 1).filter(|_| point.ramp)?;
+        let slope = (next.bpm as f64 - point.bpm as f64) / (next.beat - point.beat);
+        (slope.abs() > 1e-12).then_some(slope)
+    }
+
+    // Seconds taken by `beats` from the start of tempo segment `i`.
+    fn segment_seconds(&self, i: usize, beats: f64) -> f64 {
+        let bpm = self.tempo[i].bpm as f64;
+        match self.ramp_slope(i) {
+            Some(slope) => 60.0 / slope * log(1.0 + slope * beats / bpm),
+            None => beats * 60.0 / bpm,
         }
     }
 }
@@ -0,0 +1,266 @@
+use libm::exp2;
+
+use crate::{config::DEFAULT_CONFIG, frequency::Frequency};
+
+// Upper bound on scale degrees and keyboard mapping entries. Large enough for
+// the equal divisions found in the Scala archive.
+pub const MAX_TUNING_DEGREES: usize = 512;
+pub const MIDI_NOTE_COUNT: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuningError {
+    EmptyScale,
+    TooManyDegrees,
+    InvalidPeriod,
+    InvalidMapping,
+    UnmappedReference,
+}
+
+// Which scale degree each key plays, in the sense of a Scala .kbm file. A
+// `size` of 0 maps every key to consecutive degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyboardMap {
+    pub first_note: u8,
+    pub last_note: u8,
+    pub middle_note: u8,
+    pub reference_note: u8,
+    pub reference_freq: f32,
+    pub octave_degree: u16,
+    size: usize,
+    mapping: [Option<u16>; MAX_TUNING_DEGREES],
+}
+
+impl KeyboardMap {
+    pub const fn linear(middle_note: u8, reference_note: u8, reference_freq: f32) -> Self {
+        Self {
+            first_note: 0,
+            last_note: (MIDI_NOTE_COUNT - 1) as u8,
+            middle_note,
+            reference_note,
+            reference_freq,
+            octave_degree: 0,
+            size: 0,
+            mapping: [None; MAX_TUNING_DEGREES],
+        }
+    }
+
+    // `None` entries leave their keys silent.
+    pub fn with_mapping(
+        mut self,
+        mapping: &[Option<u16>],
+        octave_degree: u16,
+    ) -> Result<Self, TuningError> {
+        if mapping.len() > MAX_TUNING_DEGREES {
+            return Err(TuningError::TooManyDegrees);
+        }
+        self.size = mapping.len();
+        self.mapping[..mapping.len()].copy_from_slice(mapping);
+        self.octave_degree = octave_degree;
+        Ok(self)
+    }
+
+    pub fn mapping(&self) -> &[Option<u16>] {
+        &self.mapping[..self.size]
+    }
+
+    // Absolute scale degree for a key, counted from the middle note.
+    fn degree(&self, note: u8, scale_len: usize) -> Option<i32> {
+        if note < self.first_note || note > self.last_note {
+            return None;
+        }
+
+        let offset = note as i32 - self.middle_note as i32;
+        if self.size == 0 {
+            return Some(offset);
+        }
+
+        let size = self.size as i32;
+        let period = if self.octave_degree == 0 {
+            scale_len as i32
+        } else {
+            self.octave_degree as i32
+        };
+        let entry = self.mapping[offset.rem_euclid(size) as usize]?;
+        Some(offset.div_euclid(size) * period + entry as i32)
+    }
+}
+
+impl Default for KeyboardMap {
+    fn default() -> Self {
+        Self::linear(60, 69, DEFAULT_CONFIG.a4_freq)
+    }
+}
+
+// Note to frequency table for one instrument. The scale is stored in cents as
+// in a Scala .scl file: degree 0 is implied, the last entry is the period.
+#[derive(Debug, Clone, Copy)]
+pub struct Tuning {
+    cents: [f64; MAX_TUNING_DEGREES],
+    len: usize,
+    keyboard: KeyboardMap,
+    table: [Option<f32>; MIDI_NOTE_COUNT],
+}
+
+impl Tuning {
+    pub fn equal_temperament() -> Self {
+        let mut cents = [0.0; 12];
+        for (i, c) in cents.iter_mut().enumerate() {
+            *c = (i + 1) as f64 * 100.0;
+        }
+        Self::from_cents(&cents).unwrap()
+    }
+
+    pub fn equal_divisions(steps: usize, period_cents: f64) -> Result<Self, TuningError> {
+        if steps == 0 {
+            return Err(TuningError::EmptyScale);
+        }
+        if steps > MAX_TUNING_DEGREES {
+            return Err(TuningError::TooManyDegrees);
+        }
+
+        let mut cents = [0.0; MAX_TUNING_DEGREES];
+        for (i, c) in cents[..steps].iter_mut().enumerate() {
+            *c = (i + 1) as f64 * period_cents / steps as f64;
+        }
+        Self::from_cents(&cents[..steps])
+    }
+
+    pub fn from_cents(cents: &[f64]) -> Result<Self, TuningError> {
+        Self::with_keyboard_map(cents, KeyboardMap::default())
+    }
+
+    pub fn with_keyboard_map(cents: &[f64], keyboard: KeyboardMap) -> Result<Self, TuningError> {
+        let mut tuning = Self {
+            cents: [0.0; MAX_TUNING_DEGREES],
+            len: 0,
+            keyboard,
+            table: [None; MIDI_NOTE_COUNT],
+        };
+        tuning.set_scale(cents)?;
+        Ok(tuning)
+    }
+
+    pub fn set_scale(&mut self, cents: &[f64]) -> Result<(), TuningError> {
+        if cents.is_empty() {
+            return Err(TuningError::EmptyScale);
+        }
+        if cents.len() > MAX_TUNING_DEGREES {
+            return Err(TuningError::TooManyDegrees);
+        }
+        if cents[cents.len() - 1] <= 0.0 {
+            return Err(TuningError::InvalidPeriod);
+        }
+
+        let old = (self.cents, self.len);
+        self.cents[..cents.len()].copy_from_slice(cents);
+        self.len = cents.len();
+        self.rebuild().inspect_err(|_| (self.cents, self.len) = old)
+    }
+
+    pub fn set_keyboard_map(&mut self, keyboard: KeyboardMap) -> Result<(), TuningError> {
+        let old = self.keyboard;
+        self.keyboard = keyboard;
+        self.rebuild().inspect_err(|_| self.keyboard = old)
+    }
+
+    // Moves the whole tuning so `note` sounds at `freq`.
+    pub fn set_reference(&mut self, note: u8, freq: f32) -> Result<(), TuningError> {
+        let mut keyboard = self.keyboard;
+        keyboard.reference_note = note;
+        keyboard.reference_freq = freq;
+        self.set_keyboard_map(keyboard)
+    }
+
+    // Changes the frequency of the current reference note, e.g. A4 = 432 Hz.
+    pub fn set_reference_pitch(&mut self, freq: f32) {
+        if freq <= 0.0 {
+            return;
+        }
+        let ratio = freq / self.keyboard.reference_freq;
+        self.keyboard.reference_freq = freq;
+        for f in self.table.iter_mut().flatten() {
+            *f *= ratio;
+        }
+    }
+
+    // Retunes one key until the table is next rebuilt from the scale or
+    // keyboard map, e.g. for MIDI Tuning Standard changes.
+    pub fn set_note_frequency(&mut self, note: u8, freq: f32) {
+        if freq > 0.0
+            && let Some(f) = self.table.get_mut(note as usize)
+        {
+            *f = Some(freq);
+        }
+    }
+
+    pub fn keyboard_map(&self) -> &KeyboardMap {
+        &self.keyboard
+    }
+
+    pub fn scale(&self) -> &[f64] {
+        &self.cents[..self.len]
+    }
+
+    // `None` for keys the keyboard map leaves unmapped.
+    #[inline]
+    pub fn frequency(&self, note: u8) -> Option<Frequency> {
+        self.table
+            .get(note as usize)
+            .copied()
+            .flatten()
+            .map(Frequency)
+    }
+
+    fn rebuild(&mut self) -> Result<(), TuningError> {
+        let keyboard = &self.keyboard;
+        if keyboard.first_note > keyboard.last_note
+            || keyboard.last_note as usize >= MIDI_NOTE_COUNT
+            || keyboard.reference_freq <= 0.0
+        {
+            return Err(TuningError::InvalidMapping);
+        }
+
+        let scale = &self.cents[..self.len];
+
+        // The reference key may sit outside the retuned range, only its
+        // mapping matters.
+        let reference = KeyboardMap {
+            first_note: 0,
+            last_note: u8::MAX,
+            ..*keyboard
+        };
+        let reference_cents = reference
+            .degree(keyboard.reference_note, scale.len())
+            .map(|d| degree_cents(scale, d))
+            .ok_or(TuningError::UnmappedReference)?;
+        let reference_freq = keyboard.reference_freq as f64;
+
+        let mut table = [None; MIDI_NOTE_COUNT];
+        for (note, freq) in table.iter_mut().enumerate() {
+            *freq = keyboard.degree(note as u8, scale.len()).map(|d| {
+                let cents = degree_cents(scale, d) - reference_cents;
+                (reference_freq * exp2(cents / 1200.0)) as f32
+            });
+        }
+        self.table = table;
+        Ok(())
+    }
+}
+
+fn degree_cents(scale: &[f64], degree: i32) -> f64 {
+    let len = scale.len() as i32;
+    let period = scale[scale.len() - 1];
+    let step = degree.rem_euclid(len);
+    let base = if step == 0 {
+        0.0
+    } else {
+        scale[step as usize - 1]
+    };
+    degree.div_euclid(len) as f64 * period + base
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self::equal_temperament()
+    }
+}
@@ -0,0 +1,179 @@
+use squid_core::{
+    Event, EventBuffer, EventData, Transport,
+    arpeggiator::{ArpMode, Arpeggiator},
+};
+
+// 120 bpm at 48 kHz, so a sixteenth step is 6000 samples.
+const SAMPLE_RATE: f32 = 48000.0;
+const STEP: u64 = 6000;
+const FRAMES: usize = 1000;
+
+// Runs until `until` samples, feeding each input event in the block it falls
+// in. Returns note events with their sample from the start.
+fn run(arp: &mut Arpeggiator, input: &[(u64, EventData)], until: u64) -> Vec<(u64, EventData)> {
+    let mut transport = Transport::new(SAMPLE_RATE, 120.0);
+    transport.play();
+
+    let mut events = Vec::new();
+    while transport.current_sample() < until {
+        let time = transport.time_info();
+        let block: Vec<Event> = input
+            .iter()
+            .filter(|(at, _)| (time.block_start..time.block_start + FRAMES as u64).contains(at))
+            .map(|&(at, data)| Event::new((at - time.block_start) as u32, data))
+            .collect();
+
+        let mut out = EventBuffer::<64>::new();
+        arp.process(&block, &time, FRAMES, &mut out);
+        events.extend(
+            out.iter()
+                .map(|e| (time.block_start + e.timing as u64, e.data)),
+        );
+        transport.advance(FRAMES);
+    }
+    events
+}
+
+fn on(note: u8) -> EventData {
+    EventData::NoteOn {
+        note,
+        velocity: 100,
+    }
+}
+
+fn off(note: u8) -> EventData {
+    EventData::NoteOff { note }
+}
+
+fn chord(at: u64, notes: &[u8]) -> Vec<(u64, EventData)> {
+    notes.iter().map(|&n| (at, on(n))).collect()
+}
+
+fn note_ons(events: &[(u64, EventData)]) -> Vec<(u64, u8)> {
+    events
+        .iter()
+        .filter_map(|&(at, data)| match data {
+            EventData::NoteOn { note, .. } => Some((at, note)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn note_offs(events: &[(u64, EventData)]) -> Vec<u64> {
+    events
+        .iter()
+        .filter(|(_, data)| matches!(data, EventData::NoteOff { .. }))
+        .map(|&(at, _)| at)
+        .collect()
+}
+
+#[test]
+fn steps_land_on_their_samples() {
+    let mut arp = Arpeggiator::new();
+    let events = run(&mut arp, &chord(0, &[60, 64, 67]), 4 * STEP);
+
+    assert_eq!(
+        note_ons(&events),
+        [(0, 60), (STEP, 64), (2 * STEP, 67), (3 * STEP, 60)]
+    );
+    // The default gate holds half a step.
+    assert_eq!(events[1], (STEP / 2, off(60)));
+}
+
+#[test]
+fn gate_sets_the_note_off() {
+    let mut arp = Arpeggiator::new();
+    arp.set_gate(0.25);
+    let events = run(&mut arp, &chord(0, &[60]), 3 * STEP);
+    assert_eq!(note_offs(&events), [1500, STEP + 1500, 2 * STEP + 1500]);
+
+    let mut arp = Arpeggiator::new();
+    arp.set_gate(1.0);
+    let events = run(&mut arp, &chord(0, &[60]), 2 * STEP + 1);
+    // Legato notes end on the sample the next one starts.
+    assert_eq!(
+        events[..4],
+        [
+            (0, on(60)),
+            (STEP, off(60)),
+            (STEP, on(60)),
+            (2 * STEP, off(60)),
+        ]
+    );
+}
+
+#[test]
+fn swing_delays_odd_steps() {
+    let mut arp = Arpeggiator::new();
+    arp.set_swing(0.5);
+    let events = run(&mut arp, &chord(0, &[60, 64]), 4 * STEP);
+
+    assert_eq!(
+        note_ons(&events),
+        [
+            (0, 60),
+            (STEP + STEP / 2, 64),
+            (2 * STEP, 60),
+            (3 * STEP + STEP / 2, 64)
+        ]
+    );
+    // Each note holds half of its own, swung, step.
+    assert_eq!(
+        note_offs(&events)[..2],
+        [(STEP + STEP / 2) / 2, STEP + STEP / 2 + STEP / 4]
+    );
+}
+
+#[test]
+fn latch_keeps_playing_after_release() {
+    let mut input = chord(0, &[60, 64]);
+    input.push((100, off(60)));
+    input.push((200, off(64)));
+    // A new chord after everything was released replaces the latched one.
+    input.push((4 * STEP + 10, on(67)));
+
+    let mut arp = Arpeggiator::new();
+    arp.set_latch(true);
+    let events = run(&mut arp, &input, 6 * STEP);
+    assert_eq!(
+        note_ons(&events),
+        [
+            (0, 60),
+            (STEP, 64),
+            (2 * STEP, 60),
+            (3 * STEP, 64),
+            (4 * STEP, 60),
+            (5 * STEP, 67),
+        ]
+    );
+
+    // Without latch, releasing every key stops the arp at once.
+    let mut arp = Arpeggiator::new();
+    let events = run(&mut arp, &input[..4], 4 * STEP);
+    assert_eq!(events, [(0, on(60)), (200, off(60))]);
+}
+
+#[test]
+fn modes_order_the_held_notes() {
+    let order = |mode: ArpMode, octaves: u8, steps: u64| {
+        let mut arp = Arpeggiator::new();
+        arp.set_mode(mode);
+        arp.set_octaves(octaves);
+        // Pressed out of order on purpose.
+        let events = run(&mut arp, &chord(0, &[64, 60, 67]), steps * STEP);
+        note_ons(&events)
+            .into_iter()
+            .map(|(_, n)| n)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(order(ArpMode::Up, 1, 4), [60, 64, 67, 60]);
+    assert_eq!(order(ArpMode::Down, 1, 4), [67, 64, 60, 67]);
+    assert_eq!(order(ArpMode::UpDown, 1, 6), [60, 64, 67, 64, 60, 64]);
+    assert_eq!(order(ArpMode::AsPlayed, 1, 4), [64, 60, 67, 64]);
+    assert_eq!(order(ArpMode::Up, 2, 7), [60, 64, 67, 72, 76, 79, 60]);
+    assert_eq!(
+        order(ArpMode::UpDown, 2, 10),
+        [60, 64, 67, 72, 76, 79, 76, 72, 67, 64]
+    );
+}
@@ -0,0 +1,95 @@
+use core::f32::consts::PI;
+
+use squid_core::dsp::{
+    fft::{Fft, RealFft},
+    stft::Stft,
+    window::Window,
+};
+
+const SIZE: usize = 256;
+
+fn signal(len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| {
+            let t = i as f32;
+            (2.0 * PI * 5.0 * t / SIZE as f32).sin() + 0.5 * (2.0 * PI * 0.013 * t).cos()
+        })
+        .collect()
+}
+
+#[test]
+fn fft_inverse_restores_the_signal() {
+    let fft = Fft::<SIZE>::new();
+    let x = signal(SIZE);
+    let mut re = x.clone();
+    let mut im: Vec<f32> = x.iter().rev().copied().collect();
+
+    fft.forward(&mut re, &mut im);
+    fft.inverse(&mut re, &mut im);
+
+    for i in 0..SIZE {
+        assert!((re[i] - x[i]).abs() < 1e-4, "re {i}: {} vs {}", re[i], x[i]);
+        assert!((im[i] - x[SIZE - 1 - i]).abs() < 1e-4, "im {i}");
+    }
+}
+
+#[test]
+fn real_fft_finds_a_sine_and_restores_it() {
+    let fft = RealFft::<SIZE>::new();
+    let x: Vec<f32> = (0..SIZE)
+        .map(|i| (2.0 * PI * 12.0 * i as f32 / SIZE as f32).sin())
+        .collect();
+    let mut re = vec![0.0; SIZE];
+    let mut im = vec![0.0; SIZE];
+
+    fft.forward(&x, &mut re, &mut im);
+    for k in 0..RealFft::<SIZE>::BINS {
+        let magnitude = re[k].hypot(im[k]);
+        let expected = if k == 12 { SIZE as f32 / 2.0 } else { 0.0 };
+        assert!((magnitude - expected).abs() < 1e-2, "bin {k}: {magnitude}");
+    }
+
+    let mut y = vec![0.0; SIZE];
+    fft.inverse(&mut re, &mut im, &mut y);
+    for i in 0..SIZE {
+        assert!(
+            (y[i] - x[i]).abs() < 1e-4,
+            "sample {i}: {} vs {}",
+            y[i],
+            x[i]
+        );
+    }
+}
+
+// With nothing done to the spectrum the output is the input, delayed.
+fn assert_stft_passes_through(window: Window, overlap: usize) {
+    let mut stft = Stft::<SIZE>::new(window, overlap);
+    let x = signal(SIZE * 8);
+    let mut y = x.clone();
+    stft.process_block(&mut y, |_, _| {});
+
+    let latency = stft.latency();
+    for i in latency..x.len() {
+        let (a, b) = (y[i], x[i - latency]);
+        assert!(
+            (a - b).abs() < 1e-3,
+            "{window:?} overlap {overlap}, sample {i}: {a} vs {b}"
+        );
+    }
+}
+
+#[test]
+fn stft_passes_through_at_every_overlap() {
+    for window in [
+        Window::Rectangular,
+        Window::Hann,
+        Window::SqrtHann,
+        Window::Hamming,
+        Window::Blackman,
+        Window::BlackmanHarris,
+    ] {
+        for overlap in [1, 2, 4, 8] {
+            assert_stft_passes_through(window, overlap);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use squid_core::{
+    BlockEvents, Event, EventData, EventProcessor, humanize::Humanize,
+    process_context::ProcessContext,
+};
+
+#[test]
+fn dense_input_leaves_no_note_hanging() {
+    let mut humanize = Humanize::new(Humanize::MAX_TIMING_MS, 0);
+    let ctx = ProcessContext::new(48000.0, &[], &[]);
+
+    // Far more notes in flight than the delay buffer holds.
+    let mut last = HashMap::new();
+    for block in 0..100u8 {
+        let mut input = Vec::new();
+        if block < 16 {
+            for note in 0..100 {
+                let on = EventData::NoteOn {
+                    note,
+                    velocity: 100,
+                };
+                input.push(Event::new(0, on).with_channel(block));
+            }
+            for note in 0..100 {
+                input.push(Event::new(10, EventData::NoteOff { note }).with_channel(block));
+            }
+        }
+
+        let mut output = BlockEvents::new();
+        humanize.process(&ctx, &input, &mut output);
+        assert!(!output.is_full());
+        for event in output.iter() {
+            last.insert((event.channel, event.note()), event.data.is_note_on());
+        }
+    }
+
+    let hanging = last.values().filter(|&&on| on).count();
+    assert_eq!(hanging, 0);
+}
@@ -0,0 +1,278 @@
+use squid_core::{
+    Event, EventData, SysExChunk, SysExStatus, UMP_MIDI1, UMP_MIDI2, UMP_SYSEX7, Ump, sysex_chunks,
+};
+
+fn round_trip(event: Event) -> Event {
+    let ump = Ump::from_event(&event, 5).unwrap();
+    assert_eq!(ump.group(), 5);
+    let words = Ump::from_words(ump.words()).unwrap();
+    words.to_event(event.timing).unwrap()
+}
+
+#[test]
+fn midi1_bytes_round_trip() {
+    let messages: [&[u8]; 7] = [
+        &[0x80, 60, 0],
+        &[0x93, 60, 100],
+        &[0xa4, 61, 33],
+        &[0xb5, 74, 127],
+        &[0xc6, 12],
+        &[0xd7, 90],
+        &[0xef, 0x7f, 0x7f],
+    ];
+    for bytes in messages {
+        let event = Event::from_midi1_bytes(7, bytes).unwrap();
+        assert_eq!(event.timing, 7);
+        assert_eq!(event.channel, bytes[0] & 0x0f);
+
+        let (out, len) = event.to_midi1_bytes().unwrap();
+        assert_eq!(&out[..len], bytes);
+    }
+
+    assert_eq!(
+        Event::from_midi1_bytes(0, &[0x90, 60, 100]).unwrap().data,
+        EventData::NoteOn {
+            note: 60,
+            velocity: 100
+        }
+    );
+    assert_eq!(
+        Event::from_midi1_bytes(0, &[0xe0, 0x00, 0x40])
+            .unwrap()
+            .data,
+        EventData::PitchBend { value: 0x2000 }
+    );
+    // Truncated, system and data bytes aren't channel voice messages.
+    assert_eq!(Event::from_midi1_bytes(0, &[0x90, 60]), None);
+    assert_eq!(Event::from_midi1_bytes(0, &[0xf8]), None);
+    assert_eq!(Event::from_midi1_bytes(0, &[0x40, 60, 100]), None);
+}
+
+#[test]
+fn midi1_events_travel_as_midi1_packets() {
+    let event = Event::new(
+        3,
+        EventData::NoteOn {
+            note: 60,
+            velocity: 100,
+        },
+    )
+    .with_channel(3);
+    let ump = Ump::from_event(&event, 5).unwrap();
+    assert_eq!(ump.message_type(), UMP_MIDI1);
+    assert_eq!(ump.words(), [0x2593_3c64]);
+    assert_eq!(round_trip(event), event);
+
+    for data in [
+        EventData::NoteOff { note: 1 },
+        EventData::NotePressure {
+            note: 2,
+            pressure: 3,
+        },
+        EventData::ControlChange {
+            control: 7,
+            value: 0,
+        },
+        EventData::ProgramChange { program: 127 },
+        EventData::ChannelPressure { pressure: 64 },
+        EventData::PitchBend { value: 0x3fff },
+    ] {
+        let event = Event::new(0, data).with_channel(15);
+        assert_eq!(round_trip(event), event);
+    }
+}
+
+#[test]
+fn midi2_events_travel_as_midi2_packets() {
+    let event = Event::new(
+        0,
+        EventData::NoteOn16 {
+            note: 60,
+            velocity: 0x8000,
+        },
+    )
+    .with_channel(1);
+    let ump = Ump::from_event(&event, 0).unwrap();
+    assert_eq!(ump.message_type(), UMP_MIDI2);
+    assert_eq!(ump.words(), [0x4091_3c00, 0x8000_0000]);
+    assert_eq!(round_trip(event), event);
+
+    for data in [
+        EventData::NotePressure32 {
+            note: 60,
+            pressure: 0x1234_5678,
+        },
+        EventData::ControlChange32 {
+            control: 1,
+            value: u32::MAX,
+        },
+        EventData::ChannelPressure32 { pressure: 1 },
+        EventData::PitchBend32 { value: 0x8000_0000 },
+        EventData::NotePitchBend32 {
+            note: 64,
+            value: 0x4000_0000,
+        },
+        EventData::NoteControlChange32 {
+            note: 64,
+            control: 74,
+            value: 0xdead_beef,
+        },
+    ] {
+        let event = Event::new(0, data).with_channel(9);
+        assert_eq!(round_trip(event), event);
+    }
+
+    // Per-note controllers have no MIDI 1.0 form, so they go out scaled up.
+    let event = Event::new(
+        0,
+        EventData::NoteControlChange {
+            note: 60,
+            control: 74,
+            value: 64,
+        },
+    );
+    let ump = Ump::from_event(&event, 0).unwrap();
+    assert_eq!(ump.message_type(), UMP_MIDI2);
+    assert_eq!(round_trip(event).data, event.data.to_high_res());
+}
+
+#[test]
+fn sysex_splits_into_sysex7_packets() {
+    let payload: Vec<u8> = (0..15).collect();
+    let chunks: Vec<SysExChunk> = sysex_chunks(&payload).collect();
+    assert_eq!(chunks.len(), 3);
+
+    let packets: Vec<Ump> = chunks
+        .iter()
+        .map(|&chunk| Ump::from_event(&Event::new(0, EventData::SysEx { chunk }), 2).unwrap())
+        .collect();
+    assert!(packets.iter().all(|p| p.message_type() == UMP_SYSEX7));
+    assert_eq!(packets[0].words(), [0x3216_0001, 0x0203_0405]);
+    assert_eq!(packets[1].words(), [0x3226_0607, 0x0809_0a0b]);
+    assert_eq!(packets[2].words(), [0x3233_0c0d, 0x0e00_0000]);
+
+    for (packet, chunk) in packets.iter().zip(&chunks) {
+        assert_eq!(
+            packet.to_event(0).unwrap().data,
+            EventData::SysEx { chunk: *chunk }
+        );
+    }
+
+    let short = SysExChunk::new(SysExStatus::Complete, &[0x7e, 0x7f]);
+    let ump = Ump::from_event(&Event::new(0, EventData::SysEx { chunk: short }), 0).unwrap();
+    assert_eq!(ump.words(), [0x3002_7e7f, 0]);
+}
+
+#[test]
+fn packets_need_their_full_length() {
+    assert_eq!(Ump::word_count(0x2000_0000), 1);
+    assert_eq!(Ump::word_count(0x4000_0000), 2);
+    assert_eq!(Ump::from_words(&[0x4090_3c00]), None);
+    assert_eq!(Ump::from_words(&[0x2090_3c64, 0]), None);
+    // Utility messages aren't held.
+    assert_eq!(Ump::from_words(&[0x0000_0000]), None);
+    assert_eq!(Ump::from_words(&[]), None);
+}
+
+#[test]
+fn resolution_scaling_keeps_min_center_and_max() {
+    let cases = [
+        (
+            EventData::ControlChange {
+                control: 1,
+                value: 0,
+            },
+            EventData::ControlChange32 {
+                control: 1,
+                value: 0,
+            },
+        ),
+        (
+            EventData::ControlChange {
+                control: 1,
+                value: 64,
+            },
+            EventData::ControlChange32 {
+                control: 1,
+                value: 0x8000_0000,
+            },
+        ),
+        (
+            EventData::ControlChange {
+                control: 1,
+                value: 127,
+            },
+            EventData::ControlChange32 {
+                control: 1,
+                value: u32::MAX,
+            },
+        ),
+        (
+            EventData::PitchBend { value: 0 },
+            EventData::PitchBend32 { value: 0 },
+        ),
+        (
+            EventData::PitchBend { value: 0x2000 },
+            EventData::PitchBend32 { value: 0x8000_0000 },
+        ),
+        (
+            EventData::PitchBend { value: 0x3fff },
+            EventData::PitchBend32 { value: u32::MAX },
+        ),
+        (
+            EventData::NoteOn {
+                note: 60,
+                velocity: 1,
+            },
+            EventData::NoteOn16 {
+                note: 60,
+                velocity: 0x200,
+            },
+        ),
+        (
+            EventData::NoteOn {
+                note: 60,
+                velocity: 64,
+            },
+            EventData::NoteOn16 {
+                note: 60,
+                velocity: 0x8000,
+            },
+        ),
+        (
+            EventData::NoteOn {
+                note: 60,
+                velocity: 127,
+            },
+            EventData::NoteOn16 {
+                note: 60,
+                velocity: u16::MAX,
+            },
+        ),
+    ];
+    for (low, high) in cases {
+        assert_eq!(low.to_high_res(), high, "{low:?}");
+        assert_eq!(high.to_midi1(), low, "{high:?}");
+    }
+
+    // A note on stays a note on however quiet, and zero velocity is a note off.
+    assert_eq!(
+        EventData::NoteOn16 {
+            note: 60,
+            velocity: 1
+        }
+        .to_midi1(),
+        EventData::NoteOn {
+            note: 60,
+            velocity: 1
+        }
+    );
+    assert_eq!(
+        EventData::NoteOn {
+            note: 60,
+            velocity: 0
+        }
+        .to_high_res(),
+        EventData::NoteOff { note: 60 }
+    );
+}
@@ -0,0 +1,54 @@
+use squid_core::{
+    BlockEvents, Event, EventData, EventProcessor, NOTE_BEND_RANGE,
+    mpe::{MpeInput, MpeSide},
+    process_context::ProcessContext,
+};
+
+fn cc(channel: u8, control: u8, value: u8) -> Event {
+    Event::new(0, EventData::ControlChange { control, value }).with_channel(channel)
+}
+
+fn run(mpe: &mut MpeInput, input: &[Event]) -> BlockEvents {
+    let mut output = BlockEvents::new();
+    mpe.process(&ProcessContext::new(48000.0, &[], &[]), input, &mut output);
+    output
+}
+
+fn bend_semitones(value: u32) -> f64 {
+    let center = (1u64 << 31) as f64;
+    (value as f64 - center) / center * NOTE_BEND_RANGE as f64
+}
+
+#[test]
+fn master_bend_uses_the_master_bend_range() {
+    let mut mpe = MpeInput::lower(15);
+    // RPN 0 on the master channel: 12 semitones.
+    run(&mut mpe, &[cc(0, 101, 0), cc(0, 100, 0), cc(0, 6, 12)]);
+    assert_eq!(mpe.zone(MpeSide::Lower).unwrap().master_bend_range, 12.0);
+
+    let note_on = Event::new(
+        0,
+        EventData::NoteOn {
+            note: 60,
+            velocity: 100,
+        },
+    );
+    run(&mut mpe, &[note_on.with_channel(3)]);
+
+    let output = run(
+        &mut mpe,
+        &[Event::new(0, EventData::PitchBend { value: 16383 }).with_channel(0)],
+    );
+    let bends: Vec<_> = output
+        .iter()
+        .map(|e| match e.data {
+            EventData::NotePitchBend32 { note, value } => (e.channel, note, bend_semitones(value)),
+            data => panic!("unexpected {data:?}"),
+        })
+        .collect();
+
+    assert_eq!(bends.len(), 1);
+    let (channel, note, semitones) = bends[0];
+    assert_eq!((channel, note), (3, 60));
+    assert!((semitones - 12.0).abs() < 0.01, "{semitones}");
+}
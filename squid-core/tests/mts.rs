@@ -0,0 +1,166 @@
+use squid_core::{MtsMessage, Tuning};
+
+fn hz(tuning: &Tuning, note: u8) -> f32 {
+    tuning.frequency(note).unwrap().0
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < b * 1e-5
+}
+
+// A non-realtime bulk dump of 12-TET with A4 half a semitone up and key 0
+// left alone.
+fn bulk_dump() -> Vec<u8> {
+    let mut payload = vec![0x7e, 0x7f, 0x08, 0x01, 3];
+    payload.extend(b"Quarter A       ");
+    for key in 0..128u8 {
+        payload.extend(match key {
+            0 => [0x7f, 0x7f, 0x7f],
+            69 => [69, 0x40, 0x00],
+            _ => [key, 0, 0],
+        });
+    }
+    let checksum = payload.iter().fold(0, |a, b| a ^ b) & 0x7f;
+    payload.push(checksum);
+    payload
+}
+
+#[test]
+fn bulk_dump_checks_its_checksum() {
+    let payload = bulk_dump();
+    assert_eq!(payload.len(), 5 + 16 + 384 + 1);
+
+    let Some(MtsMessage::Dump {
+        bank,
+        program,
+        name,
+        data,
+    }) = MtsMessage::parse(&payload)
+    else {
+        panic!("not a dump");
+    };
+    assert_eq!((bank, program), (0, 3));
+    assert_eq!(name, b"Quarter A       ");
+    assert_eq!(data.len(), 384);
+
+    let mut bad = payload.clone();
+    *bad.last_mut().unwrap() ^= 1;
+    assert_eq!(MtsMessage::parse(&bad), None);
+
+    // The checksum is optional, but the data isn't.
+    assert!(MtsMessage::parse(&payload[..payload.len() - 1]).is_some());
+    assert_eq!(MtsMessage::parse(&payload[..payload.len() - 2]), None);
+}
+
+#[test]
+fn bulk_dump_retunes_every_sent_key() {
+    let mut tuning = Tuning::equal_temperament();
+    tuning.set_note_frequency(0, 1.0);
+    let payload = bulk_dump();
+    let message = MtsMessage::parse(&payload).unwrap();
+    assert!(message.apply(&mut tuning, 1));
+
+    assert!(close(hz(&tuning, 69), 440.0 * 2f32.powf(0.5 / 12.0)));
+    assert!(close(hz(&tuning, 60), 261.6256));
+    // 7F 7F 7F keeps the old frequency.
+    assert_eq!(hz(&tuning, 0), 1.0);
+}
+
+#[test]
+fn single_note_changes_move_only_their_keys() {
+    // Realtime, program 0, two changes: key 60 to its own pitch a quarter
+    // semitone up, and key 69 to 12-TET Bb4.
+    let payload = [
+        0x7f, 0x7f, 0x08, 0x02, 0, 2, 60, 60, 0x20, 0x00, 69, 70, 0x00, 0x00,
+    ];
+    let message = MtsMessage::parse(&payload).unwrap();
+    assert_eq!(
+        message,
+        MtsMessage::SingleNote {
+            bank: 0,
+            program: 0,
+            changes: &payload[6..],
+        }
+    );
+
+    let mut tuning = Tuning::equal_temperament();
+    assert!(message.apply(&mut tuning, 1));
+    assert!(close(hz(&tuning, 60), 261.6256 * 2f32.powf(0.25 / 12.0)));
+    assert!(close(hz(&tuning, 69), 466.1638));
+    assert!(close(hz(&tuning, 61), 277.1826));
+
+    // A count past the data only takes the complete changes.
+    let mut short = payload.to_vec();
+    short[5] = 5;
+    short.pop();
+    let Some(MtsMessage::SingleNote { changes, .. }) = MtsMessage::parse(&short) else {
+        panic!("not a single note change");
+    };
+    assert_eq!(changes, &payload[6..10]);
+}
+
+#[test]
+fn octave_tuning_one_byte() {
+    // Channels 0 and 15, every pitch class in tune except A, 50 cents up.
+    let mut payload = vec![0x7e, 0x7f, 0x08, 0x08, 0b10, 0x00, 0b01];
+    payload.extend([64; 12]);
+    payload[7 + 9] = 64 + 50;
+
+    let message = MtsMessage::parse(&payload).unwrap();
+    let MtsMessage::Octave { channels, cents } = message else {
+        panic!("not an octave tuning");
+    };
+    assert_eq!(channels, 1 | 1 << 15);
+    assert_eq!(cents[9], 50.0);
+
+    let mut tuning = Tuning::equal_temperament();
+    assert!(!message.apply(&mut tuning, 1 << 3));
+    assert_eq!(hz(&tuning, 69), 440.0);
+
+    assert!(message.apply(&mut tuning, 1 << 15));
+    assert!(close(hz(&tuning, 69), 440.0 * 2f32.powf(0.5 / 12.0)));
+    assert!(close(hz(&tuning, 57), 220.0 * 2f32.powf(0.5 / 12.0)));
+    assert!(close(hz(&tuning, 60), 261.6256));
+
+    // Twelve offsets are needed.
+    assert_eq!(MtsMessage::parse(&payload[..18]), None);
+}
+
+#[test]
+fn octave_tuning_two_bytes() {
+    // Channels 7 to 13. C a semitone down, A 50 cents up, the rest in tune.
+    let mut payload = vec![0x7f, 0x7f, 0x08, 0x09, 0x00, 0x7f, 0x00];
+    for pitch_class in 0..12 {
+        payload.extend(match pitch_class {
+            0 => [0x00, 0x00],
+            9 => [0x60, 0x00],
+            _ => [0x40, 0x00],
+        });
+    }
+
+    let message = MtsMessage::parse(&payload).unwrap();
+    let MtsMessage::Octave { channels, cents } = message else {
+        panic!("not an octave tuning");
+    };
+    assert_eq!(channels, 0x3f80);
+    assert_eq!(cents[0], -100.0);
+    assert_eq!(cents[9], 50.0);
+    assert_eq!(cents[4], 0.0);
+
+    let mut tuning = Tuning::equal_temperament();
+    assert!(message.apply(&mut tuning, 1 << 7));
+    assert!(close(hz(&tuning, 60), 246.9417));
+    assert!(close(hz(&tuning, 69), 440.0 * 2f32.powf(0.5 / 12.0)));
+    assert!(close(hz(&tuning, 64), 329.6276));
+
+    assert_eq!(MtsMessage::parse(&payload[..30]), None);
+}
+
+#[test]
+fn other_sysex_is_not_mts() {
+    // General MIDI on, a manufacturer message and an unknown MTS sub id.
+    assert_eq!(MtsMessage::parse(&[0x7e, 0x7f, 0x09, 0x01]), None);
+    assert_eq!(MtsMessage::parse(&[0x41, 0x10, 0x08, 0x02, 0, 0]), None);
+    assert_eq!(MtsMessage::parse(&[0x7e, 0x7f, 0x08, 0x7f, 0, 0]), None);
+    assert_eq!(MtsMessage::parse(&[]), None);
+}
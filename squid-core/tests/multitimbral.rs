@@ -0,0 +1,105 @@
+use squid_core::{
+    AudioNode, Event, EventData, SysExChunk, SysExStatus,
+    process_context::{FixedBuf, ProcessContext},
+    synths::multitimbral::MultiTimbral,
+};
+
+// Keeps the events of the last block.
+#[derive(Default)]
+struct Recorder {
+    events: Vec<Event>,
+}
+
+impl AudioNode for Recorder {
+    fn process(&mut self, ctx: &ProcessContext, _outputs: &mut [&mut FixedBuf]) {
+        self.events = ctx.events.to_vec();
+    }
+
+    fn reset(&mut self, _sample_rate: f32) {}
+}
+
+fn note_on(timing: u32, channel: u8, note: u8) -> Event {
+    Event::new(
+        timing,
+        EventData::NoteOn {
+            note,
+            velocity: 100,
+        },
+    )
+    .with_channel(channel)
+}
+
+fn run(host: &mut MultiTimbral<Recorder, 4>, events: &[Event]) {
+    let mut left = FixedBuf::default();
+    let mut right = FixedBuf::default();
+    host.process(
+        &ProcessContext::new(48000.0, events, &[]),
+        &mut [&mut left, &mut right],
+    );
+}
+
+fn received(host: &MultiTimbral<Recorder, 4>, slot: usize) -> Vec<(u32, u8, Option<u8>)> {
+    let recorder = host.slot(slot).unwrap().instrument();
+    recorder
+        .events
+        .iter()
+        .map(|e| (e.timing, e.channel, e.note()))
+        .collect()
+}
+
+#[test]
+fn channels_route_to_their_slots() {
+    let mut host = MultiTimbral::<Recorder, 4>::new();
+    host.set_instrument(0, Recorder::default());
+    host.set_instrument(1, Recorder::default());
+
+    run(
+        &mut host,
+        &[note_on(0, 0, 60), note_on(5, 1, 62), note_on(9, 2, 64)],
+    );
+    assert_eq!(received(&host, 0), [(0, 0, Some(60))]);
+    assert_eq!(received(&host, 1), [(5, 1, Some(62))]);
+}
+
+#[test]
+fn slots_on_several_channels_get_events_in_time_order() {
+    let mut host = MultiTimbral::<Recorder, 4>::new();
+    host.set_instrument(0, Recorder::default())
+        .unwrap()
+        .set_channels(0b111);
+    host.set_instrument(1, Recorder::default());
+
+    run(
+        &mut host,
+        &[note_on(1, 2, 60), note_on(3, 0, 62), note_on(7, 1, 64)],
+    );
+    assert_eq!(
+        received(&host, 0),
+        [(1, 2, Some(60)), (3, 0, Some(62)), (7, 1, Some(64))]
+    );
+    // Layered on channel 1.
+    assert_eq!(received(&host, 1), [(7, 1, Some(64))]);
+}
+
+#[test]
+fn splits_transpose_and_sysex() {
+    let mut host = MultiTimbral::<Recorder, 4>::new();
+    let low = host.set_instrument(0, Recorder::default()).unwrap();
+    low.set_key_range(0, 59);
+    low.set_transpose(12);
+    let high = host.set_instrument(1, Recorder::default()).unwrap();
+    high.set_channels(1);
+    high.set_key_range(60, 127);
+
+    let sysex = Event::new(
+        4,
+        EventData::SysEx {
+            chunk: SysExChunk::new(SysExStatus::Complete, &[0x7e, 0x7f]),
+        },
+    )
+    .with_channel(9);
+    run(&mut host, &[note_on(0, 0, 48), note_on(2, 0, 72), sysex]);
+
+    assert_eq!(received(&host, 0), [(0, 0, Some(60)), (4, 9, None)]);
+    assert_eq!(received(&host, 1), [(2, 0, Some(72)), (4, 9, None)]);
+}
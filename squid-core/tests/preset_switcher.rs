@@ -0,0 +1,89 @@
+use squid_core::{
+    AudioNode, Event, EventData, MAX_BLOCK_SIZE, PresetQueue, Presets, ProgramId,
+    preset_switcher::PresetSwitcher,
+    process_context::{FixedBuf, ProcessContext},
+};
+
+const SAMPLE_RATE: f32 = 48000.0;
+// The default 5 ms fade at 48 kHz.
+const FADE: usize = 240;
+
+// Outputs its preset as a constant level.
+struct Level(f32);
+
+impl AudioNode for Level {
+    fn process(&mut self, _ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        for output in outputs.iter_mut() {
+            output.data.fill(self.0);
+        }
+    }
+
+    fn reset(&mut self, _sample_rate: f32) {}
+}
+
+impl Presets for Level {
+    type Preset = f32;
+
+    fn preset(&self) -> f32 {
+        self.0
+    }
+
+    fn load_preset(&mut self, preset: &f32) {
+        self.0 = *preset;
+    }
+}
+
+fn render<Q>(switcher: &mut PresetSwitcher<Level, Q, 4>, events: &[Event]) -> Vec<f32>
+where
+    Q: core::ops::Deref<Target = PresetQueue<f32>>,
+{
+    let mut left = FixedBuf::default();
+    let mut right = FixedBuf::default();
+    let ctx = ProcessContext::new(SAMPLE_RATE, events, &[]);
+    switcher.process(&ctx, &mut [&mut left, &mut right]);
+    left.iter().copied().collect()
+}
+
+#[test]
+fn program_change_fades_out_loads_and_fades_in() {
+    let queue = PresetQueue::<f32>::new();
+    let mut switcher = PresetSwitcher::<_, _, 4>::new(Level(1.0), &queue);
+    let id = ProgramId::new(0, 1);
+    assert!(queue.push((id, 0.5)).is_ok());
+
+    let change = Event::new(0, EventData::ProgramChange { program: 1 });
+    let mut output = render(&mut switcher, &[change]);
+    while output.len() < 4 * FADE {
+        output.extend(render(&mut switcher, &[]));
+    }
+
+    assert_eq!(switcher.program(), Some(id));
+    assert_eq!(switcher.node().preset(), 0.5);
+
+    // Down to silence over the fade, without jumps, at the context's rate
+    // even though the switcher was never reset.
+    let silent = output.iter().position(|&x| x == 0.0).unwrap();
+    // Rounding in the gain steps may take one more sample to reach zero.
+    assert!((FADE - 1..=FADE).contains(&silent));
+    assert!(output[..silent].windows(2).all(|w| w[1] < w[0]));
+    assert!(output[..silent].windows(2).all(|w| w[0] - w[1] < 0.01));
+
+    // The new preset comes back in once the block it was loaded after ends.
+    let loaded = silent.div_ceil(MAX_BLOCK_SIZE) * MAX_BLOCK_SIZE;
+    assert!(output[silent..loaded].iter().all(|&x| x == 0.0));
+    let back = &output[loaded..];
+    assert!(back.windows(2).all(|w| w[1] >= w[0] && w[1] - w[0] < 0.01));
+    // And stays there.
+    assert!(back[FADE + 1..].iter().all(|&x| x == 0.5));
+}
+
+#[test]
+fn unknown_programs_are_ignored() {
+    let queue = PresetQueue::<f32>::new();
+    let mut switcher = PresetSwitcher::<_, _, 4>::new(Level(1.0), &queue);
+    let change = Event::new(0, EventData::ProgramChange { program: 9 });
+    let output = render(&mut switcher, &[change]);
+
+    assert_eq!(switcher.program(), None);
+    assert!(output.iter().all(|&x| x == 1.0));
+}
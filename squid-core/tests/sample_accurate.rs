@@ -0,0 +1,104 @@
+use squid_core::{
+    AudioNode, Event, EventData, MAX_BLOCK_SIZE,
+    dsp::mod_core::adsr_mod_source::{AdsrModSource, calculate_coefficient},
+    oscillators::saw_osc::SawOsc,
+    process_context::{FixedBuf, ProcessContext},
+    synths::poly_synth::PolySynth,
+};
+
+// Instant attack, decay and release so the output is either silent or not.
+fn synth(sample_rate: f32) -> PolySynth<SawOsc> {
+    let mut env = AdsrModSource::new();
+    let instant = calculate_coefficient(0.0, sample_rate);
+    env.set_parameters(instant, instant, instant, 0.8);
+    PolySynth::new(SawOsc::new(), env)
+}
+
+fn render(synth: &mut PolySynth<SawOsc>, sample_rate: f32, events: &[Event]) -> Vec<f32> {
+    let mut left = FixedBuf::default();
+    let mut right = FixedBuf::default();
+    let ctx = ProcessContext::new(sample_rate, events, &[]);
+    synth.process(&ctx, &mut [&mut left, &mut right]);
+    left.to_vec()
+}
+
+fn note_on(timing: u32) -> Event {
+    Event::new(
+        timing,
+        EventData::NoteOn {
+            note: 60,
+            velocity: 100,
+        },
+    )
+}
+
+fn note_off(timing: u32) -> Event {
+    Event::new(timing, EventData::NoteOff { note: 60 })
+}
+
+#[test]
+fn note_starts_on_its_sample() {
+    for offset in 0..MAX_BLOCK_SIZE {
+        let mut synth = synth(48000.0);
+        let block = render(&mut synth, 48000.0, &[note_on(offset as u32)]);
+
+        let first = block.iter().position(|&s| s != 0.0);
+        assert_eq!(first, Some(offset), "note on at {offset}");
+    }
+}
+
+#[test]
+fn note_starts_on_its_sample_in_a_later_block() {
+    let mut synth = synth(48000.0);
+    let silent = render(&mut synth, 48000.0, &[]);
+    assert!(silent.iter().all(|&s| s == 0.0));
+
+    let block = render(&mut synth, 48000.0, &[note_on(21)]);
+    assert!(block[..21].iter().all(|&s| s == 0.0));
+    assert!(block[21..].iter().all(|&s| s != 0.0));
+}
+
+#[test]
+fn note_stops_on_its_sample() {
+    let mut synth = synth(48000.0);
+    let off = MAX_BLOCK_SIZE as u32 / 2 + 3;
+    let block = render(&mut synth, 48000.0, &[note_on(5), note_off(off)]);
+
+    let off = off as usize;
+    assert!(block[..5].iter().all(|&s| s == 0.0));
+    assert!(block[5..off].iter().all(|&s| s != 0.0));
+    assert!(block[off..].iter().all(|&s| s == 0.0));
+
+    let after = render(&mut synth, 48000.0, &[]);
+    assert!(after.iter().all(|&s| s == 0.0));
+}
+
+#[test]
+fn pitch_follows_the_context_sample_rate() {
+    // A4 lasts exactly 64 samples here, so the saw falls through zero every
+    // 64 samples. Any other sample rate would drift.
+    let sample_rate = 440.0 * 64.0;
+    let mut synth = synth(sample_rate);
+
+    let mut samples = Vec::new();
+    let a4 = Event::new(
+        0,
+        EventData::NoteOn {
+            note: 69,
+            velocity: 100,
+        },
+    );
+    samples.extend(render(&mut synth, sample_rate, &[a4]));
+    for _ in 0..8 {
+        samples.extend(render(&mut synth, sample_rate, &[]));
+    }
+
+    let wraps: Vec<usize> = samples
+        .windows(2)
+        .enumerate()
+        .filter(|(_, w)| w[0] > 0.0 && w[1] <= 0.0)
+        .map(|(i, _)| i)
+        .collect();
+    assert!(wraps.len() > 2);
+    assert!(wraps.windows(2).all(|w| w[1] - w[0] == 64), "{wraps:?}");
+}
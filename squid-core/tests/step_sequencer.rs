@@ -0,0 +1,87 @@
+use squid_core::{
+    EventBuffer, EventData, Transport,
+    step_sequencer::{Pattern, Step, StepSequencer, Track},
+};
+
+// 120 bpm at 48 kHz, so a sixteenth step is 6000 samples.
+const SAMPLE_RATE: f32 = 48000.0;
+const STEP: u64 = 6000;
+
+fn play(track: Track, blocks: usize, frames: usize) -> Vec<(u64, EventData)> {
+    let mut sequencer = StepSequencer::new(Pattern::new().with_track(0, track));
+    let mut transport = Transport::new(SAMPLE_RATE, 120.0);
+    transport.play();
+
+    let mut events = Vec::new();
+    for _ in 0..blocks {
+        let time = transport.time_info();
+        let mut out = EventBuffer::<64>::new();
+        sequencer.process(&time, frames, &mut out);
+        events.extend(
+            out.iter()
+                .map(|e| (time.block_start + e.timing as u64, e.data)),
+        );
+        transport.advance(frames);
+    }
+    events
+}
+
+fn on(note: u8) -> EventData {
+    EventData::NoteOn {
+        note,
+        velocity: 100,
+    }
+}
+
+fn off(note: u8) -> EventData {
+    EventData::NoteOff { note }
+}
+
+fn slide_into(note: u8) -> Track {
+    let mut track = Track::new(4, 0.25);
+    track.set_step(
+        0,
+        Step {
+            slide: true,
+            ..Step::note(60, 100)
+        },
+    );
+    track.set_step(1, Step::note(note, 100));
+    track
+}
+
+#[test]
+fn slide_into_the_same_note_ties() {
+    let events = play(slide_into(60), 2, 12000);
+    assert_eq!(events, [(0, on(60)), (STEP + STEP / 2, off(60))]);
+}
+
+#[test]
+fn slide_into_another_note_overlaps() {
+    let events = play(slide_into(62), 2, 12000);
+    assert_eq!(
+        events,
+        [
+            (0, on(60)),
+            (STEP, on(62)),
+            (STEP + 1, off(60)),
+            (STEP + STEP / 2, off(62)),
+        ]
+    );
+}
+
+#[test]
+fn stopping_during_a_tie_releases_the_note() {
+    let mut sequencer = StepSequencer::new(Pattern::new().with_track(0, slide_into(60)));
+    let mut transport = Transport::new(SAMPLE_RATE, 120.0);
+    transport.play();
+
+    let mut out = EventBuffer::<64>::new();
+    sequencer.process(&transport.time_info(), 4000, &mut out);
+    transport.advance(4000);
+    transport.stop();
+    sequencer.process(&transport.time_info(), 4000, &mut out);
+
+    let notes: Vec<_> = out.iter().map(|e| e.data).collect();
+    assert_eq!(notes, [on(60), off(60)]);
+}
@@ -0,0 +1,59 @@
+use squid_core::{SysExAssembler, SysExChunk, SysExStatus, sysex_chunks};
+
+#[test]
+fn chunks_join_back_into_the_message() {
+    let payload: Vec<u8> = (0..20).collect();
+    let chunks: Vec<SysExChunk> = sysex_chunks(&payload).collect();
+    let statuses: Vec<SysExStatus> = chunks.iter().map(|c| c.status).collect();
+    assert_eq!(
+        statuses,
+        [
+            SysExStatus::Start,
+            SysExStatus::Continue,
+            SysExStatus::Continue,
+            SysExStatus::End
+        ]
+    );
+    assert_eq!(chunks[3].bytes(), [18, 19]);
+
+    let mut assembler = SysExAssembler::<64>::new();
+    for chunk in &chunks[..3] {
+        assert_eq!(assembler.push(chunk), None);
+    }
+    assert_eq!(assembler.push(&chunks[3]), Some(&payload[..]));
+
+    let single = SysExChunk::new(SysExStatus::Complete, &[0x7e, 0x00]);
+    assert_eq!(assembler.push(&single), Some(&[0x7e, 0x00][..]));
+}
+
+#[test]
+fn broken_messages_are_dropped() {
+    let payload: Vec<u8> = (0..20).collect();
+    let chunks: Vec<SysExChunk> = sysex_chunks(&payload).collect();
+    let mut assembler = SysExAssembler::<64>::new();
+
+    // Without its start the rest of a message means nothing.
+    assert_eq!(assembler.push(&chunks[1]), None);
+    assert_eq!(assembler.push(&chunks[3]), None);
+
+    // A new start throws away the message in progress.
+    assembler.push(&chunks[0]);
+    assembler.push(&chunks[1]);
+    let restart = SysExChunk::new(SysExStatus::Start, &[1, 2]);
+    assert_eq!(assembler.push(&restart), None);
+    let end = SysExChunk::new(SysExStatus::End, &[3]);
+    assert_eq!(assembler.push(&end), Some(&[1, 2, 3][..]));
+
+    // Too long for the capacity, so even the end yields nothing.
+    let mut small = SysExAssembler::<8>::new();
+    for chunk in &chunks {
+        assert_eq!(small.push(chunk), None);
+    }
+    let whole = SysExChunk::new(SysExStatus::Complete, &[5; 6]);
+    assert_eq!(small.push(&whole), Some(&[5; 6][..]));
+
+    // A reset drops a half received message.
+    assembler.push(&chunks[0]);
+    assembler.reset();
+    assert_eq!(assembler.push(&chunks[3]), None);
+}
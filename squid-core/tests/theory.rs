@@ -0,0 +1,105 @@
+use squid_core::{
+    Note, NoteError, PitchClass, Spelling,
+    chord::{Chord, ChordQuality, Voicing},
+};
+
+fn notes(chord: &Chord) -> Vec<u8> {
+    chord.midi_notes().to_vec()
+}
+
+#[test]
+fn chord_symbols_parse_in_the_given_octave() {
+    assert_eq!(notes(&Chord::parse("C", 4).unwrap()), [60, 64, 67]);
+    assert_eq!(
+        notes(&Chord::parse("F#m9", 3).unwrap()),
+        [54, 57, 61, 64, 68]
+    );
+    assert_eq!(notes(&Chord::parse("Bbdim7", 2).unwrap()), [46, 49, 52, 55]);
+    assert_eq!(
+        Chord::parse("Ebø", 4).unwrap().quality(),
+        ChordQuality::HalfDiminished7
+    );
+    assert_eq!(
+        Chord::parse("Dmin7", 4).unwrap().quality(),
+        ChordQuality::Minor7
+    );
+}
+
+#[test]
+fn accidentals_crossing_the_octave_match_note_parsing() {
+    let cb: Note = "Cb4".parse().unwrap();
+    assert_eq!(cb.to_midi(), 59);
+    assert_eq!(notes(&Chord::parse("Cbmaj7", 4).unwrap()), [59, 63, 66, 70]);
+
+    let b_sharp: Note = "B#4".parse().unwrap();
+    assert_eq!(b_sharp.to_midi(), 72);
+    assert_eq!(notes(&Chord::parse("B#", 4).unwrap()), [72, 76, 79]);
+}
+
+#[test]
+fn bad_chord_symbols_are_errors() {
+    assert_eq!(Chord::parse("Cfoo", 4), Err(NoteError::UnknownChord));
+    assert_eq!(Chord::parse("H7", 4), Err(NoteError::InvalidName));
+    assert_eq!(Chord::parse("G13", 9), Err(NoteError::OutOfRange(131)));
+    assert_eq!(Chord::parse("Cb", -1), Err(NoteError::OutOfRange(-1)));
+}
+
+#[test]
+fn chords_are_spelled_with_either_accidental() {
+    let chord = Chord::parse("Dbm7", 4).unwrap();
+    assert_eq!(chord.root(), PitchClass::CSharp);
+    assert_eq!(chord.to_string(), "C#m7");
+    assert_eq!(chord.spelled(Spelling::Flat).to_string(), "Dbm7");
+    assert_eq!(
+        Chord::parse("G", 4)
+            .unwrap()
+            .spelled(Spelling::Flat)
+            .to_string(),
+        "G"
+    );
+}
+
+#[test]
+fn inversions_move_the_lowest_notes_up() {
+    let chord = Chord::parse("Cmaj7", 4).unwrap();
+    assert_eq!(notes(&chord.inversion(1).unwrap()), [64, 67, 71, 72]);
+    assert_eq!(notes(&chord.inversion(2).unwrap()), [67, 71, 72, 76]);
+    assert_eq!(notes(&chord.inversion(4).unwrap()), [72, 76, 79, 83]);
+    assert_eq!(chord.inversion(1).unwrap().root(), PitchClass::C);
+
+    let high = Chord::parse("C", 9).unwrap();
+    assert!(high.inversion(1).is_err());
+}
+
+#[test]
+fn voicings_rearrange_a_close_chord() {
+    let chord = Chord::parse("Cmaj7", 4).unwrap();
+    assert_eq!(
+        notes(&chord.voicing(Voicing::Close).unwrap()),
+        [60, 64, 67, 71]
+    );
+    assert_eq!(
+        notes(&chord.voicing(Voicing::Drop2).unwrap()),
+        [55, 60, 64, 71]
+    );
+    assert_eq!(
+        notes(&chord.voicing(Voicing::Drop3).unwrap()),
+        [52, 60, 67, 71]
+    );
+    assert_eq!(
+        notes(&chord.voicing(Voicing::Spread).unwrap()),
+        [60, 67, 76, 83]
+    );
+
+    // In a triad the third note from the top is the root.
+    let triad = Chord::parse("C", 4).unwrap();
+    assert_eq!(notes(&triad.voicing(Voicing::Drop2).unwrap()), [52, 60, 67]);
+    assert_eq!(notes(&triad.voicing(Voicing::Drop3).unwrap()), [48, 64, 67]);
+}
+
+#[test]
+fn transposing_moves_notes_and_root() {
+    let chord = Chord::parse("Am", 3).unwrap().transpose(3).unwrap();
+    assert_eq!(notes(&chord), [60, 63, 67]);
+    assert_eq!(chord.to_string(), "Cm");
+}
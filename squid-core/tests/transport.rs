@@ -0,0 +1,232 @@
+use squid_core::{
+    MAX_BPM, PPQ, SignatureChange, SongPosition, TempoPoint, TimeSignature, TimingError, Transport,
+};
+
+const EPSILON: f64 = 1e-9;
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < EPSILON,
+        "expected {expected}, got {actual}"
+    );
+}
+
+fn song(bar: u32, beat: u32, tick: u32) -> SongPosition {
+    SongPosition { bar, beat, tick }
+}
+
+#[test]
+fn block_advance_matches_ticks() {
+    let mut blocks = Transport::new(48000.0, 120.0);
+    let mut ticks = Transport::new(48000.0, 120.0);
+    blocks.play();
+    ticks.play();
+
+    for _ in 0..10 {
+        blocks.advance(512);
+    }
+    for _ in 0..5120 {
+        ticks.tick();
+    }
+
+    assert_eq!(blocks.current_sample(), 5120);
+    assert_eq!(blocks.get_position(), ticks.get_position());
+}
+
+#[test]
+fn fractional_phases_at_constant_tempo() {
+    // 120 bpm at 48 kHz is 24000 samples per beat.
+    let mut transport = Transport::new(48000.0, 120.0);
+    transport.play();
+    transport.advance(24000 * 5 + 12000);
+
+    let position = transport.get_position();
+    assert_close(position.beat, 5.5);
+    assert_close(position.phase_in_beat, 0.5);
+    assert_close(position.bar, 1.375);
+    assert_close(position.phase_in_bar, 0.375);
+    assert_eq!(position.ticks, 5 * PPQ as u64 + 480);
+    assert_eq!(position.song_position, song(2, 2, 480));
+    assert_eq!(position.song_position.to_string(), "2:2:480");
+}
+
+#[test]
+fn position_is_reported_while_stopped() {
+    let mut transport = Transport::new(48000.0, 120.0);
+    transport.seek(36000);
+
+    let position = transport.get_position();
+    assert!(!position.is_playing);
+    assert_close(position.beat, 1.5);
+    assert_eq!(position.song_position, song(1, 2, 480));
+
+    transport.advance(1000);
+    assert_eq!(transport.current_sample(), 36000);
+}
+
+#[test]
+fn time_signature_changes() {
+    // Two bars of 3/4 (6 quarter notes), then 6/8 counted in eighths.
+    let mut transport = Transport::new(1000.0, 60.0);
+    transport
+        .set_signature_map(&[
+            SignatureChange {
+                bar: 0,
+                signature: TimeSignature::new(3, 4).unwrap(),
+            },
+            SignatureChange {
+                bar: 2,
+                signature: TimeSignature::new(6, 8).unwrap(),
+            },
+        ])
+        .unwrap();
+
+    assert_close(transport.beat_at_bar(2), 6.0);
+    assert_close(transport.beat_at_bar(3), 9.0);
+    assert_close(transport.next_bar_beat(4.0), 6.0);
+    assert_close(transport.next_bar_beat(6.0), 9.0);
+
+    // Beat 4.25 is a quarter into the second beat of bar 2.
+    transport.seek_to_beat(4.25);
+    let position = transport.get_position();
+    assert_eq!(position.time_signature, TimeSignature::new(3, 4).unwrap());
+    assert_close(position.bar, 1.0 + 1.25 / 3.0);
+    assert_eq!(position.song_position, song(2, 2, 240));
+
+    // Beat 7.75 is 1.75 quarters into bar 3: the fourth eighth plus 0.25.
+    transport.seek_to_beat(7.75);
+    let position = transport.get_position();
+    assert_eq!(position.time_signature, TimeSignature::new(6, 8).unwrap());
+    assert_close(position.bar, 2.0 + 1.75 / 3.0);
+    assert_close(position.phase_in_bar, 1.75 / 3.0);
+    assert_eq!(position.song_position, song(3, 4, 240));
+    assert_close(transport.beats_per_bar(), 3.0);
+}
+
+#[test]
+fn tempo_steps() {
+    // 60 bpm for four beats, then 120 bpm, at 1 kHz.
+    let mut transport = Transport::new(1000.0, 60.0);
+    transport
+        .set_tempo_map(&[TempoPoint::new(0.0, 60.0), TempoPoint::new(4.0, 120.0)])
+        .unwrap();
+
+    assert_close(transport.sample_at_beat(4.0), 4000.0);
+    assert_close(transport.sample_at_beat(6.0), 5000.0);
+    assert_close(transport.beat_at_sample(2000.0), 2.0);
+    assert_close(transport.beat_at_sample(4500.0), 5.0);
+
+    transport.seek(4500);
+    assert_eq!(transport.bpm(), 120.0);
+    assert_close(transport.samples_per_beat(), 500.0);
+}
+
+#[test]
+fn tempo_ramps() {
+    // 60 to 120 bpm over four beats rises 15 bpm per beat, which takes
+    // 60 / 15 * ln(120 / 60) = 4 ln 2 seconds.
+    let mut transport = Transport::new(1000.0, 60.0);
+    transport
+        .set_tempo_map(&[TempoPoint::ramp(0.0, 60.0), TempoPoint::new(4.0, 120.0)])
+        .unwrap();
+
+    let ramp_end = 4000.0 * core::f64::consts::LN_2;
+    assert!((transport.sample_at_beat(4.0) - ramp_end).abs() < 1e-6);
+    assert!((transport.beat_at_sample(ramp_end) - 4.0).abs() < 1e-9);
+
+    // Halfway through the beats the tempo is 90 bpm, reached after
+    // 4 ln 1.5 seconds.
+    assert_close(transport.bpm_at_beat(2.0), 90.0);
+    let halfway = 4000.0 * libm::log(1.5);
+    assert!((transport.sample_at_beat(2.0) - halfway).abs() < 1e-6);
+
+    // After one second the tempo is 60 e^(1/4), so the beat is
+    // (60 e^(1/4) - 60) / 15.
+    let expected = (60.0 * libm::exp(0.25) - 60.0) / 15.0;
+    assert!((transport.beat_at_sample(1000.0) - expected).abs() < 1e-9);
+
+    // Constant 120 bpm after the ramp.
+    assert!((transport.sample_at_beat(6.0) - (ramp_end + 1000.0)).abs() < 1e-6);
+}
+
+#[test]
+fn tempo_changes_keep_the_musical_position() {
+    let mut transport = Transport::new(48000.0, 120.0);
+    transport.seek_to_beat(3.0);
+    assert_eq!(transport.current_sample(), 72000);
+
+    transport.set_bpm(60.0);
+    assert_eq!(transport.current_sample(), 144000);
+    assert_close(transport.get_position().beat, 3.0);
+}
+
+#[test]
+fn loops_wrap_inside_a_block() {
+    // Beats 4 to 8 are samples 96000 to 192000.
+    let mut transport = Transport::new(48000.0, 120.0);
+    transport.set_loop(4.0, 8.0).unwrap();
+    transport.seek_to_beat(7.0);
+    transport.play();
+
+    transport.advance(48000);
+    assert_eq!(transport.current_sample(), 120000);
+    assert_close(transport.get_position().beat, 5.0);
+
+    // Landing exactly on the end goes back to the start.
+    transport.seek_to_beat(7.5);
+    transport.advance(12000);
+    assert_close(transport.get_position().beat, 4.0);
+
+    // Before the loop the playhead runs into it and stays there.
+    transport.seek(0);
+    transport.advance(24000 * 9);
+    assert_close(transport.get_position().beat, 5.0);
+
+    transport.set_looping(false);
+    transport.advance(24000 * 4);
+    assert_close(transport.get_position().beat, 9.0);
+
+    assert_eq!(transport.set_loop(2.0, 2.0), Err(TimingError::InvalidLoop));
+}
+
+#[test]
+fn invalid_maps_are_rejected() {
+    let mut transport = Transport::new(48000.0, 120.0);
+
+    assert_eq!(transport.set_tempo_map(&[]), Err(TimingError::EmptyMap));
+    assert_eq!(
+        transport.set_tempo_map(&[TempoPoint::new(1.0, 120.0)]),
+        Err(TimingError::InvalidOrder)
+    );
+    assert_eq!(
+        transport.set_tempo_map(&[TempoPoint::new(0.0, 0.0)]),
+        Err(TimingError::InvalidTempo)
+    );
+    assert_eq!(TimeSignature::new(7, 6), Err(TimingError::InvalidSignature));
+    assert_eq!(transport.tempo_map(), &[TempoPoint::new(0.0, 120.0)]);
+}
+
+#[test]
+fn bad_tempos_and_signatures_are_refused() {
+    let mut transport = Transport::new(48000.0, 120.0);
+    transport.set_bpm(f32::INFINITY);
+    transport.set_bpm(f32::NAN);
+    assert_eq!(transport.bpm(), 120.0);
+
+    transport.set_bpm(1e9);
+    assert_eq!(transport.bpm(), MAX_BPM);
+    assert_eq!(
+        transport.set_tempo_map(&[TempoPoint::new(0.0, f32::INFINITY)]),
+        Err(TimingError::InvalidTempo)
+    );
+
+    let zero = TimeSignature {
+        numerator: 0,
+        denominator: 4,
+    };
+    assert_eq!(
+        transport.set_time_signature(zero),
+        Err(TimingError::InvalidSignature)
+    );
+    assert_eq!(transport.time_signature(), TimeSignature::COMMON);
+}
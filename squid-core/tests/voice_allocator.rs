@@ -0,0 +1,157 @@
+use squid_core::{
+    AudioNode, Event, EventData,
+    dsp::mod_core::adsr_mod_source::{AdsrModSource, calculate_coefficient},
+    oscillators::saw_osc::SawOsc,
+    process_context::{FixedBuf, ProcessContext},
+    synths::{poly_synth::PolySynth, voice_allocator::MAX_VOICES},
+};
+
+fn synth(sample_rate: f32) -> PolySynth<SawOsc> {
+    let mut env = AdsrModSource::new();
+    let instant = calculate_coefficient(0.0, sample_rate);
+    env.set_parameters(instant, instant, instant, 0.8);
+    PolySynth::new(SawOsc::new(), env)
+}
+
+fn render(synth: &mut PolySynth<SawOsc>, sample_rate: f32, events: &[Event]) {
+    let mut left = FixedBuf::default();
+    let mut right = FixedBuf::default();
+    let ctx = ProcessContext::new(sample_rate, events, &[]);
+    synth.process(&ctx, &mut [&mut left, &mut right]);
+}
+
+// Long enough for stolen voices to fade out and waiting notes to start.
+fn settle(synth: &mut PolySynth<SawOsc>, sample_rate: f32) {
+    for _ in 0..8 {
+        render(synth, sample_rate, &[]);
+    }
+}
+
+fn note_on(note: u8) -> Event {
+    Event::new(
+        0,
+        EventData::NoteOn {
+            note,
+            velocity: 100,
+        },
+    )
+}
+
+fn playing(synth: &PolySynth<SawOsc>, note: u8) -> usize {
+    synth.voices().iter().filter(|v| v.is_playing(note)).count()
+}
+
+#[test]
+fn unison_steals_when_voices_run_out() {
+    let unison = 16;
+    let fit = (MAX_VOICES / unison) as u8;
+
+    let mut synth = synth(48000.0);
+    synth.allocator_mut().set_unison(unison, 10.0);
+    for note in 60..60 + fit + 3 {
+        render(&mut synth, 48000.0, &[note_on(note)]);
+        settle(&mut synth, 48000.0);
+        assert_eq!(playing(&synth, note), unison, "note {note}");
+    }
+
+    // The oldest notes made room for the newest ones.
+    assert_eq!(playing(&synth, 60), 0);
+    assert_eq!(playing(&synth, 60 + fit + 2), unison);
+}
+
+#[test]
+fn unison_within_polyphony_steals_at_the_limit() {
+    let mut synth = synth(48000.0);
+    synth.allocator_mut().set_unison(4, 10.0);
+    synth.allocator_mut().set_polyphony(2);
+    for note in [60, 64, 67] {
+        render(&mut synth, 48000.0, &[note_on(note)]);
+        settle(&mut synth, 48000.0);
+    }
+
+    assert_eq!(playing(&synth, 60), 0);
+    assert_eq!(playing(&synth, 64), 4);
+    assert_eq!(playing(&synth, 67), 4);
+}
+
+#[test]
+fn same_key_on_two_channels_are_separate_notes() {
+    let mut synth = synth(48000.0);
+    render(
+        &mut synth,
+        48000.0,
+        &[note_on(60).with_channel(1), note_on(60).with_channel(2)],
+    );
+    assert_eq!(playing(&synth, 60), 2);
+
+    render(
+        &mut synth,
+        48000.0,
+        &[
+            Event::new(
+                0,
+                EventData::NotePressure {
+                    note: 60,
+                    pressure: 127,
+                },
+            )
+            .with_channel(2),
+            Event::new(0, EventData::NoteOff { note: 60 }).with_channel(1),
+        ],
+    );
+
+    let voices: Vec<_> = synth.voices().iter().filter(|v| v.is_playing(60)).collect();
+    assert_eq!(voices.len(), 1);
+    assert_eq!(voices[0].channel(), 2);
+    assert_eq!(voices[0].expression().pressure, 1.0);
+}
+
+#[test]
+fn stealing_from_full_voices_fades_the_victim() {
+    let mut synth = synth(48000.0);
+    let first = 30;
+    let next = first + MAX_VOICES as u8;
+    for note in first..next {
+        render(&mut synth, 48000.0, &[note_on(note)]);
+    }
+    assert!(synth.voices().iter().all(|v| v.is_active()));
+
+    render(&mut synth, 48000.0, &[note_on(next)]);
+    let victim = synth.voices().iter().find(|v| v.note() == first).unwrap();
+    assert!(victim.is_fading());
+    assert!(victim.level() > 0.0);
+    // The new note waits for the fade instead of cutting it.
+    assert_eq!(playing(&synth, next), 0);
+
+    settle(&mut synth, 48000.0);
+    assert_eq!(playing(&synth, first), 0);
+    assert_eq!(playing(&synth, next), 1);
+    assert_eq!(
+        synth.voices().iter().filter(|v| v.is_active()).count(),
+        MAX_VOICES
+    );
+}
+
+#[test]
+fn releasing_a_waiting_note_drops_it() {
+    let mut synth = synth(48000.0);
+    let next = MAX_VOICES as u8;
+    for note in 0..next {
+        render(&mut synth, 48000.0, &[note_on(note)]);
+    }
+
+    render(
+        &mut synth,
+        48000.0,
+        &[
+            note_on(next),
+            Event::new(1, EventData::NoteOff { note: next }),
+        ],
+    );
+    settle(&mut synth, 48000.0);
+    assert_eq!(playing(&synth, next), 0);
+    assert_eq!(
+        synth.voices().iter().filter(|v| v.is_active()).count(),
+        MAX_VOICES - 1
+    );
+}
@@ -0,0 +1,474 @@
+use std::{f64::consts::PI, path::Path, simd::Simd};
+
+use squid_core::{
+    AudioNode, MAX_BLOCK_SIZE,
+    dsp::fft::RealFft,
+    effects::Effect,
+    process_context::{FixedBuf, ProcessContext},
+};
+
+use crate::{
+    error::{Result, SquidError},
+    formats::wav::Wav,
+};
+
+// The head partition matches the engine block, so the convolution adds no latency.
+const HEAD_FFT: usize = MAX_BLOCK_SIZE * 2;
+const TAIL_FFT: usize = 2048;
+const TAIL_PARTITION: usize = TAIL_FFT / 2;
+
+const RESAMPLE_TAPS: f64 = 16.0;
+const TRIM_FADE_MS: f32 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IrLayout {
+    // One response used for both channels.
+    Mono,
+    // Left response for the left channel, right response for the right one.
+    Stereo,
+    // Four responses in LL, LR, RL, RR order, each input feeds both outputs.
+    TrueStereo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Partitioning {
+    // Every partition has the size of an engine block.
+    Uniform,
+    // Block sized partitions for the start of the response, larger ones for
+    // the tail. Much cheaper for long reverbs.
+    NonUniform,
+}
+
+// Uniformly partitioned overlap-save convolution with a frequency domain delay
+// line. Consumes and produces `FFT / 2` samples per call.
+struct Upols<const FFT: usize> {
+    fft: RealFft<FFT>,
+    count: usize,
+    filter_re: Vec<f32>,
+    filter_im: Vec<f32>,
+    // Partitions made of silence (pre-delay, trimmed tails) are skipped.
+    active: Vec<bool>,
+
+    fdl_re: Vec<f32>,
+    fdl_im: Vec<f32>,
+    fdl_pos: usize,
+
+    input: Vec<f32>,
+    frame: Vec<f32>,
+    acc_re: Vec<f32>,
+    acc_im: Vec<f32>,
+}
+
+impl<const FFT: usize> Upols<FFT> {
+    const PARTITION: usize = FFT / 2;
+    const BINS: usize = FFT / 2 + 1;
+
+    fn new(ir: &[f32]) -> Self {
+        let fft = RealFft::<FFT>::new();
+        let count = ir.len().div_ceil(Self::PARTITION).max(1);
+
+        let mut filter_re = vec![0.0; count * Self::BINS];
+        let mut filter_im = vec![0.0; count * Self::BINS];
+        let mut active = vec![false; count];
+
+        let mut frame = vec![0.0; FFT];
+        let mut re = vec![0.0; FFT];
+        let mut im = vec![0.0; FFT];
+
+        for (k, part) in ir.chunks(Self::PARTITION).enumerate() {
+            frame.fill(0.0);
+            frame[..part.len()].copy_from_slice(part);
+            fft.forward(&frame, &mut re, &mut im);
+
+            let bins = k * Self::BINS..(k + 1) * Self::BINS;
+            filter_re[bins.clone()].copy_from_slice(&re[..Self::BINS]);
+            filter_im[bins].copy_from_slice(&im[..Self::BINS]);
+            active[k] = part.iter().any(|&x| x != 0.0);
+        }
+
+        Self {
+            fft,
+            count,
+            filter_re,
+            filter_im,
+            active,
+
+            fdl_re: vec![0.0; count * Self::BINS],
+            fdl_im: vec![0.0; count * Self::BINS],
+            fdl_pos: 0,
+
+            input: vec![0.0; FFT],
+            frame,
+            acc_re: re,
+            acc_im: im,
+        }
+    }
+
+    fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        let part = Self::PARTITION;
+        let bins = Self::BINS;
+
+        self.input.copy_within(part.., 0);
+        self.input[part..].copy_from_slice(&input[..part]);
+
+        let slot = self.fdl_pos * bins..(self.fdl_pos + 1) * bins;
+        self.fft
+            .forward(&self.input, &mut self.acc_re, &mut self.acc_im);
+        self.fdl_re[slot.clone()].copy_from_slice(&self.acc_re[..bins]);
+        self.fdl_im[slot].copy_from_slice(&self.acc_im[..bins]);
+
+        self.acc_re.fill(0.0);
+        self.acc_im.fill(0.0);
+
+        for k in (0..self.count).filter(|&k| self.active[k]) {
+            let delayed = (self.fdl_pos + self.count - k) % self.count;
+            let x_re = &self.fdl_re[delayed * bins..(delayed + 1) * bins];
+            let x_im = &self.fdl_im[delayed * bins..(delayed + 1) * bins];
+            let h_re = &self.filter_re[k * bins..(k + 1) * bins];
+            let h_im = &self.filter_im[k * bins..(k + 1) * bins];
+
+            let acc = self.acc_re.iter_mut().zip(self.acc_im.iter_mut());
+            let x = x_re.iter().zip(x_im);
+            let h = h_re.iter().zip(h_im);
+            for ((ar, ai), ((xr, xi), (hr, hi))) in acc.zip(x.zip(h)) {
+                *ar += xr * hr - xi * hi;
+                *ai += xr * hi + xi * hr;
+            }
+        }
+
+        self.fdl_pos = (self.fdl_pos + 1) % self.count;
+
+        self.fft
+            .inverse(&mut self.acc_re, &mut self.acc_im, &mut self.frame);
+        output[..part].copy_from_slice(&self.frame[part..]);
+    }
+
+    fn clear(&mut self) {
+        self.fdl_re.fill(0.0);
+        self.fdl_im.fill(0.0);
+        self.fdl_pos = 0;
+        self.input.fill(0.0);
+    }
+}
+
+// Tail partitions are only computed once a whole partition of input has been
+// collected, their result is played back during the following partition. The
+// tail response starts `TAIL_PARTITION` samples in, which hides that delay.
+struct TailStage {
+    upols: Upols<TAIL_FFT>,
+    pending: Vec<f32>,
+    ready: Vec<f32>,
+    pos: usize,
+}
+
+impl TailStage {
+    fn new(ir: &[f32]) -> Self {
+        Self {
+            upols: Upols::new(ir),
+            pending: vec![0.0; TAIL_PARTITION],
+            ready: vec![0.0; TAIL_PARTITION],
+            pos: 0,
+        }
+    }
+
+    fn process_add(&mut self, input: &[f32], output: &mut [f32]) {
+        for (&x, y) in input.iter().zip(output.iter_mut()) {
+            self.pending[self.pos] = x;
+            *y += self.ready[self.pos];
+
+            self.pos += 1;
+            if self.pos == TAIL_PARTITION {
+                self.pos = 0;
+                self.upols.process(&self.pending, &mut self.ready);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.upols.clear();
+        self.pending.fill(0.0);
+        self.ready.fill(0.0);
+        self.pos = 0;
+    }
+}
+
+// One input to one output path.
+struct Convolver {
+    head: Upols<HEAD_FFT>,
+    tail: Option<TailStage>,
+}
+
+impl Convolver {
+    fn new(ir: &[f32], partitioning: Partitioning) -> Self {
+        match partitioning {
+            Partitioning::NonUniform if ir.len() > TAIL_PARTITION => Self {
+                head: Upols::new(&ir[..TAIL_PARTITION]),
+                tail: Some(TailStage::new(&ir[TAIL_PARTITION..])),
+            },
+            _ => Self {
+                head: Upols::new(ir),
+                tail: None,
+            },
+        }
+    }
+
+    fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        self.head.process(input, output);
+        if let Some(tail) = &mut self.tail {
+            tail.process_add(input, output);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.head.clear();
+        if let Some(tail) = &mut self.tail {
+            tail.clear();
+        }
+    }
+}
+
+// Convolution reverb / cabinet simulator. Responses are kept at their original
+// rate and rebuilt for the engine rate whenever a setting that changes them is
+// applied, so the setters allocate and belong outside the audio thread.
+pub struct ConvolverFx {
+    ir: Vec<Vec<f32>>,
+    ir_rate: f32,
+    layout: IrLayout,
+
+    pre_delay_ms: f32,
+    trim_start_ms: f32,
+    trim_length_ms: Option<f32>,
+    stretch: f32,
+    partitioning: Partitioning,
+    mix: f32,
+    sample_rate: f32,
+
+    paths: Vec<Convolver>,
+    wet: [FixedBuf; 2],
+    scratch: FixedBuf,
+}
+
+impl ConvolverFx {
+    // Accepts 1 (mono), 2 (stereo) or 4 (true stereo) channel responses.
+    pub fn from_wav(wav: &Wav) -> Result<Self> {
+        let channels = wav.spec.num_channels as usize;
+        let layout = match channels {
+            1 => IrLayout::Mono,
+            2 => IrLayout::Stereo,
+            4 => IrLayout::TrueStereo,
+            _ => {
+                return Err(SquidError::UnsupportedFormat(format!(
+                    "Impulse responses need 1, 2 or 4 channels, found {}",
+                    channels
+                )));
+            }
+        };
+        if wav.samples.len() < channels {
+            return Err(SquidError::InvalidData("Impulse response is empty".into()));
+        }
+
+        let ir = (0..channels)
+            .map(|ch| {
+                wav.samples
+                    .iter()
+                    .skip(ch)
+                    .step_by(channels)
+                    .copied()
+                    .collect()
+            })
+            .collect();
+
+        let mut fx = Self {
+            ir,
+            ir_rate: wav.spec.sample_rate as f32,
+            layout,
+
+            pre_delay_ms: 0.0,
+            trim_start_ms: 0.0,
+            trim_length_ms: None,
+            stretch: 1.0,
+            partitioning: Partitioning::NonUniform,
+            mix: 1.0,
+            sample_rate: 44100.0,
+
+            paths: Vec::new(),
+            wet: [FixedBuf::default(), FixedBuf::default()],
+            scratch: FixedBuf::default(),
+        };
+        fx.rebuild();
+        Ok(fx)
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_wav(&Wav::from_path(path)?)
+    }
+
+    pub fn layout(&self) -> IrLayout {
+        self.layout
+    }
+
+    pub fn set_pre_delay_ms(&mut self, ms: f32) {
+        self.pre_delay_ms = ms.clamp(0.0, 500.0);
+        self.rebuild();
+    }
+
+    // Cuts the response to start `start_ms` in and last at most `length_ms`.
+    pub fn set_trim(&mut self, start_ms: f32, length_ms: Option<f32>) {
+        self.trim_start_ms = start_ms.max(0.0);
+        self.trim_length_ms = length_ms.map(|l| l.max(1.0));
+        self.rebuild();
+    }
+
+    // Time scales the response, > 1 makes the space sound bigger.
+    pub fn set_stretch(&mut self, stretch: f32) {
+        self.stretch = stretch.clamp(0.25, 4.0);
+        self.rebuild();
+    }
+
+    pub fn set_partitioning(&mut self, partitioning: Partitioning) {
+        self.partitioning = partitioning;
+        self.rebuild();
+    }
+
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = mix.clamp(0.0, 1.0);
+    }
+
+    fn rebuild(&mut self) {
+        let ms_to_ir = |ms: f32| (ms * 0.001 * self.ir_rate) as usize;
+        let ratio = self.sample_rate as f64 / self.ir_rate as f64 * self.stretch as f64;
+        let pre_delay = (self.pre_delay_ms * 0.001 * self.sample_rate) as usize;
+
+        let responses: Vec<Vec<f32>> = self
+            .ir
+            .iter()
+            .map(|channel| {
+                let start = ms_to_ir(self.trim_start_ms).min(channel.len() - 1);
+                let end = match self.trim_length_ms {
+                    Some(length) => (start + ms_to_ir(length)).min(channel.len()),
+                    None => channel.len(),
+                };
+
+                let mut trimmed = channel[start..end].to_vec();
+                if end < channel.len() {
+                    fade_out(&mut trimmed, ms_to_ir(TRIM_FADE_MS));
+                }
+
+                let mut response = vec![0.0; pre_delay];
+                response.extend(resample(&trimmed, ratio));
+                response
+            })
+            .collect();
+
+        // Loudest path ends up at unity energy, so switching responses keeps
+        // roughly the same level.
+        let peak_energy = responses
+            .iter()
+            .map(|r| r.iter().map(|x| x * x).sum::<f32>())
+            .fold(0.0, f32::max);
+        let gain = if peak_energy > 0.0 {
+            1.0 / peak_energy.sqrt()
+        } else {
+            1.0
+        };
+
+        // A mono response still needs separate state for each channel.
+        let paths = match self.layout {
+            IrLayout::Mono => 2,
+            _ => responses.len(),
+        };
+        self.paths = (0..paths)
+            .map(|p| {
+                let response = &responses[p % responses.len()];
+                let scaled: Vec<f32> = response.iter().map(|x| x * gain).collect();
+                Convolver::new(&scaled, self.partitioning)
+            })
+            .collect();
+    }
+}
+
+impl AudioNode for ConvolverFx {
+    fn process(&mut self, ctx: &ProcessContext, outputs: &mut [&mut FixedBuf]) {
+        let inputs = [ctx.inputs[0], ctx.inputs[1]];
+        let [wet_l, wet_r] = &mut self.wet;
+
+        match self.layout {
+            IrLayout::Mono | IrLayout::Stereo => {
+                self.paths[0].process(inputs[0], wet_l);
+                self.paths[1].process(inputs[1], wet_r);
+            }
+            IrLayout::TrueStereo => {
+                let scratch = &mut self.scratch;
+
+                self.paths[0].process(inputs[0], wet_l);
+                self.paths[2].process(inputs[1], scratch);
+                wet_l.zip_map_in_place(scratch, |a, b| a + b);
+
+                self.paths[1].process(inputs[0], wet_r);
+                self.paths[3].process(inputs[1], scratch);
+                wet_r.zip_map_in_place(scratch, |a, b| a + b);
+            }
+        }
+
+        let wet = Simd::splat(self.mix);
+        let dry = Simd::splat(1.0 - self.mix);
+        for ((output, input), wet_buf) in outputs.iter_mut().zip(inputs).zip(&self.wet) {
+            output.zip_map_from(input, wet_buf, |d, w| d * dry + w * wet);
+        }
+    }
+
+    // Only a new rate changes the responses. At the same rate the state is
+    // cleared in place, so resetting from the audio thread doesn't allocate.
+    fn reset(&mut self, sample_rate: f32) {
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            self.rebuild();
+        } else {
+            for path in &mut self.paths {
+                path.clear();
+            }
+        }
+    }
+}
+
+impl Effect for ConvolverFx {}
+
+// Windowed sinc resampling, low passed when the rate goes down.
+fn resample(input: &[f32], ratio: f64) -> Vec<f32> {
+    if (ratio - 1.0).abs() < 1e-9 {
+        return input.to_vec();
+    }
+
+    let out_len = ((input.len() as f64 * ratio).ceil() as usize).max(1);
+    let cutoff = ratio.min(1.0);
+    let radius = RESAMPLE_TAPS / cutoff;
+
+    (0..out_len)
+        .map(|n| {
+            let t = n as f64 / ratio;
+            let from = ((t - radius).ceil() as isize).max(0);
+            let to = ((t + radius).floor() as isize).min(input.len() as isize - 1);
+
+            (from..=to)
+                .map(|i| {
+                    let x = t - i as f64;
+                    let sinc = if x.abs() < 1e-9 {
+                        1.0
+                    } else {
+                        (PI * x * cutoff).sin() / (PI * x * cutoff)
+                    };
+                    let window = 0.5 + 0.5 * (PI * x / radius).cos();
+                    input[i as usize] as f64 * sinc * cutoff * window
+                })
+                .sum::<f64>() as f32
+        })
+        .collect()
+}
+
+fn fade_out(samples: &mut [f32], len: usize) {
+    let len = len.min(samples.len());
+    let start = samples.len() - len;
+    for (i, x) in samples[start..].iter_mut().enumerate() {
+        *x *= 1.0 - (i + 1) as f32 / len as f32;
+    }
+}
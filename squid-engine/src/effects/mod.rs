@@ -0,0 +1 @@
+pub mod convolver_fx;
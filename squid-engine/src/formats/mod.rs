@@ -1,3 +1,4 @@
+pub mod scala;
 pub mod wav;
 
 // use crate::buffer::AudioBufferML;
@@ -0,0 +1,155 @@
+use crate::error::{Result, SquidError};
+use squid_core::{KeyboardMap, MAX_TUNING_DEGREES, Tuning, TuningError};
+use std::fs;
+use std::path::Path;
+
+// A scale as read from a Scala .scl file. Degree 0 (1/1) is implied, the last
+// pitch is the period the scale repeats at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scl {
+    pub description: String,
+    pub cents: Vec<f64>,
+}
+
+impl Scl {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = data_lines(text);
+
+        let description = lines
+            .next()
+            .ok_or_else(|| SquidError::InvalidHeader("Missing scale description".into()))?
+            .trim()
+            .to_string();
+
+        let count = lines
+            .next()
+            .and_then(|l| first_token(l).parse::<usize>().ok())
+            .ok_or_else(|| SquidError::InvalidHeader("Missing or invalid note count".into()))?;
+        if count == 0 || count > MAX_TUNING_DEGREES {
+            return Err(SquidError::UnsupportedFormat(format!(
+                "Scale with {} notes",
+                count
+            )));
+        }
+
+        let cents = lines
+            .take(count)
+            .map(|l| parse_pitch(first_token(l)))
+            .collect::<Result<Vec<_>>>()?;
+        if cents.len() != count {
+            return Err(SquidError::InvalidData(format!(
+                "Expected {} pitches, found {}",
+                count,
+                cents.len()
+            )));
+        }
+
+        Ok(Self { description, cents })
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn to_tuning(&self, keyboard: Option<KeyboardMap>) -> Result<Tuning> {
+        Tuning::with_keyboard_map(&self.cents, keyboard.unwrap_or_default()).map_err(tuning_error)
+    }
+}
+
+// Reads a Scala .kbm keyboard mapping.
+pub fn parse_kbm(text: &str) -> Result<KeyboardMap> {
+    let mut lines = data_lines(text).map(first_token);
+    let mut field = |name: &str| {
+        lines
+            .next()
+            .ok_or_else(|| SquidError::InvalidHeader(format!("Missing {}", name)))
+    };
+
+    let size = parse_field::<usize>(field("map size")?, "map size")?;
+    let first_note = parse_field::<u8>(field("first note")?, "first note")?;
+    let last_note = parse_field::<u8>(field("last note")?, "last note")?;
+    let middle_note = parse_field::<u8>(field("middle note")?, "middle note")?;
+    let reference_note = parse_field::<u8>(field("reference note")?, "reference note")?;
+    let reference_freq = parse_field::<f32>(field("reference frequency")?, "reference frequency")?;
+    let octave_degree = parse_field::<u16>(field("octave degree")?, "octave degree")?;
+
+    if size > MAX_TUNING_DEGREES {
+        return Err(SquidError::UnsupportedFormat(format!(
+            "Keyboard map with {} keys",
+            size
+        )));
+    }
+
+    // Missing entries at the end count as unmapped.
+    let mut mapping = vec![None; size];
+    for (entry, token) in mapping.iter_mut().zip(lines) {
+        *entry = match token {
+            "x" | "X" => None,
+            _ => Some(parse_field::<u16>(token, "mapping entry")?),
+        };
+    }
+
+    let mut keyboard = KeyboardMap::linear(middle_note, reference_note, reference_freq);
+    keyboard.first_note = first_note;
+    keyboard.last_note = last_note;
+    keyboard.octave_degree = octave_degree;
+    if size > 0 {
+        keyboard = keyboard
+            .with_mapping(&mapping, octave_degree)
+            .map_err(tuning_error)?;
+    }
+    Ok(keyboard)
+}
+
+pub fn kbm_from_path<P: AsRef<Path>>(path: P) -> Result<KeyboardMap> {
+    parse_kbm(&fs::read_to_string(path)?)
+}
+
+// Loads a .scl file with an optional .kbm, 12 keys per period with A4 as the
+// reference otherwise.
+pub fn load_tuning<P: AsRef<Path>>(scl: P, kbm: Option<P>) -> Result<Tuning> {
+    let keyboard = kbm.map(kbm_from_path).transpose()?;
+    Scl::from_path(scl)?.to_tuning(keyboard)
+}
+
+fn data_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(|l| l.trim_end_matches('\r'))
+        .filter(|l| !l.starts_with('!'))
+}
+
+fn first_token(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or("")
+}
+
+fn parse_field<T: std::str::FromStr>(token: &str, name: &str) -> Result<T> {
+    token
+        .parse()
+        .map_err(|_| SquidError::InvalidData(format!("Invalid {}: '{}'", name, token)))
+}
+
+// Pitches with a period are cents, anything else is a ratio or an integer.
+fn parse_pitch(token: &str) -> Result<f64> {
+    if token.contains('.') {
+        return parse_field::<f64>(token, "pitch");
+    }
+
+    let (num, den) = match token.split_once('/') {
+        Some((num, den)) => (
+            parse_field::<f64>(num, "pitch")?,
+            parse_field::<f64>(den, "pitch")?,
+        ),
+        None => (parse_field::<f64>(token, "pitch")?, 1.0),
+    };
+    if num <= 0.0 || den <= 0.0 {
+        return Err(SquidError::InvalidData(format!(
+            "Invalid ratio: '{}'",
+            token
+        )));
+    }
+    Ok(1200.0 * (num / den).log2())
+}
+
+fn tuning_error(err: TuningError) -> SquidError {
+    SquidError::InvalidData(format!("Invalid tuning: {:?}", err))
+}
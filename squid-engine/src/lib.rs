@@ -3,19 +3,27 @@
 pub mod audio_bridge;
 pub mod audio_graph;
 pub mod buffer_adapter;
+pub mod effects;
 pub mod error;
 pub mod filler;
 pub mod formats;
 pub mod live_playback;
 pub mod oscillators;
+pub mod spectrum_analyzer;
 pub mod stream_context;
+pub mod time_stretch;
+pub mod transport_control;
 
 pub use audio_bridge::*;
 pub use audio_graph::*;
 pub use buffer_adapter::*;
+pub use effects::*;
 pub use error::*;
 pub use filler::*;
 pub use formats::*;
 pub use live_playback::*;
 pub use oscillators::*;
+pub use spectrum_analyzer::*;
 pub use stream_context::*;
+pub use time_stretch::*;
+pub use transport_control::*;
@@ -0,0 +1,221 @@
+use squid_core::{
+    FixedSpscQueue,
+    dsp::{fft::RealFft, window::Window},
+};
+
+pub const SPECTRUM_FFT_SIZE: usize = 2048;
+pub const SPECTRUM_TAP_CAPACITY: usize = 8192;
+
+const BINS: usize = SPECTRUM_FFT_SIZE / 2 + 1;
+const SILENCE_DB: f32 = -120.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectrumBin {
+    pub freq: f32,
+    pub level_db: f32,
+    pub peak_db: f32,
+}
+
+// Runs on the GUI side. The audio thread only pushes raw samples into the
+// `StreamContext::spectrum` tap, which gets drained here before each frame.
+pub struct SpectrumAnalyzer {
+    fft: RealFft<SPECTRUM_FFT_SIZE>,
+    window: Vec<f32>,
+    window_gain: f32,
+    sample_rate: f32,
+    hop: usize,
+
+    history: Vec<f32>,
+    write: usize,
+    pending: usize,
+
+    averaging: f32,
+    peak_hold: f32,
+    peak_decay: f32,
+
+    power: Vec<f32>,
+    peaks: Vec<f32>,
+    peak_age: Vec<f32>,
+
+    frame: Vec<f32>,
+    re: Vec<f32>,
+    im: Vec<f32>,
+}
+
+impl SpectrumAnalyzer {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut window = vec![0.0; SPECTRUM_FFT_SIZE];
+        Window::BlackmanHarris.fill(&mut window);
+        let window_gain = 2.0 / window.iter().sum::<f32>();
+
+        Self {
+            fft: RealFft::new(),
+            window,
+            window_gain,
+            sample_rate,
+            hop: SPECTRUM_FFT_SIZE / 4,
+
+            history: vec![0.0; SPECTRUM_FFT_SIZE],
+            write: 0,
+            pending: 0,
+
+            averaging: 0.7,
+            peak_hold: 1.0,
+            peak_decay: 12.0,
+
+            power: vec![0.0; BINS],
+            peaks: vec![SILENCE_DB; BINS],
+            peak_age: vec![0.0; BINS],
+
+            frame: vec![0.0; SPECTRUM_FFT_SIZE],
+            re: vec![0.0; SPECTRUM_FFT_SIZE],
+            im: vec![0.0; SPECTRUM_FFT_SIZE],
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    // 0 shows every frame as is, values close to 1 average over many frames.
+    pub fn set_averaging(&mut self, amount: f32) {
+        self.averaging = amount.clamp(0.0, 0.99);
+    }
+
+    // Seconds a peak stays in place before it starts to fall.
+    pub fn set_peak_hold(&mut self, secs: f32) {
+        self.peak_hold = secs.max(0.0);
+    }
+
+    pub fn set_peak_decay(&mut self, db_per_sec: f32) {
+        self.peak_decay = db_per_sec.max(0.0);
+    }
+
+    pub fn push_samples(&mut self, samples: &[f32]) {
+        for &x in samples {
+            self.history[self.write] = x;
+            self.write = (self.write + 1) % SPECTRUM_FFT_SIZE;
+
+            self.pending += 1;
+            if self.pending == self.hop {
+                self.pending = 0;
+                self.analyze();
+            }
+        }
+    }
+
+    pub fn drain<const N: usize>(&mut self, tap: &FixedSpscQueue<f32, N>) {
+        let mut chunk = [0.0; 256];
+        loop {
+            let count = tap.pop_slice(&mut chunk);
+            if count == 0 {
+                break;
+            }
+            self.push_samples(&chunk[..count]);
+        }
+    }
+
+    pub fn bin_freq(&self, bin: usize) -> f32 {
+        bin as f32 * self.sample_rate / SPECTRUM_FFT_SIZE as f32
+    }
+
+    // Linear bins, DC to Nyquist.
+    pub fn levels_db(&self) -> impl Iterator<Item = f32> + '_ {
+        self.power.iter().map(|&p| power_to_db(p))
+    }
+
+    // Spectrum reduced to `count` bands spaced evenly on a log frequency axis.
+    // Bands narrower than an FFT bin are interpolated, wider ones take the
+    // loudest bin they cover.
+    pub fn log_bins(&self, count: usize, min_freq: f32, max_freq: f32) -> Vec<SpectrumBin> {
+        let nyquist = self.sample_rate * 0.5;
+        let max_freq = max_freq.clamp(1.0, nyquist);
+        let min_freq = min_freq.clamp(1.0, max_freq);
+        let ratio = (max_freq / min_freq).ln();
+        let bin_width = self.sample_rate / SPECTRUM_FFT_SIZE as f32;
+
+        let edge = |i: usize| min_freq * (ratio * i as f32 / count as f32).exp();
+
+        (0..count)
+            .map(|i| {
+                let (lo, hi) = (edge(i), edge(i + 1));
+                let freq = (lo * hi).sqrt();
+
+                let first = (lo / bin_width).ceil() as usize;
+                let last = ((hi / bin_width).floor() as usize).min(BINS - 1);
+
+                let (power, peak) = if first <= last {
+                    (first..=last).fold((0.0f32, SILENCE_DB), |(p, pk), b| {
+                        (p.max(self.power[b]), pk.max(self.peaks[b]))
+                    })
+                } else {
+                    let pos = (freq / bin_width).min((BINS - 2) as f32);
+                    let b = pos as usize;
+                    let t = pos - b as f32;
+                    (
+                        self.power[b] + (self.power[b + 1] - self.power[b]) * t,
+                        self.peaks[b] + (self.peaks[b + 1] - self.peaks[b]) * t,
+                    )
+                };
+
+                SpectrumBin {
+                    freq,
+                    level_db: power_to_db(power),
+                    peak_db: peak,
+                }
+            })
+            .collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.write = 0;
+        self.pending = 0;
+        self.power.fill(0.0);
+        self.peaks.fill(SILENCE_DB);
+        self.peak_age.fill(0.0);
+    }
+
+    fn analyze(&mut self) {
+        // Oldest sample first.
+        let (newer, older) = self.history.split_at(self.write);
+        for ((f, &x), &w) in self
+            .frame
+            .iter_mut()
+            .zip(older.iter().chain(newer))
+            .zip(&self.window)
+        {
+            *f = x * w;
+        }
+
+        self.fft.forward(&self.frame, &mut self.re, &mut self.im);
+
+        let frame_secs = self.hop as f32 / self.sample_rate;
+        let keep = self.averaging;
+        let gain = self.window_gain * self.window_gain;
+
+        for b in 0..BINS {
+            let p = (self.re[b] * self.re[b] + self.im[b] * self.im[b]) * gain;
+            self.power[b] = p + (self.power[b] - p) * keep;
+
+            let db = power_to_db(p);
+            if db >= self.peaks[b] {
+                self.peaks[b] = db;
+                self.peak_age[b] = 0.0;
+            } else {
+                self.peak_age[b] += frame_secs;
+                if self.peak_age[b] > self.peak_hold {
+                    self.peaks[b] = (self.peaks[b] - self.peak_decay * frame_secs).max(db);
+                }
+            }
+        }
+    }
+}
+
+fn power_to_db(power: f32) -> f32 {
+    if power > 1e-12 {
+        (10.0 * power.log10()).max(SILENCE_DB)
+    } else {
+        SILENCE_DB
+    }
+}
@@ -1,10 +1,15 @@
 use std::sync::{Arc, atomic::AtomicU8};
 
-use squid_core::{Event, FixedSpscQueue};
+use squid_core::{Event, FixedSpscQueue, MeterReadings};
+
+use crate::{SPECTRUM_TAP_CAPACITY, TransportControl};
 
 pub struct StreamContext {
     pub waveform: Arc<FixedSpscQueue<f32, 512>>,
+    pub spectrum: Arc<FixedSpscQueue<f32, SPECTRUM_TAP_CAPACITY>>,
     pub events: Arc<FixedSpscQueue<Event, 128>>,
+    pub master_meter: Arc<MeterReadings>,
+    pub transport: Arc<TransportControl>,
     pub f1: Arc<AtomicU8>,
     pub f2: Arc<AtomicU8>,
     pub f3: Arc<AtomicU8>,
@@ -14,7 +19,10 @@ impl StreamContext {
     pub fn new() -> Self {
         Self {
             waveform: Arc::new(FixedSpscQueue::new()),
+            spectrum: Arc::new(FixedSpscQueue::new()),
             events: Arc::new(FixedSpscQueue::new()),
+            master_meter: Arc::new(MeterReadings::new()),
+            transport: Arc::new(TransportControl::default()),
             f1: Arc::new(AtomicU8::new(0)),
             f2: Arc::new(AtomicU8::new(0)),
             f3: Arc::new(AtomicU8::new(0)),
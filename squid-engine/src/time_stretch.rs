@@ -0,0 +1,176 @@
+use squid_core::Transport;
+
+use crate::formats::wav::Wav;
+
+// Below this frame size WSOLA can't find a period to line up, so shorter
+// inputs are resampled instead.
+const MIN_FRAME_SIZE: usize = 64;
+
+// Offline WSOLA (waveform similarity overlap-add) time stretcher. Changes the
+// length of a `Wav` without touching its pitch.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeStretcher {
+    pub frame_size: usize,
+    pub tolerance: usize,
+}
+
+impl TimeStretcher {
+    pub fn new() -> Self {
+        Self {
+            frame_size: 1024,
+            tolerance: 256,
+        }
+    }
+
+    // `ratio` > 1 makes the result longer, < 1 shorter. Inputs shorter than
+    // two frames, like drum hits, use a frame of half their length, and ones
+    // too short for that are resampled, which changes their pitch.
+    pub fn stretch(&self, wav: &Wav, ratio: f32) -> Wav {
+        let channels = wav.spec.num_channels.max(1) as usize;
+        let in_frames = wav.samples.len() / channels;
+        let out_frames = (in_frames as f64 * ratio.max(0.01) as f64) as usize;
+
+        let mut out = Wav::new(wav.spec);
+        let n = self.frame_size.min(in_frames / 2) & !1;
+        if n < MIN_FRAME_SIZE || out_frames == 0 {
+            out.samples = resample(&wav.samples, channels, out_frames);
+            return out;
+        }
+        let tolerance = self.tolerance * n / self.frame_size.max(1);
+
+        let hop_out = n / 2;
+        let hop_in = hop_out as f64 / ratio.max(0.01) as f64;
+        let window: Vec<f32> = (0..n)
+            .map(|i| {
+                let x = std::f32::consts::PI * i as f32 / n as f32;
+                x.sin() * x.sin()
+            })
+            .collect();
+
+        let mono: Vec<f32> = wav
+            .samples
+            .chunks_exact(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+
+        let last_start = in_frames - n;
+        let mut output = vec![0.0; (out_frames + n) * channels];
+        let mut norm = vec![0.0; out_frames + n];
+        let mut prev_start = 0usize;
+
+        let mut k = 0usize;
+        loop {
+            let out_start = k * hop_out;
+            if out_start >= out_frames {
+                break;
+            }
+
+            let nominal = ((k as f64 * hop_in) as usize).min(last_start);
+            let start = if k == 0 {
+                0
+            } else {
+                let natural = (prev_start + hop_out).min(last_start);
+                best_offset(&mono, n / 4, tolerance, natural, nominal, last_start)
+            };
+
+            for i in 0..n {
+                let w = window[i];
+                let src = (start + i) * channels;
+                let dst = (out_start + i) * channels;
+                for ch in 0..channels {
+                    output[dst + ch] += wav.samples[src + ch] * w;
+                }
+                norm[out_start + i] += w;
+            }
+
+            prev_start = start;
+            k += 1;
+        }
+
+        for (frame, &w) in output.chunks_exact_mut(channels).zip(norm.iter()) {
+            if w > 1e-3 {
+                frame.iter_mut().for_each(|s| *s /= w);
+            }
+        }
+        output.truncate(out_frames * channels);
+
+        out.samples = output;
+        out
+    }
+
+    // Stretches a loop spanning `beats` so it plays in sync at `bpm`.
+    pub fn fit_to_bpm(&self, wav: &Wav, beats: f32, bpm: f32) -> Wav {
+        let channels = wav.spec.num_channels.max(1) as usize;
+        let frames = wav.samples.len() / channels;
+        let current_secs = frames as f32 / wav.spec.sample_rate as f32;
+        let target_secs = beats * 60.0 / bpm.max(1.0);
+
+        self.stretch(wav, target_secs / current_secs)
+    }
+
+    pub fn fit_to_transport(&self, wav: &Wav, beats: f32, transport: &Transport) -> Wav {
+        self.fit_to_bpm(wav, beats, transport.bpm())
+    }
+}
+
+impl Default for TimeStretcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Searches `tolerance` frames around `nominal` for the frame whose first
+// `len` samples continue the waveform at `natural` most smoothly.
+fn best_offset(
+    mono: &[f32],
+    len: usize,
+    tolerance: usize,
+    natural: usize,
+    nominal: usize,
+    last: usize,
+) -> usize {
+    let from = nominal.saturating_sub(tolerance);
+    let to = (nominal + tolerance).min(last);
+    let reference = &mono[natural..natural + len];
+
+    let mut best = nominal;
+    let mut best_score = f32::MIN;
+    for candidate in from..=to {
+        let score: f32 = mono[candidate..candidate + len]
+            .iter()
+            .zip(reference)
+            .map(|(a, b)| a * b)
+            .sum();
+        if score > best_score {
+            best_score = score;
+            best = candidate;
+        }
+    }
+    best
+}
+
+// Linear interpolation to `out_frames` frames.
+fn resample(samples: &[f32], channels: usize, out_frames: usize) -> Vec<f32> {
+    let in_frames = samples.len() / channels;
+    if in_frames == 0 {
+        return vec![0.0; out_frames * channels];
+    }
+    let step = if out_frames > 1 {
+        (in_frames - 1) as f64 / (out_frames - 1) as f64
+    } else {
+        0.0
+    };
+
+    let mut out = Vec::with_capacity(out_frames * channels);
+    for i in 0..out_frames {
+        let pos = i as f64 * step;
+        let a = (pos as usize).min(in_frames - 1);
+        let b = (a + 1).min(in_frames - 1);
+        let t = (pos - a as f64) as f32;
+        for ch in 0..channels {
+            let (x, y) = (samples[a * channels + ch], samples[b * channels + ch]);
+            out.push(x + (y - x) * t);
+        }
+    }
+    out
+}
@@ -0,0 +1,130 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+
+use squid_core::{AtomicF32, MAX_BPM, MIN_BPM, SongPosition, TimeInfo, Transport};
+
+const NO_SEEK: u64 = u64::MAX;
+// Far enough for any song, and short of overflowing the sample position.
+const MAX_SEEK_BEAT: f64 = 1e9;
+
+// Shared between the UI and the audio thread. The UI writes requests, the
+// audio thread owns the `Transport`, applies them at the start of each block
+// and publishes where the playhead ended up.
+pub struct TransportControl {
+    playing: AtomicBool,
+    bpm: AtomicF32,
+    seek_beat: AtomicU64,
+
+    beat: AtomicU64,
+    bar: AtomicU32,
+    bar_beat: AtomicU32,
+    tick: AtomicU32,
+    current_bpm: AtomicF32,
+}
+
+impl TransportControl {
+    pub fn new(bpm: f32) -> Self {
+        let bpm = if bpm.is_finite() {
+            bpm.clamp(MIN_BPM, MAX_BPM)
+        } else {
+            120.0
+        };
+        Self {
+            playing: AtomicBool::new(false),
+            bpm: AtomicF32::new(bpm),
+            seek_beat: AtomicU64::new(NO_SEEK),
+
+            beat: AtomicU64::new(0f64.to_bits()),
+            bar: AtomicU32::new(1),
+            bar_beat: AtomicU32::new(1),
+            tick: AtomicU32::new(0),
+            current_bpm: AtomicF32::new(bpm),
+        }
+    }
+
+    pub fn play(&self) {
+        self.playing.store(true, Ordering::Relaxed);
+    }
+
+    pub fn stop(&self) {
+        self.playing.store(false, Ordering::Relaxed);
+    }
+
+    // Requests from the UI are checked here, so nothing the audio thread
+    // applies can fail. Tempos and beats that aren't finite are ignored.
+    pub fn set_bpm(&self, bpm: f32) {
+        if bpm.is_finite() {
+            self.bpm.store(bpm.clamp(MIN_BPM, MAX_BPM));
+        }
+    }
+
+    // Beats past `MAX_SEEK_BEAT` are ignored too.
+    pub fn seek_to_beat(&self, beat: f64) {
+        if beat.is_finite() && beat <= MAX_SEEK_BEAT {
+            self.seek_beat
+                .store(beat.max(0.0).to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing.load(Ordering::Relaxed)
+    }
+
+    pub fn bpm(&self) -> f32 {
+        self.current_bpm.load()
+    }
+
+    pub fn beat(&self) -> f64 {
+        f64::from_bits(self.beat.load(Ordering::Relaxed))
+    }
+
+    pub fn song_position(&self) -> SongPosition {
+        SongPosition {
+            bar: self.bar.load(Ordering::Relaxed),
+            beat: self.bar_beat.load(Ordering::Relaxed),
+            tick: self.tick.load(Ordering::Relaxed),
+        }
+    }
+
+    // Called by the audio thread once per block. Returns the time info for
+    // the block and moves the transport past it.
+    pub fn next_block(&self, transport: &mut Transport, frames: usize) -> TimeInfo {
+        if self.is_playing() != transport.is_playing() {
+            if self.is_playing() {
+                transport.play();
+            } else {
+                transport.stop();
+            }
+        }
+
+        let bpm = self.bpm.load();
+        if transport.tempo_map().len() == 1 && transport.tempo_map()[0].bpm != bpm {
+            transport.set_bpm(bpm);
+        }
+
+        let seek = self.seek_beat.swap(NO_SEEK, Ordering::Relaxed);
+        if seek != NO_SEEK {
+            transport.seek_to_beat(f64::from_bits(seek));
+        }
+
+        let time = transport.time_info();
+        transport.advance(frames);
+
+        let position = transport.get_position();
+        self.beat.store(position.beat.to_bits(), Ordering::Relaxed);
+        self.bar
+            .store(position.song_position.bar, Ordering::Relaxed);
+        self.bar_beat
+            .store(position.song_position.beat, Ordering::Relaxed);
+        self.tick
+            .store(position.song_position.tick, Ordering::Relaxed);
+        self.current_bpm.store(position.bpm);
+
+        time
+    }
+}
+
+impl Default for TransportControl {
+    fn default() -> Self {
+        Self::new(120.0)
+    }
+}
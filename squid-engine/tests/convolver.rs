@@ -0,0 +1,86 @@
+use squid_core::{
+    AudioNode, MAX_BLOCK_SIZE,
+    process_context::{FixedBuf, ProcessContext},
+};
+use squid_engine::{
+    effects::convolver_fx::{ConvolverFx, Partitioning},
+    formats::wav::{Wav, WavSpec},
+};
+
+const SAMPLE_RATE: f32 = 44100.0;
+
+// A mono response that is silent except for a unit spike at `at`.
+fn spike(at: usize, len: usize) -> ConvolverFx {
+    let mut wav = Wav::new(WavSpec::cd_mono());
+    wav.samples = vec![0.0; len];
+    wav.samples[at] = 1.0;
+    ConvolverFx::from_wav(&wav).unwrap()
+}
+
+// Feeds `input` to the left channel block by block and returns the left output.
+fn run(fx: &mut ConvolverFx, input: &[f32]) -> Vec<f32> {
+    let mut output = Vec::new();
+    for block in input.chunks(MAX_BLOCK_SIZE) {
+        let mut left_in = FixedBuf::default();
+        left_in[..block.len()].copy_from_slice(block);
+        let right_in = FixedBuf::default();
+        let inputs = [&left_in, &right_in];
+
+        let mut left = FixedBuf::default();
+        let mut right = FixedBuf::default();
+        fx.process(
+            &ProcessContext::new(SAMPLE_RATE, &[], &inputs),
+            &mut [&mut left, &mut right],
+        );
+        output.extend_from_slice(&left[..block.len()]);
+    }
+    output
+}
+
+fn impulse(len: usize) -> Vec<f32> {
+    let mut input = vec![0.0; len];
+    input[0] = 1.0;
+    input
+}
+
+fn peak(output: &[f32]) -> usize {
+    (0..output.len())
+        .max_by(|&a, &b| output[a].abs().total_cmp(&output[b].abs()))
+        .unwrap()
+}
+
+#[test]
+fn impulses_come_out_without_latency() {
+    // In the first block, past the first block, and in the tail partitions
+    // of the non-uniform layout.
+    for at in [0, 10, MAX_BLOCK_SIZE + 5, 1500, 2500] {
+        for partitioning in [Partitioning::Uniform, Partitioning::NonUniform] {
+            let mut fx = spike(at, 3000);
+            fx.set_partitioning(partitioning);
+            let output = run(&mut fx, &impulse(4096));
+
+            assert_eq!(peak(&output), at, "{partitioning:?} at {at}");
+            assert!((output[at] - 1.0).abs() < 1e-3, "{partitioning:?} at {at}");
+            let rest = output
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != at)
+                .map(|(_, x)| x.abs())
+                .fold(0.0, f32::max);
+            assert!(rest < 1e-3, "{partitioning:?} at {at}");
+        }
+    }
+}
+
+#[test]
+fn reset_clears_the_tail() {
+    for partitioning in [Partitioning::Uniform, Partitioning::NonUniform] {
+        let mut fx = spike(2500, 3000);
+        fx.set_partitioning(partitioning);
+        run(&mut fx, &impulse(1024));
+        fx.reset(SAMPLE_RATE);
+
+        let output = run(&mut fx, &[0.0; 4096]);
+        assert!(output.iter().all(|x| x.abs() < 1e-6), "{partitioning:?}");
+    }
+}
@@ -0,0 +1,43 @@
+use squid_engine::{
+    TimeStretcher,
+    formats::wav::{Wav, WavSpec},
+};
+
+fn sine(frames: usize, channels: u16) -> Wav {
+    let mut wav = Wav::new(WavSpec {
+        num_channels: channels,
+        ..WavSpec::cd_mono()
+    });
+    wav.samples = (0..frames * channels as usize)
+        .map(|i| (i as f32 / channels as f32 * 0.1).sin())
+        .collect();
+    wav
+}
+
+#[test]
+fn output_length_follows_the_ratio() {
+    let stretcher = TimeStretcher::new();
+    // Long input, a one-shot shorter than two frames, and one too short to
+    // stretch that gets resampled.
+    for frames in [44100, 1500, 100] {
+        for channels in [1, 2] {
+            let wav = sine(frames, channels);
+            for ratio in [0.5, 1.0, 1.5, 2.0] {
+                let out = stretcher.stretch(&wav, ratio);
+                let expected = (frames as f64 * ratio as f64) as usize * channels as usize;
+                assert_eq!(out.samples.len(), expected, "{frames} frames at {ratio}");
+                assert!(out.samples.iter().all(|s| s.is_finite()));
+            }
+        }
+    }
+}
+
+#[test]
+fn short_input_is_stretched_not_copied() {
+    let wav = sine(1500, 1);
+    let out = TimeStretcher::new().stretch(&wav, 2.0);
+    assert_eq!(out.samples.len(), 3000);
+    // The signal carries on through the added length.
+    let tail = &out.samples[2000..];
+    assert!(tail.iter().map(|s| s.abs()).fold(0.0, f32::max) > 0.5);
+}
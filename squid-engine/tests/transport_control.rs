@@ -0,0 +1,35 @@
+use squid_core::{MAX_BPM, MIN_BPM, Transport};
+use squid_engine::TransportControl;
+
+#[test]
+fn bad_requests_from_the_ui_are_ignored() {
+    let control = TransportControl::new(120.0);
+    let mut transport = Transport::new(48000.0, 120.0);
+    control.play();
+    control.seek_to_beat(4.0);
+    control.next_block(&mut transport, 64);
+
+    control.set_bpm(f32::INFINITY);
+    control.set_bpm(f32::NAN);
+    control.seek_to_beat(f64::INFINITY);
+    control.seek_to_beat(1e300);
+    for _ in 0..4 {
+        control.next_block(&mut transport, 64);
+    }
+
+    assert_eq!(transport.bpm(), 120.0);
+    assert_eq!(transport.current_sample(), 96000 + 5 * 64);
+}
+
+#[test]
+fn tempos_are_clamped_before_reaching_the_transport() {
+    let control = TransportControl::new(120.0);
+    let mut transport = Transport::new(48000.0, 120.0);
+    control.set_bpm(0.0);
+    control.next_block(&mut transport, 64);
+    assert_eq!(transport.bpm(), MIN_BPM);
+
+    control.set_bpm(1e9);
+    control.next_block(&mut transport, 64);
+    assert_eq!(control.bpm(), MAX_BPM);
+}